@@ -0,0 +1,991 @@
+use super::config;
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+use super::policy::{self, QrAlgorithm};
+use super::reduction::{dot, norm};
+use super::stats::{Operation, Probe};
+use super::workspace::Workspace;
+
+impl Matrix {
+	/* QR decomposition via Householder reflections: A = Q * R with
+	- Q: MxM orthogonal
+	- R: MxN upper triangular
+	Each reflection H = I - 2 * v * v^T zeroes the entries below the diagonal
+	of one column of R. Q accumulates the reflections: Q = H1 * H2 * ... * Hk.
+	*/
+	pub fn qr(&self) -> Result<(Matrix, Matrix), MathMatrixError> {
+		let (rows, cols) = self.get_size();
+		return self.qr_with(policy::global_policy().qr_for(rows, cols));
+	}
+
+	pub fn qr_with(&self, algorithm: QrAlgorithm) -> Result<(Matrix, Matrix), MathMatrixError> {
+		match algorithm {
+			QrAlgorithm::GramSchmidt => self.qr_gram_schmidt(),
+			QrAlgorithm::Householder => self.qr_with_workspace(&mut Workspace::new()),
+		}
+	}
+
+	/* Modified Gram-Schmidt. Columns that are (numerically) dependent on the
+	previous ones add no new direction; Q is then completed to an orthogonal
+	MxM matrix with the standard basis, so the shapes match `qr_with_workspace`.
+	*/
+	fn qr_gram_schmidt(&self) -> Result<(Matrix, Matrix), MathMatrixError> {
+		let probe = Probe::start(Operation::QrDecomposition);
+		let (rows, cols) = self.get_size();
+		let data = self.get_data();
+		let mut basis: Vec<Vec<f64>> = Vec::with_capacity(rows);
+		let mut r = Matrix::zeros(rows, cols)?;
+		let add_direction = |basis: &mut Vec<Vec<f64>>, mut v: Vec<f64>| -> Option<f64> {
+			let original_norm = norm(&v);
+			for q in basis.iter() {
+				let projection = dot(q, &v);
+				for (elem, q_i) in v.iter_mut().zip(q.iter()) {
+					*elem -= projection * q_i;
+				}
+			}
+			let v_norm = norm(&v);
+			if basis.len() == rows || v_norm <= 1e-12 * original_norm || v_norm == 0.0 {
+				return None;
+			}
+			basis.push(v.iter().map(|elem| elem / v_norm).collect());
+			return Some(v_norm);
+		};
+		for j in 0..cols {
+			let column = data[j * rows..(j + 1) * rows].to_vec();
+			for (i, q) in basis.iter().enumerate() {
+				r.set_value(i, j, dot(q, &column))?;
+			}
+			let index = basis.len();
+			if let Some(v_norm) = add_direction(&mut basis, column) {
+				r.set_value(index, j, v_norm)?;
+			}
+		}
+		for i in 0..rows {
+			let mut unit = vec![0f64; rows];
+			unit[i] = 1.0;
+			add_direction(&mut basis, unit);
+		}
+		probe.finish((2 * rows * cols * cols) as u64, 2);
+		return Ok((Matrix::new(rows, rows, basis.concat())?, r));
+	}
+
+	// Same as `qr`, borrowing the Householder vector from `workspace`
+	pub fn qr_with_workspace(
+		&self,
+		workspace: &mut Workspace,
+	) -> Result<(Matrix, Matrix), MathMatrixError> {
+		let probe = Probe::start(Operation::QrDecomposition);
+		let (rows, cols) = self.get_size();
+		let (r, reflectors, flops) = householder_reduce(self, workspace);
+		// Q = H1 * H2 * ... * Hk * I
+		let mut q = Matrix::identity(rows, rows)?.into_vec();
+		reflectors.apply(&mut q, rows, false);
+		probe.finish((flops + 4 * rows * rows * reflectors.tau.len()) as u64, 2);
+		return Ok((Matrix::new(rows, rows, q)?, Matrix::new(rows, cols, r)?));
+	}
+
+	/* Householder QR keeping Q implicit as its reflectors, which costs
+	O(M * N) memory instead of O(M^2) for a tall MxN matrix. R is returned
+	thin (min(M, N) x N).
+	*/
+	pub fn qr_compact(&self) -> Result<CompactQr, MathMatrixError> {
+		let probe = Probe::start(Operation::QrDecomposition);
+		let (rows, cols) = self.get_size();
+		let (r, reflectors, flops) = householder_reduce(self, &mut Workspace::new());
+		let k = rows.min(cols);
+		let mut thin_r = Matrix::zeros(k, cols)?;
+		for j in 0..cols {
+			for i in 0..k.min(j + 1) {
+				thin_r.set_value(i, j, r[j * rows + i])?;
+			}
+		}
+		probe.finish(flops as u64, 2);
+		return Ok(CompactQr {
+			reflectors,
+			r: thin_r,
+		});
+	}
+
+	/* Tall-skinny QR for an MxN matrix with M >> N, returning the thin Q (MxN)
+	and R (NxN). Row blocks of `block_rows` rows (at least N) are factored
+	independently, in parallel up to `config::max_threads`, and their R factors
+	are stacked and factored once more:
+	A_i = Q_i * R_i, [R_1; ...; R_p] = Q' * R, Q = diag(Q_i) * Q'
+	*/
+	pub fn tsqr(&self, block_rows: usize) -> Result<(Matrix, Matrix), MathMatrixError> {
+		let (rows, cols) = self.get_size();
+		if block_rows < cols || rows < cols {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				format!(
+					"TSQR needs M >= N and blocks of at least N rows, got {}x{} with blocks of {}",
+					rows, cols, block_rows
+				),
+			));
+		}
+		// Row ranges; a last block shorter than N is merged into the previous one
+		let mut ranges: Vec<(usize, usize)> = (0..rows)
+			.step_by(block_rows)
+			.map(|start| (start, (start + block_rows).min(rows)))
+			.collect();
+		if ranges.len() > 1 && ranges[ranges.len() - 1].1 - ranges[ranges.len() - 1].0 < cols {
+			let (_, end) = ranges.pop().unwrap();
+			ranges.last_mut().unwrap().1 = end;
+		}
+		let data = self.get_data();
+		let blocks: Vec<Matrix> = ranges
+			.iter()
+			.map(|(start, end)| {
+				let mut block = Vec::with_capacity((end - start) * cols);
+				for j in 0..cols {
+					block.extend_from_slice(&data[j * rows + start..j * rows + end]);
+				}
+				return Matrix::from_vec_unchecked(end - start, cols, block);
+			})
+			.collect();
+		let threads = config::max_threads().min(blocks.len()).max(1);
+		let factors: Vec<Result<(Matrix, Matrix), MathMatrixError>> = if threads == 1 {
+			blocks.iter().map(thin_qr).collect()
+		} else {
+			std::thread::scope(|scope| {
+				let handles: Vec<_> = blocks
+					.chunks(blocks.len().div_ceil(threads))
+					.map(|chunk| scope.spawn(move || chunk.iter().map(thin_qr).collect::<Vec<_>>()))
+					.collect();
+				return handles
+					.into_iter()
+					.flat_map(|handle| handle.join().unwrap())
+					.collect();
+			})
+		};
+		let factors = factors.into_iter().collect::<Result<Vec<_>, _>>()?;
+		let mut stacked = Matrix::zeros(cols * factors.len(), cols)?;
+		for (b, (_, r_block)) in factors.iter().enumerate() {
+			for j in 0..cols {
+				for i in 0..cols {
+					stacked.set_value(b * cols + i, j, r_block.get_value(i, j)?)?;
+				}
+			}
+		}
+		let (q_stacked, r) = thin_qr(&stacked)?;
+		let mut q = Matrix::zeros(rows, cols)?;
+		for (b, ((q_block, _), (start, _))) in factors.iter().zip(ranges.iter()).enumerate() {
+			let mut q_part = Matrix::zeros(cols, cols)?;
+			for j in 0..cols {
+				for i in 0..cols {
+					q_part.set_value(i, j, q_stacked.get_value(b * cols + i, j)?)?;
+				}
+			}
+			let product = q_block.multiplied_by_matrix(&q_part)?;
+			let (block_rows, _) = product.get_size();
+			for j in 0..cols {
+				for i in 0..block_rows {
+					q.set_value(start + i, j, product.get_value(i, j)?)?;
+				}
+			}
+		}
+		return Ok((q, r));
+	}
+
+	/* Cholesky decomposition of a symmetric positive-definite matrix:
+	A = L * L^T with L lower triangular. Only the lower triangle of A is read.
+	*/
+	pub fn cholesky(&self) -> Result<Matrix, MathMatrixError> {
+		let (rows, cols) = self.get_size();
+		if rows != cols {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				"Cholesky decomposition allowed only for square matrices".to_owned(),
+			));
+		}
+		let probe = Probe::start(Operation::CholeskyDecomposition);
+		let size = rows;
+		let mut l = Matrix::zeros(size, size)?;
+		for j in 0..size {
+			let mut diagonal = self.get_value(j, j)?;
+			for k in 0..j {
+				diagonal -= l.get_value(j, k)?.powi(2);
+			}
+			if diagonal <= 0.0 || diagonal.is_nan() {
+				return Err(MathMatrixError::new(
+					NotPositiveDefinite,
+					format!("Non-positive pivot {} at row {}", diagonal, j),
+				));
+			}
+			let l_jj = diagonal.sqrt();
+			l.set_value(j, j, l_jj)?;
+			for i in (j + 1)..size {
+				let mut elem = self.get_value(i, j)?;
+				for k in 0..j {
+					elem -= l.get_value(i, k)? * l.get_value(j, k)?;
+				}
+				l.set_value(i, j, elem / l_jj)?;
+			}
+		}
+		probe.finish((size * size * size / 3) as u64, 1);
+		return Ok(l);
+	}
+
+	// `cholesky` kept as a factorization that can be updated in place
+	pub fn cholesky_decomposition(&self) -> Result<CholeskyDecomposition, MathMatrixError> {
+		let l = self.cholesky()?;
+		return Ok(CholeskyDecomposition {
+			size: self.get_size().0,
+			l: l.into_vec(),
+		});
+	}
+
+	/* LDL^T decomposition of a symmetric matrix: A = L * D * L^T with L unit
+	lower triangular and D diagonal. Unlike Cholesky, D may have negative
+	entries, so symmetric indefinite matrices such as saddle-point systems
+	work too. There is no pivoting, so a zero pivot fails. Only the lower
+	triangle of A is read.
+	*/
+	pub fn ldlt(&self) -> Result<(Matrix, Matrix), MathMatrixError> {
+		let (rows, cols) = self.get_size();
+		if rows != cols {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				"LDL^T decomposition allowed only for square matrices".to_owned(),
+			));
+		}
+		let size = rows;
+		let mut l = Matrix::identity(size, size)?;
+		let mut d = Matrix::zeros(size, size)?;
+		for j in 0..size {
+			let mut d_jj = self.get_value(j, j)?;
+			for k in 0..j {
+				d_jj -= l.get_value(j, k)?.powi(2) * d.get_value(k, k)?;
+			}
+			if d_jj == 0.0 || d_jj.is_nan() {
+				return Err(MathMatrixError::new(
+					FailedToDecompose,
+					format!("Zero pivot at row {}", j),
+				));
+			}
+			d.set_value(j, j, d_jj)?;
+			for i in (j + 1)..size {
+				let mut elem = self.get_value(i, j)?;
+				for k in 0..j {
+					elem -= l.get_value(i, k)? * l.get_value(j, k)? * d.get_value(k, k)?;
+				}
+				l.set_value(i, j, elem / d_jj)?;
+			}
+		}
+		return Ok((l, d));
+	}
+
+	/* Least-squares solution of min ||A * X - B|| (Frobenius norm), returned
+	with the norm of the residual A * X - B. Full column rank systems are
+	solved with Householder QR. Rank-deficient and underdetermined systems
+	fall back to the pseudo-inverse, which gives the minimum-norm solution.
+	*/
+	pub fn lstsq(&self, b: &Matrix) -> Result<(Matrix, f64), MathMatrixError> {
+		let (rows, cols) = self.get_size();
+		let (b_rows, b_cols) = b.get_size();
+		if b_rows != rows {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Least squares requires A: MxN and B: MxK".to_owned(),
+			));
+		}
+		let mut x = None;
+		if rows >= cols {
+			let qr = self.qr_compact()?;
+			let r = qr.get_r();
+			let largest = (0..cols).fold(0f64, |acc, i| acc.max(r.get_value(i, i).unwrap().abs()));
+			let tolerance = rows as f64 * f64::EPSILON * largest;
+			if largest > 0.0 && (0..cols).all(|i| r.get_value(i, i).unwrap().abs() > tolerance) {
+				// R * X = (Q^T * B), using the first N rows only. The reflectors
+				// are applied to B directly, Q is never formed.
+				let qt_b = qr.q_t_mul(b)?;
+				let mut solution = Matrix::zeros(cols, b_cols)?;
+				for k in 0..b_cols {
+					for i in (0..cols).rev() {
+						let mut elem = qt_b.get_value(i, k)?;
+						for j in (i + 1)..cols {
+							elem -= r.get_value(i, j)? * solution.get_value(j, k)?;
+						}
+						solution.set_value(i, k, elem / r.get_value(i, i)?)?;
+					}
+				}
+				x = Some(solution);
+			}
+		}
+		let x = match x {
+			Some(x) => x,
+			None => {
+				if rows >= cols {
+					numerical_warning!(
+						"Least squares matrix is rank deficient: using the pseudo-inverse"
+					);
+				}
+				self.pinv(None)?.matrix.multiplied_by_matrix(b)?
+			}
+		};
+		let residual = (self.multiplied_by_matrix(&x)? - b.clone())?;
+		return Ok((x, norm(&residual.get_data())));
+	}
+
+	/* Reduced row echelon form by Gauss-Jordan elimination with partial
+	pivoting, returned with the pivot columns in increasing order. Entries
+	with magnitude up to max(M, N) * eps * max|a_ij| count as zero, so the
+	number of pivots is the numerical rank.
+	*/
+	pub fn rref(&self) -> Result<(Matrix, Vec<usize>), MathMatrixError> {
+		let (rows, cols) = self.get_size();
+		let mut reduced = self.clone();
+		let largest = self.get_data().iter().fold(0f64, |acc, x| acc.max(x.abs()));
+		let tolerance = rows.max(cols) as f64 * f64::EPSILON * largest;
+		let mut pivots = Vec::new();
+		for j in 0..cols {
+			let row = pivots.len();
+			if row == rows {
+				break;
+			}
+			let mut pivot_row = row;
+			for i in row..rows {
+				if reduced.get_value(i, j)?.abs() > reduced.get_value(pivot_row, j)?.abs() {
+					pivot_row = i;
+				}
+			}
+			let pivot = reduced.get_value(pivot_row, j)?;
+			if pivot.abs() <= tolerance {
+				for i in row..rows {
+					reduced.set_value(i, j, 0.0)?;
+				}
+				continue;
+			}
+			for k in 0..cols {
+				let elem = reduced.get_value(pivot_row, k)?;
+				reduced.set_value(pivot_row, k, reduced.get_value(row, k)?)?;
+				reduced.set_value(row, k, elem / pivot)?;
+			}
+			for i in 0..rows {
+				let factor = reduced.get_value(i, j)?;
+				if i == row || factor == 0.0 {
+					continue;
+				}
+				for k in 0..cols {
+					let elem = reduced.get_value(i, k)? - factor * reduced.get_value(row, k)?;
+					reduced.set_value(i, k, elem)?;
+				}
+				// Exact zero rather than rounding noise below and above the pivot
+				reduced.set_value(i, j, 0.0)?;
+			}
+			pivots.push(j);
+		}
+		return Ok((reduced, pivots));
+	}
+
+	/* Solves A * X = B by reducing the augmented matrix [A | B] to RREF. Any
+	consistent system is accepted: when A is rank deficient the free variables
+	(the non-pivot columns of A) are set to zero. A pivot in the B part means
+	the system has no solution.
+	*/
+	pub fn solve_augmented(&self, b: &Matrix) -> Result<Matrix, MathMatrixError> {
+		let (rows, cols) = self.get_size();
+		let (b_rows, b_cols) = b.get_size();
+		if b_rows != rows {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Augmented solve requires A: MxN and B: MxK".to_owned(),
+			));
+		}
+		let mut data = self.get_data();
+		data.extend(b.get_data());
+		let (reduced, pivots) = Matrix::new(rows, cols + b_cols, data)?.rref()?;
+		if pivots.last().is_some_and(|j| *j >= cols) {
+			return Err(MathMatrixError::new(
+				FailedToDecompose,
+				"System is inconsistent".to_owned(),
+			));
+		}
+		let mut x = Matrix::zeros(cols, b_cols)?;
+		for (row, j) in pivots.iter().enumerate() {
+			for k in 0..b_cols {
+				x.set_value(*j, k, reduced.get_value(row, cols + k)?)?;
+			}
+		}
+		return Ok(x);
+	}
+}
+
+// Solution of A * x = b with its response to perturbations dA and db
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sensitivity {
+	pub solution: Matrix,
+	// First-order change of x: dx = A^-1 * (db - dA * x)
+	pub derivative: Matrix,
+	// 2-norm condition number of A
+	pub condition_number: f64,
+	/* Bound on ||x' - x|| / ||x|| for the solution x' of the perturbed system
+	(A + dA) * x' = b + db, in 2-norms: k * (eA + eb) / (1 - k * eA) with
+	k the condition number, eA = ||dA|| / ||A|| and eb = ||db|| / ||b||.
+	Infinite when k * eA >= 1, since A + dA may then be singular.
+	*/
+	pub relative_error_bound: f64,
+}
+
+/* Sensitivity of the solution of A * x = b for A: NxN and b: NxK to the
+perturbations dA: NxN and db: NxK, e.g. the measurement uncertainty of the
+inputs. x + dx is the first-order estimate of the perturbed solution.
+*/
+pub fn solve_sensitivity(
+	a: &Matrix,
+	b: &Matrix,
+	da: &Matrix,
+	db: &Matrix,
+) -> Result<Sensitivity, MathMatrixError> {
+	if da.get_size() != a.get_size() || db.get_size() != b.get_size() {
+		return Err(MathMatrixError::new(
+			SizeMismatch,
+			"Perturbations must have the same size as A and b".to_owned(),
+		));
+	}
+	let solution = a.solve(b)?;
+	let change = (db.clone() - da.multiplied_by_matrix(&solution)?)?;
+	let derivative = a.solve(&change)?;
+	let condition_number = a.condition_number()?;
+	let spectral_norm = |mat: &Matrix| -> Result<f64, MathMatrixError> {
+		let (_, s, _) = mat.svd()?;
+		return s.get_value(0, 0);
+	};
+	let a_error = spectral_norm(da)? / spectral_norm(a)?;
+	let b_norm = spectral_norm(b)?;
+	let b_error = if b_norm == 0.0 {
+		0.0
+	} else {
+		spectral_norm(db)? / b_norm
+	};
+	let relative_error_bound = if condition_number * a_error < 1.0 {
+		condition_number * (a_error + b_error) / (1.0 - condition_number * a_error)
+	} else {
+		f64::INFINITY
+	};
+	return Ok(Sensitivity {
+		solution,
+		derivative,
+		condition_number,
+		relative_error_bound,
+	});
+}
+
+// QR factorization with Q = H1 * H2 * ... * Hk stored as Householder reflectors
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompactQr {
+	reflectors: Reflectors,
+	r: Matrix,
+}
+
+impl CompactQr {
+	// Thin upper triangular R, min(M, N) x N
+	pub fn get_r(&self) -> &Matrix {
+		return &self.r;
+	}
+
+	// Q * X for X with M rows
+	pub fn q_mul(&self, x: &Matrix) -> Result<Matrix, MathMatrixError> {
+		return self.apply(x, false);
+	}
+
+	// Q^T * X for X with M rows
+	pub fn q_t_mul(&self, x: &Matrix) -> Result<Matrix, MathMatrixError> {
+		return self.apply(x, true);
+	}
+
+	// Full MxM Q, as returned by `qr`
+	pub fn to_q(&self) -> Result<Matrix, MathMatrixError> {
+		let rows = self.reflectors.rows;
+		return self.q_mul(&Matrix::identity(rows, rows)?);
+	}
+
+	fn apply(&self, x: &Matrix, transpose: bool) -> Result<Matrix, MathMatrixError> {
+		let (rows, cols) = x.get_size();
+		if rows != self.reflectors.rows {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				format!("Expected {} rows, got {}", self.reflectors.rows, rows),
+			));
+		}
+		let mut data = x.get_data();
+		self.reflectors.apply(&mut data, cols, transpose);
+		return Matrix::new(rows, cols, data);
+	}
+}
+
+/* Cholesky factor A = L * L^T that follows rank-1 changes of A: `update`
+and `downdate` rewrite L for A + x * x^T and A - x * x^T in O(N^2), instead
+of the O(N^3) of factorizing again.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct CholeskyDecomposition {
+	size: usize,
+	// L, column major
+	l: Vec<f64>,
+}
+
+impl CholeskyDecomposition {
+	pub fn get_l(&self) -> Matrix {
+		return Matrix::from_vec_unchecked(self.size, self.size, self.l.clone());
+	}
+
+	// Solve A * X = B with two triangular solves
+	pub fn solve(&self, b: &Matrix) -> Result<Matrix, MathMatrixError> {
+		let l = self.get_l();
+		return l
+			.transposed()
+			.solve_upper_triangular(&l.solve_lower_triangular(b)?);
+	}
+
+	// L for A + x * x^T, x: Nx1
+	pub fn update(&mut self, x: &Matrix) -> Result<(), MathMatrixError> {
+		return self.rank_one(x, 1.0);
+	}
+
+	/* L for A - x * x^T, x: Nx1. Fails with NotPositiveDefinite, leaving the
+	factor unchanged, if A - x * x^T is not positive definite.
+	*/
+	pub fn downdate(&mut self, x: &Matrix) -> Result<(), MathMatrixError> {
+		return self.rank_one(x, -1.0);
+	}
+
+	/* Sweep of plane rotations (hyperbolic ones for a downdate) that fold
+	x into L column by column
+	*/
+	fn rank_one(&mut self, x: &Matrix, sign: f64) -> Result<(), MathMatrixError> {
+		let n = self.size;
+		if x.get_size() != (n, 1) {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				format!("Expected a {}x1 vector, got {:?}", n, x.get_size()),
+			));
+		}
+		let mut x = x.get_data();
+		let mut l = self.l.clone();
+		for k in 0..n {
+			let l_kk = l[k * n + k];
+			let squared = l_kk * l_kk + sign * x[k] * x[k];
+			if squared <= 0.0 || squared.is_nan() {
+				return Err(MathMatrixError::new(
+					NotPositiveDefinite,
+					format!("Non-positive pivot {} at row {}", squared, k),
+				));
+			}
+			let r = squared.sqrt();
+			let (c, s) = (r / l_kk, x[k] / l_kk);
+			l[k * n + k] = r;
+			for i in (k + 1)..n {
+				l[k * n + i] = (l[k * n + i] + sign * s * x[i]) / c;
+				x[i] = c * x[i] - s * l[k * n + i];
+			}
+		}
+		self.l = l;
+		return Ok(());
+	}
+}
+
+/* Reflectors H_k = I - 2 * v_k * v_k^T with unit v_k. Column k of `vectors`
+holds v_k in rows k.. and zeros above; a zero column is the identity (the
+column was already reduced).
+*/
+#[derive(Debug, Clone, PartialEq)]
+struct Reflectors {
+	rows: usize,
+	vectors: Vec<f64>,
+	tau: Vec<f64>,
+}
+
+impl Reflectors {
+	// X <- H1 * ... * Hk * X, or X <- Hk * ... * H1 * X when transposed
+	fn apply(&self, x: &mut [f64], cols: usize, transpose: bool) {
+		let rows = self.rows;
+		let order: Vec<usize> = if transpose {
+			(0..self.tau.len()).collect()
+		} else {
+			(0..self.tau.len()).rev().collect()
+		};
+		for k in order {
+			if self.tau[k] == 0.0 {
+				continue;
+			}
+			let v = &self.vectors[k * rows + k..(k + 1) * rows];
+			for j in 0..cols {
+				let column = &mut x[j * rows + k..(j + 1) * rows];
+				let dot: f64 = v.iter().zip(column.iter()).map(|(a, b)| a * b).sum();
+				for (elem, v_i) in column.iter_mut().zip(v.iter()) {
+					*elem -= self.tau[k] * dot * v_i;
+				}
+			}
+		}
+	}
+}
+
+// Reduce A to R with Householder reflections. Returns (R data, reflectors, flops).
+fn householder_reduce(mat: &Matrix, workspace: &mut Workspace) -> (Vec<f64>, Reflectors, usize) {
+	let (rows, cols) = mat.get_size();
+	let count = cols.min(rows - 1);
+	let mut flops = 0;
+	let mut r = mat.get_data();
+	let mut reflectors = Reflectors {
+		rows,
+		vectors: vec![0f64; rows * count],
+		tau: vec![0f64; count],
+	};
+	let mut v = workspace.take(rows);
+	for k in 0..count {
+		if !householder_vector(&r[k * rows + k..(k + 1) * rows], &mut v) {
+			continue;
+		}
+		flops += 4 * (rows - k) * (cols - k);
+		// R[k.., k..] -= 2 * v * (v^T * R[k.., k..])
+		for j in k..cols {
+			let column = &mut r[j * rows + k..(j + 1) * rows];
+			let dot: f64 = v.iter().zip(column.iter()).map(|(a, b)| a * b).sum();
+			for (elem, v_i) in column.iter_mut().zip(v.iter()) {
+				*elem -= 2.0 * dot * v_i;
+			}
+		}
+		for i in (k + 1)..rows {
+			r[k * rows + i] = 0.0;
+		}
+		reflectors.vectors[k * rows + k..(k + 1) * rows].copy_from_slice(&v);
+		reflectors.tau[k] = 2.0;
+	}
+	workspace.recycle(v);
+	return (r, reflectors, flops);
+}
+
+// Thin Q (MxN) and R (NxN) of an MxN matrix with M >= N
+fn thin_qr(mat: &Matrix) -> Result<(Matrix, Matrix), MathMatrixError> {
+	let (rows, cols) = mat.get_size();
+	let compact = mat.qr_compact()?;
+	let q = compact.q_mul(&Matrix::identity(rows, cols)?)?;
+	return Ok((q, compact.get_r().clone()));
+}
+
+// Store in v the unit vector such that (I - 2 * v * v^T) * x is a multiple of e1.
+// Returns false when x is already zero.
+fn householder_vector(x: &[f64], v: &mut Vec<f64>) -> bool {
+	let norm = x.iter().map(|elem| elem * elem).sum::<f64>().sqrt();
+	if norm == 0.0 {
+		return false;
+	}
+	let alpha = if x[0] > 0.0 { -norm } else { norm };
+	v.clear();
+	v.extend_from_slice(x);
+	v[0] -= alpha;
+	let v_norm = v.iter().map(|elem| elem * elem).sum::<f64>().sqrt();
+	if v_norm == 0.0 {
+		return false;
+	}
+	for elem in v.iter_mut() {
+		*elem /= v_norm;
+	}
+	return true;
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn assert_upper_triangular(mat: &Matrix) {
+		let (rows, cols) = mat.get_size();
+		for j in 0..cols {
+			for i in (j + 1)..rows {
+				assert_eq!(mat.get_value(i, j).unwrap(), 0.0);
+			}
+		}
+	}
+
+	#[test]
+	fn test_qr_square() {
+		let mat = Matrix::new(
+			3,
+			3,
+			vec![12.0, 6.0, -4.0, -51.0, 167.0, 24.0, 4.0, -68.0, -41.0],
+		)
+		.unwrap();
+		let (q, r) = mat.qr().unwrap();
+		assert!(q.multiplied_by_matrix(&r).unwrap().approx_eq(&mat, 1e-10));
+		assert!(q
+			.transposed()
+			.multiplied_by_matrix(&q)
+			.unwrap()
+			.approx_eq(&Matrix::identity(3, 3).unwrap(), 1e-12));
+		assert_upper_triangular(&r);
+		assert!((r.get_value(0, 0).unwrap().abs() - 14.0).abs() < 1e-12);
+	}
+
+	#[test]
+	fn test_qr_rectangular() {
+		let tall = Matrix::new(4, 2, vec![1.0, 1.0, 1.0, 1.0, 1.0, 2.0, 3.0, 4.0]).unwrap();
+		let (q, r) = tall.qr().unwrap();
+		assert_eq!(q.get_size(), (4, 4));
+		assert_eq!(r.get_size(), (4, 2));
+		assert!(q.multiplied_by_matrix(&r).unwrap().approx_eq(&tall, 1e-12));
+		assert_upper_triangular(&r);
+
+		let wide = tall.transposed();
+		let (q, r) = wide.qr().unwrap();
+		assert_eq!(q.get_size(), (2, 2));
+		assert!(q.multiplied_by_matrix(&r).unwrap().approx_eq(&wide, 1e-12));
+		assert_upper_triangular(&r);
+	}
+
+	#[test]
+	fn test_qr_with_workspace() {
+		let mut workspace = Workspace::new();
+		let mat = Matrix::new(3, 2, vec![1.0, 2.0, 2.0, 3.0, 1.0, 0.0]).unwrap();
+		let (q, r) = mat.qr_with_workspace(&mut workspace).unwrap();
+		assert!(workspace.capacity() >= 3);
+		assert_eq!((q, r), mat.qr().unwrap());
+	}
+
+	#[test]
+	fn test_cholesky() {
+		let mat = Matrix::new(
+			3,
+			3,
+			vec![4.0, 12.0, -16.0, 12.0, 37.0, -43.0, -16.0, -43.0, 98.0],
+		)
+		.unwrap();
+		let l = mat.cholesky().unwrap();
+		let expected =
+			Matrix::new(3, 3, vec![2.0, 6.0, -8.0, 0.0, 1.0, 5.0, 0.0, 0.0, 3.0]).unwrap();
+		assert!(l.approx_eq(&expected, 1e-12));
+		assert!(l
+			.multiplied_by_matrix(&l.transposed())
+			.unwrap()
+			.approx_eq(&mat, 1e-12));
+	}
+
+	#[test]
+	fn test_cholesky_not_positive_definite() {
+		let mat = Matrix::new(2, 2, vec![1.0, 2.0, 2.0, 1.0]).unwrap();
+		let err = mat.cholesky().unwrap_err();
+		assert_eq!(err.get_kind(), "NotPositiveDefinite");
+		assert!(Matrix::zeros(2, 3).unwrap().cholesky().is_err());
+	}
+
+	#[test]
+	fn test_cholesky_update_downdate() {
+		let mat = Matrix::new(
+			3,
+			3,
+			vec![4.0, 12.0, -16.0, 12.0, 37.0, -43.0, -16.0, -43.0, 98.0],
+		)
+		.unwrap();
+		let x = Matrix::new(3, 1, vec![1.0, -2.0, 0.5]).unwrap();
+		let outer = x.multiplied_by_matrix(&x.transposed()).unwrap();
+		let mut decomposition = mat.cholesky_decomposition().unwrap();
+		decomposition.update(&x).unwrap();
+		let updated = (mat.clone() + outer.clone()).unwrap();
+		assert!(decomposition
+			.get_l()
+			.approx_eq(&updated.cholesky().unwrap(), 1e-12));
+		decomposition.downdate(&x).unwrap();
+		assert!(decomposition
+			.get_l()
+			.approx_eq(&mat.cholesky().unwrap(), 1e-12));
+		let b = Matrix::new(3, 1, vec![1.0, 2.0, 3.0]).unwrap();
+		assert!(mat
+			.multiplied_by_matrix(&decomposition.solve(&b).unwrap())
+			.unwrap()
+			.approx_eq(&b, 1e-10));
+		// a_11 = 4, so A - 9 * e1 * e1^T is indefinite; the factor is kept
+		let before = decomposition.clone();
+		let large = Matrix::new(3, 1, vec![3.0, 0.0, 0.0]).unwrap();
+		let err = decomposition.downdate(&large).unwrap_err();
+		assert_eq!(err.get_kind(), "NotPositiveDefinite");
+		assert_eq!(decomposition, before);
+		assert!(decomposition.update(&b.transposed()).is_err());
+	}
+
+	#[test]
+	fn test_qr_gram_schmidt() {
+		let tall = Matrix::new(
+			4,
+			3,
+			vec![1.0, 1.0, 1.0, 1.0, 1.0, 2.0, 3.0, 4.0, 2.0, 3.0, 4.0, 5.0],
+		)
+		.unwrap();
+		// The third column is the sum of the first two
+		let (q, r) = tall.qr_with(QrAlgorithm::GramSchmidt).unwrap();
+		assert_eq!(q.get_size(), (4, 4));
+		assert_eq!(r.get_size(), (4, 3));
+		assert!(q.multiplied_by_matrix(&r).unwrap().approx_eq(&tall, 1e-12));
+		assert!(q
+			.transposed()
+			.multiplied_by_matrix(&q)
+			.unwrap()
+			.approx_eq(&Matrix::identity(4, 4).unwrap(), 1e-12));
+		assert_upper_triangular(&r);
+	}
+
+	#[test]
+	fn test_lstsq() {
+		// Fit y = 1 + 2t through noisy points
+		let t = [0.0, 1.0, 2.0, 3.0];
+		let y = [1.1, 2.9, 5.2, 6.8];
+		let mut data = vec![1.0; 4];
+		data.extend_from_slice(&t);
+		let a = Matrix::new(4, 2, data).unwrap();
+		let b = Matrix::new(4, 1, y.to_vec()).unwrap();
+		let (x, residual) = a.lstsq(&b).unwrap();
+		// Normal equations A^T * A * x = A^T * b
+		let at = a.transposed();
+		let expected = at
+			.multiplied_by_matrix(&a)
+			.unwrap()
+			.solve(&at.multiplied_by_matrix(&b).unwrap())
+			.unwrap();
+		assert!(x.approx_eq(&expected, 1e-12));
+		let fitted = a.multiplied_by_matrix(&x).unwrap().get_data();
+		let expected_residual = fitted
+			.iter()
+			.zip(y.iter())
+			.map(|(f, y)| (f - y) * (f - y))
+			.sum::<f64>()
+			.sqrt();
+		assert!((residual - expected_residual).abs() < 1e-12);
+		// Consistent system: zero residual
+		let exact = Matrix::new(4, 1, vec![1.0, 3.0, 5.0, 7.0]).unwrap();
+		assert!(a.lstsq(&exact).unwrap().1 < 1e-12);
+		assert!(a.lstsq(&Matrix::zeros(3, 1).unwrap()).is_err());
+	}
+
+	#[test]
+	fn test_lstsq_rank_deficient() {
+		// Duplicate columns: minimum-norm solution splits the weight evenly
+		let a = Matrix::new(3, 2, vec![1.0, 2.0, 3.0, 1.0, 2.0, 3.0]).unwrap();
+		let b = Matrix::new(3, 1, vec![2.0, 4.0, 6.0]).unwrap();
+		let (x, residual) = a.lstsq(&b).unwrap();
+		assert!(x.approx_eq(&Matrix::new(2, 1, vec![1.0, 1.0]).unwrap(), 1e-12));
+		assert!(residual < 1e-12);
+		// Underdetermined: x + y = 2
+		let wide = Matrix::new(1, 2, vec![1.0, 1.0]).unwrap();
+		let (x, _) = wide.lstsq(&Matrix::new(1, 1, vec![2.0]).unwrap()).unwrap();
+		assert!(x.approx_eq(&Matrix::new(2, 1, vec![1.0, 1.0]).unwrap(), 1e-12));
+	}
+
+	#[test]
+	fn test_ldlt() {
+		// Saddle-point system: Cholesky fails, LDL^T works
+		let mat = Matrix::new(3, 3, vec![2.0, 1.0, 1.0, 1.0, 3.0, 1.0, 1.0, 1.0, 0.0]).unwrap();
+		assert!(mat.cholesky().is_err());
+		let (l, d) = mat.ldlt().unwrap();
+		assert_eq!(l.get_value(0, 0).unwrap(), 1.0);
+		assert_eq!(l.get_value(0, 2).unwrap(), 0.0);
+		assert!((d.get_value(2, 2).unwrap() + 0.6).abs() < 1e-12);
+		let product = l
+			.multiplied_by_matrix(&d)
+			.unwrap()
+			.multiplied_by_matrix(&l.transposed())
+			.unwrap();
+		assert!(product.approx_eq(&mat, 1e-12));
+		let zero_pivot = Matrix::new(2, 2, vec![0.0, 1.0, 1.0, 0.0]).unwrap();
+		assert_eq!(
+			zero_pivot.ldlt().unwrap_err().get_kind(),
+			"FailedToDecompose"
+		);
+	}
+
+	#[test]
+	fn test_qr_compact() {
+		let mat = Matrix::new(
+			5,
+			2,
+			vec![1.0, 2.0, 0.0, -1.0, 3.0, 2.0, 0.0, 1.0, 1.0, -2.0],
+		)
+		.unwrap();
+		let compact = mat.qr_compact().unwrap();
+		let (q, r) = mat.qr().unwrap();
+		assert!(compact.to_q().unwrap().approx_eq(&q, 1e-12));
+		assert_eq!(compact.get_r().get_size(), (2, 2));
+		for j in 0..2 {
+			for i in 0..2 {
+				let expected = r.get_value(i, j).unwrap();
+				assert!((compact.get_r().get_value(i, j).unwrap() - expected).abs() < 1e-12);
+			}
+		}
+		let x = Matrix::new(5, 1, vec![1.0, -1.0, 2.0, 0.5, 3.0]).unwrap();
+		let qx = compact.q_mul(&x).unwrap();
+		assert!(qx.approx_eq(&q.multiplied_by_matrix(&x).unwrap(), 1e-12));
+		assert!(compact.q_t_mul(&qx).unwrap().approx_eq(&x, 1e-12));
+		assert!(compact.q_mul(&Matrix::zeros(2, 1).unwrap()).is_err());
+	}
+
+	#[test]
+	fn test_tsqr() {
+		let rows = 103;
+		let data = (0..rows * 3)
+			.map(|i| ((i * 29 % 13) as f64 - 6.0) / 4.0)
+			.collect();
+		let mat = Matrix::new(rows, 3, data).unwrap();
+		for threads in [1, 4] {
+			let (q, r) = crate::config::with_max_threads(threads, || mat.tsqr(10).unwrap());
+			assert_eq!(q.get_size(), (rows, 3));
+			assert_eq!(r.get_size(), (3, 3));
+			assert_upper_triangular(&r);
+			let identity = Matrix::identity(3, 3).unwrap();
+			assert!(q
+				.transposed()
+				.multiplied_by_matrix(&q)
+				.unwrap()
+				.approx_eq(&identity, 1e-12));
+			assert!(q.multiplied_by_matrix(&r).unwrap().approx_eq(&mat, 1e-12));
+		}
+		assert!(mat.tsqr(2).is_err());
+	}
+
+	#[test]
+	fn test_rref() {
+		// Second row is twice the first
+		let mat = Matrix::new(3, 3, vec![1.0, 2.0, 1.0, 2.0, 4.0, 0.0, 3.0, 6.0, 1.0]).unwrap();
+		let (reduced, pivots) = mat.rref().unwrap();
+		assert_eq!(pivots, vec![0, 1]);
+		let expected =
+			Matrix::new(3, 3, vec![1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0]).unwrap();
+		assert!(reduced.approx_eq(&expected, 1e-12));
+
+		// Consistent: x + 2y + 3z = 6, x + z = 2 -> free z = 0 gives (2, 2, 0)
+		let b = Matrix::new(3, 1, vec![6.0, 12.0, 2.0]).unwrap();
+		let x = mat.solve_augmented(&b).unwrap();
+		assert!(x.approx_eq(&Matrix::new(3, 1, vec![2.0, 2.0, 0.0]).unwrap(), 1e-12));
+		assert!(mat.multiplied_by_matrix(&x).unwrap().approx_eq(&b, 1e-12));
+		// Inconsistent: the second row no longer matches the first
+		let b = Matrix::new(3, 1, vec![6.0, 11.0, 2.0]).unwrap();
+		assert!(mat.solve_augmented(&b).is_err());
+	}
+
+	#[test]
+	fn test_solve_sensitivity() {
+		let a = Matrix::new(2, 2, vec![4.0, 2.0, 1.0, 3.0]).unwrap();
+		let b = Matrix::new(2, 1, vec![1.0, 2.0]).unwrap();
+		let da = Matrix::new(2, 2, vec![1e-6, 0.0, -2e-6, 1e-6]).unwrap();
+		let db = Matrix::new(2, 1, vec![1e-6, -1e-6]).unwrap();
+		let sensitivity = solve_sensitivity(&a, &b, &da, &db).unwrap();
+		let perturbed = (a.clone() + da.clone())
+			.unwrap()
+			.solve(&(b.clone() + db.clone()).unwrap())
+			.unwrap();
+		let estimate = (sensitivity.solution.clone() + sensitivity.derivative.clone()).unwrap();
+		// First order: the estimate is off by O(perturbation^2)
+		assert!(estimate.approx_eq(&perturbed, 1e-10));
+		let error = (perturbed - sensitivity.solution.clone())
+			.unwrap()
+			.get_data();
+		let x = sensitivity.solution.get_data();
+		let relative_error = error.iter().map(|v| v * v).sum::<f64>().sqrt()
+			/ x.iter().map(|v| v * v).sum::<f64>().sqrt();
+		assert!(relative_error <= sensitivity.relative_error_bound);
+		assert!(sensitivity.relative_error_bound < 1e-4);
+
+		let huge = a.multiplied_by_scalar(-1.0);
+		let sensitivity = solve_sensitivity(&a, &b, &huge, &db).unwrap();
+		assert_eq!(sensitivity.relative_error_bound, f64::INFINITY);
+		assert!(solve_sensitivity(&a, &b, &db, &db).is_err());
+	}
+}
@@ -0,0 +1,246 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, Weak};
+
+// Size of the chunks buffers are carved from, in f64 (512 KiB)
+const CHUNK_LEN: usize = 1 << 16;
+
+/* Bump allocator for matrices created with `Matrix::zeros_in` and
+`Matrix::new_in`. Buffers are carved one after the other out of large
+chunks, so an allocation is a pointer bump, and `reset` releases every
+allocation made so far in one go by rewinding to the start of the first
+chunk. A matrix still alive at the reset keeps its own chunk alive, which is
+freed with the last such matrix, so a reset is always safe. Chunks nobody
+uses any more are kept for the allocations after the reset, so per-frame
+code stops allocating once the arena has warmed up. Clones of an Arena
+share the same chunks, and matrices may outlive their arena.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct Arena {
+	chunks: Arc<Mutex<Chunks>>,
+}
+
+impl Arena {
+	pub fn new() -> Self {
+		return Self::default();
+	}
+
+	/* Release every allocation at once. The memory of matrices that are
+	still alive is not reused; it is freed once the last of them is dropped.
+	*/
+	pub fn reset(&self) {
+		let mut chunks = self.lock();
+		let used = std::mem::take(&mut chunks.used);
+		// A count of 1 means no buffer refers to the chunk; buffers are only
+		// created under the lock, so it cannot go up again
+		let (idle, busy): (Vec<_>, Vec<_>) = used
+			.into_iter()
+			.partition(|chunk| Arc::strong_count(chunk) == 1);
+		drop(busy);
+		chunks.idle.extend(idle);
+		chunks.offset = 0;
+	}
+
+	// Number of f64 held in the arena's chunks, used or not
+	pub fn capacity(&self) -> usize {
+		let chunks = self.lock();
+		return chunks
+			.used
+			.iter()
+			.chain(chunks.idle.iter())
+			.map(|chunk| chunk.len)
+			.sum();
+	}
+
+	pub(crate) fn take(&self, len: usize) -> ArenaBuffer {
+		let mut chunks = self.lock();
+		let (chunk, offset) = chunks.bump(len);
+		return ArenaBuffer {
+			chunk,
+			offset,
+			len,
+			arena: Arc::downgrade(&self.chunks),
+		};
+	}
+
+	fn lock(&self) -> std::sync::MutexGuard<'_, Chunks> {
+		return self.chunks.lock().unwrap_or_else(|err| err.into_inner());
+	}
+}
+
+#[derive(Debug, Default)]
+struct Chunks {
+	// Chunks handed out since the last reset; the last one is being filled
+	used: Vec<Arc<Chunk>>,
+	// Chunks released by a reset and free for reuse
+	idle: Vec<Arc<Chunk>>,
+	// First free element of the last used chunk
+	offset: usize,
+}
+
+impl Chunks {
+	// Zeroed range of `len` elements, as its chunk and offset
+	fn bump(&mut self, len: usize) -> (Arc<Chunk>, usize) {
+		let fits = match self.used.last() {
+			Some(chunk) => chunk.len - self.offset >= len,
+			None => false,
+		};
+		if !fits {
+			let index = self.idle.iter().position(|chunk| chunk.len >= len);
+			let chunk = match index {
+				Some(index) => self.idle.swap_remove(index),
+				None => Arc::new(Chunk::zeros(len.max(CHUNK_LEN))),
+			};
+			self.used.push(chunk);
+			self.offset = 0;
+		}
+		let chunk = self.used.last().unwrap().clone();
+		let offset = self.offset;
+		self.offset += len;
+		// Reused chunks hold the data of the matrices before the reset
+		unsafe { chunk.slice_mut(offset, len) }.fill(0.0);
+		return (chunk, offset);
+	}
+}
+
+#[derive(Debug)]
+struct Chunk {
+	ptr: *mut f64,
+	len: usize,
+}
+
+// The chunk is plain memory; each ArenaBuffer owns a disjoint range of it
+unsafe impl Send for Chunk {}
+unsafe impl Sync for Chunk {}
+
+impl Chunk {
+	fn zeros(len: usize) -> Self {
+		let data = Box::into_raw(vec![0f64; len].into_boxed_slice());
+		return Self {
+			ptr: data as *mut f64,
+			len,
+		};
+	}
+
+	// Caller must hold the only reference to this range
+	#[allow(clippy::mut_from_ref)]
+	unsafe fn slice_mut(&self, offset: usize, len: usize) -> &mut [f64] {
+		return std::slice::from_raw_parts_mut(self.ptr.add(offset), len);
+	}
+}
+
+impl Drop for Chunk {
+	fn drop(&mut self) {
+		unsafe {
+			drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+				self.ptr, self.len,
+			)));
+		}
+	}
+}
+
+/* A range of an arena chunk. The arena hands every range out once and, after
+a reset, only reuses chunks no ArenaBuffer refers to, so the range is never
+aliased.
+*/
+pub(crate) struct ArenaBuffer {
+	chunk: Arc<Chunk>,
+	offset: usize,
+	len: usize,
+	arena: Weak<Mutex<Chunks>>,
+}
+
+impl ArenaBuffer {
+	// Copy of the data; the range itself is released by the arena
+	pub(crate) fn into_vec(self) -> Vec<f64> {
+		return self.to_vec();
+	}
+}
+
+impl Deref for ArenaBuffer {
+	type Target = [f64];
+
+	fn deref(&self) -> &[f64] {
+		return unsafe { std::slice::from_raw_parts(self.chunk.ptr.add(self.offset), self.len) };
+	}
+}
+
+impl DerefMut for ArenaBuffer {
+	fn deref_mut(&mut self) -> &mut [f64] {
+		return unsafe { self.chunk.slice_mut(self.offset, self.len) };
+	}
+}
+
+impl Clone for ArenaBuffer {
+	// The copy comes from the same arena, or a chunk of its own once the arena is gone
+	fn clone(&self) -> Self {
+		let mut copy = match self.arena.upgrade() {
+			Some(chunks) => Arena { chunks }.take(self.len),
+			None => ArenaBuffer {
+				chunk: Arc::new(Chunk::zeros(self.len)),
+				offset: 0,
+				len: self.len,
+				arena: Weak::new(),
+			},
+		};
+		copy.copy_from_slice(self);
+		return copy;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::matrix::Matrix;
+
+	#[test]
+	fn test_arena_bump_allocates() {
+		let arena = Arena::new();
+		let first = Matrix::new_in(&arena, 10, 10, &[2.0; 100]).unwrap();
+		let second = Matrix::zeros_in(&arena, 5, 20).unwrap();
+		let copy = first.clone();
+		// All three share the first chunk
+		assert_eq!(arena.capacity(), CHUNK_LEN);
+		assert_eq!(copy.get_data(), vec![2.0; 100]);
+		assert_eq!(second.get_data(), vec![0.0; 100]);
+		// A buffer larger than a chunk gets a chunk of its own
+		let large = Matrix::zeros_in(&arena, CHUNK_LEN, 2).unwrap();
+		assert_eq!(arena.capacity(), 3 * CHUNK_LEN);
+		assert!(Matrix::new_in(&arena, 2, 2, &[1.0]).is_err());
+		drop((first, second, copy, large));
+		// Once everything is dropped, the reset keeps both chunks for reuse
+		arena.reset();
+		assert_eq!(arena.capacity(), 3 * CHUNK_LEN);
+		let mut temporaries = Vec::new();
+		for _ in 0..1000 {
+			temporaries.push(Matrix::zeros_in(&arena, 8, 8).unwrap());
+		}
+		assert_eq!(arena.capacity(), 3 * CHUNK_LEN);
+		assert!(temporaries
+			.iter()
+			.all(|mat| mat.get_data() == vec![0.0; 64]));
+		drop(temporaries);
+		arena.reset();
+		assert_eq!(arena.capacity(), 3 * CHUNK_LEN);
+	}
+
+	#[test]
+	fn test_reset_with_live_matrices() {
+		let arena = Arena::new();
+		let alive = Matrix::new_in(&arena, 3, 3, &[1.0; 9]).unwrap();
+		arena.reset();
+		// The chunk of the live matrix leaves the arena and is not reused
+		assert_eq!(arena.capacity(), 0);
+		let next = Matrix::new_in(&arena, 3, 3, &[5.0; 9]).unwrap();
+		assert_eq!(alive.get_data(), vec![1.0; 9]);
+		assert_eq!(next.get_data(), vec![5.0; 9]);
+		assert_eq!(alive.into_vec(), vec![1.0; 9]);
+	}
+
+	#[test]
+	fn test_matrix_outlives_arena() {
+		let arena = Arena::new();
+		let mat = Matrix::zeros_in(&arena, 3, 3).unwrap();
+		drop(arena);
+		assert_eq!(mat.clone().get_data(), vec![0.0; 9]);
+	}
+}
@@ -0,0 +1,164 @@
+use super::matrix::Matrix;
+
+/* Direction of a reduction. `Axis::Rows` gives one value per row, an Mx1
+result; `Axis::Cols` one value per column, a 1xN result.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+	Rows,
+	Cols,
+}
+
+/* Sums, means and extrema of the entries, over the whole matrix or along
+an axis. NaN entries never win a min or max comparison.
+*/
+impl Matrix {
+	pub fn sum(&self) -> f64 {
+		return self.get_data().iter().sum();
+	}
+
+	pub fn sum_axis(&self, axis: Axis) -> Matrix {
+		return self.reduce(axis, |line| line.iter().sum());
+	}
+
+	// Mx1: the sum of each row
+	pub fn sum_rows(&self) -> Matrix {
+		return self.sum_axis(Axis::Rows);
+	}
+
+	// 1xN: the sum of each column
+	pub fn sum_cols(&self) -> Matrix {
+		return self.sum_axis(Axis::Cols);
+	}
+
+	pub fn mean(&self) -> f64 {
+		let (rows, cols) = self.get_size();
+		return self.sum() / (rows * cols) as f64;
+	}
+
+	pub fn mean_axis(&self, axis: Axis) -> Matrix {
+		return self.reduce(axis, |line| line.iter().sum::<f64>() / line.len() as f64);
+	}
+
+	// Mx1: the mean of each row
+	pub fn mean_rows(&self) -> Matrix {
+		return self.mean_axis(Axis::Rows);
+	}
+
+	// 1xN: the mean of each column
+	pub fn mean_cols(&self) -> Matrix {
+		return self.mean_axis(Axis::Cols);
+	}
+
+	pub fn min(&self) -> f64 {
+		return self.get_data().iter().copied().fold(f64::NAN, f64::min);
+	}
+
+	pub fn max(&self) -> f64 {
+		return self.get_data().iter().copied().fold(f64::NAN, f64::max);
+	}
+
+	pub fn min_axis(&self, axis: Axis) -> Matrix {
+		return self.reduce(axis, |line| line.iter().copied().fold(f64::NAN, f64::min));
+	}
+
+	pub fn max_axis(&self, axis: Axis) -> Matrix {
+		return self.reduce(axis, |line| line.iter().copied().fold(f64::NAN, f64::max));
+	}
+
+	// (row, col) of the smallest entry, the first one in column-major order on ties
+	pub fn argmin(&self) -> (usize, usize) {
+		return self.position(best_index(&self.get_data(), |a, b| a < b));
+	}
+
+	// (row, col) of the largest entry, the first one in column-major order on ties
+	pub fn argmax(&self) -> (usize, usize) {
+		return self.position(best_index(&self.get_data(), |a, b| a > b));
+	}
+
+	// Column of the smallest entry of each row (Rows) or row of each column (Cols)
+	pub fn argmin_axis(&self, axis: Axis) -> Vec<usize> {
+		return self
+			.lines(axis)
+			.iter()
+			.map(|line| best_index(line, |a, b| a < b))
+			.collect();
+	}
+
+	// Column of the largest entry of each row (Rows) or row of each column (Cols)
+	pub fn argmax_axis(&self, axis: Axis) -> Vec<usize> {
+		return self
+			.lines(axis)
+			.iter()
+			.map(|line| best_index(line, |a, b| a > b))
+			.collect();
+	}
+
+	fn reduce<F: Fn(&[f64]) -> f64>(&self, axis: Axis, f: F) -> Matrix {
+		let values = self
+			.lines(axis)
+			.iter()
+			.map(|line| f(line))
+			.collect::<Vec<f64>>();
+		let length = values.len();
+		return match axis {
+			Axis::Rows => Matrix::from_vec_unchecked(length, 1, values),
+			Axis::Cols => Matrix::from_vec_unchecked(1, length, values),
+		};
+	}
+
+	// The rows or the columns, each as a contiguous vector
+	fn lines(&self, axis: Axis) -> Vec<Vec<f64>> {
+		let (rows, cols) = self.get_size();
+		let data = self.get_data();
+		return match axis {
+			Axis::Rows => (0..rows)
+				.map(|i| (0..cols).map(|j| data[j * rows + i]).collect())
+				.collect(),
+			Axis::Cols => data.chunks(rows).map(|column| column.to_vec()).collect(),
+		};
+	}
+
+	fn position(&self, index: usize) -> (usize, usize) {
+		let rows = self.get_size().0;
+		return (index % rows, index / rows);
+	}
+}
+
+// First index whose value beats every other under `better`; NaN never beats
+fn best_index<F: Fn(f64, f64) -> bool>(values: &[f64], better: F) -> usize {
+	let mut best = 0;
+	for (index, value) in values.iter().enumerate() {
+		if values[best].is_nan() || better(*value, values[best]) {
+			best = index;
+		}
+	}
+	return best;
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_axis_reductions() {
+		// [1, 5, 2; 7, -3, 7]
+		let a = Matrix::new(2, 3, vec![1.0, 7.0, 5.0, -3.0, 2.0, 7.0]).unwrap();
+		assert_eq!(a.sum(), 19.0);
+		assert_eq!(a.sum_rows().get_data(), vec![8.0, 11.0]);
+		assert_eq!(a.sum_cols().get_size(), (1, 3));
+		assert_eq!(a.mean_cols().get_data(), vec![4.0, 1.0, 4.5]);
+		assert_eq!(a.mean_rows(), a.mean_axis(Axis::Rows));
+		assert_eq!((a.min(), a.max()), (-3.0, 7.0));
+		assert_eq!(a.max_axis(Axis::Rows).get_data(), vec![5.0, 7.0]);
+		assert_eq!(a.min_axis(Axis::Cols).get_data(), vec![1.0, -3.0, 2.0]);
+		assert_eq!(a.argmin(), (1, 1));
+		// Ties go to the first in column-major order
+		assert_eq!(a.argmax(), (1, 0));
+		assert_eq!(a.argmax_axis(Axis::Rows), vec![1, 0]);
+		assert_eq!(a.argmin_axis(Axis::Cols), vec![0, 1, 0]);
+		let with_nan = Matrix::new(1, 3, vec![f64::NAN, 2.0, 1.0]).unwrap();
+		assert_eq!(with_nan.max(), 2.0);
+		assert_eq!(with_nan.argmin(), (0, 2));
+	}
+}
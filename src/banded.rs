@@ -0,0 +1,338 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+use super::operator::LinearOperator;
+
+/* Square matrix with `lower` nonzero diagonals below the main one and
+`upper` above it, in LAPACK band storage: each column keeps its
+lower + upper + 1 band entries, A[i][j] at row upper + i - j of column j.
+Memory and work grow with N * bandwidth instead of N^2.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct BandedMatrix {
+	size: usize,
+	lower: usize,
+	upper: usize,
+	data: Vec<f64>,
+}
+
+impl BandedMatrix {
+	pub fn zeros(size: usize, lower: usize, upper: usize) -> Result<Self, MathMatrixError> {
+		if size == 0 {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"Rows and columns must be lager than 0".to_owned(),
+			));
+		}
+		let lower = lower.min(size - 1);
+		let upper = upper.min(size - 1);
+		return Ok(Self {
+			size,
+			lower,
+			upper,
+			data: vec![0f64; size * (lower + upper + 1)],
+		});
+	}
+
+	// Fails if A has a nonzero entry outside the band
+	pub fn from_dense(mat: &Matrix, lower: usize, upper: usize) -> Result<Self, MathMatrixError> {
+		let (rows, cols) = mat.get_size();
+		if rows != cols {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Banded matrices must be square".to_owned(),
+			));
+		}
+		let mut banded = Self::zeros(rows, lower, upper)?;
+		for j in 0..cols {
+			for i in 0..rows {
+				let value = mat.get_value(i, j)?;
+				if banded.in_band(i, j) {
+					let index = banded.index(i, j);
+					banded.data[index] = value;
+				} else if value != 0.0 {
+					return Err(MathMatrixError::new(
+						OutOfBoundary,
+						format!("Entry ({}, {}) outside the band", i, j),
+					));
+				}
+			}
+		}
+		return Ok(banded);
+	}
+
+	pub fn to_dense(&self) -> Result<Matrix, MathMatrixError> {
+		let mut mat = Matrix::zeros(self.size, self.size)?;
+		for j in 0..self.size {
+			for i in self.band_rows(j) {
+				mat.set_value(i, j, self.data[self.index(i, j)])?;
+			}
+		}
+		return Ok(mat);
+	}
+
+	pub fn get_size(&self) -> (usize, usize) {
+		return (self.size, self.size);
+	}
+
+	// (lower, upper) bandwidths
+	pub fn get_bandwidths(&self) -> (usize, usize) {
+		return (self.lower, self.upper);
+	}
+
+	// Zero outside the band
+	pub fn get_value(&self, row: usize, col: usize) -> Result<f64, MathMatrixError> {
+		self.check_position(row, col)?;
+		if !self.in_band(row, col) {
+			return Ok(0.0);
+		}
+		return Ok(self.data[self.index(row, col)]);
+	}
+
+	pub fn set_value(&mut self, row: usize, col: usize, value: f64) -> Result<(), MathMatrixError> {
+		self.check_position(row, col)?;
+		if !self.in_band(row, col) {
+			return Err(MathMatrixError::new(
+				OutOfBoundary,
+				format!("Entry ({}, {}) outside the band", row, col),
+			));
+		}
+		let index = self.index(row, col);
+		self.data[index] = value;
+		return Ok(());
+	}
+
+	pub fn multiplied_by_matrix(&self, other: &Matrix) -> Result<Matrix, MathMatrixError> {
+		let (other_rows, other_cols) = other.get_size();
+		if self.size != other_rows {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Multiplication allowed for NxM * MxO".to_owned(),
+			));
+		}
+		let b = other.get_data();
+		let mut c = vec![0f64; self.size * other_cols];
+		for k in 0..other_cols {
+			for j in 0..self.size {
+				let b_jk = b[k * self.size + j];
+				for i in self.band_rows(j) {
+					c[k * self.size + i] += self.data[self.index(i, j)] * b_jk;
+				}
+			}
+		}
+		return Matrix::new(self.size, other_cols, c);
+	}
+
+	/* Solution of A * X = B by band LU with partial pivoting. Row swaps
+	widen the upper band of U to lower + upper, so the factorization works
+	on a copy with room for the fill. O(N * lower * (lower + upper)) per
+	factorization plus O(N * (lower + upper)) per right-hand side.
+	*/
+	pub fn solve(&self, b: &Matrix) -> Result<Matrix, MathMatrixError> {
+		let (rows, cols) = b.get_size();
+		if rows != self.size {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				format!("Expected {} rows, got {}", self.size, rows),
+			));
+		}
+		let n = self.size;
+		let upper = (self.lower + self.upper).min(n - 1);
+		let mut lu = Self::zeros(n, self.lower, upper)?;
+		for j in 0..n {
+			for i in self.band_rows(j) {
+				let index = lu.index(i, j);
+				lu.data[index] = self.data[self.index(i, j)];
+			}
+		}
+		let mut x = b.get_data();
+		for k in 0..n {
+			let last_row = (k + self.lower).min(n - 1);
+			let last_col = (k + upper).min(n - 1);
+			let mut pivot = k;
+			for i in (k + 1)..=last_row {
+				if lu.data[lu.index(i, k)].abs() > lu.data[lu.index(pivot, k)].abs() {
+					pivot = i;
+				}
+			}
+			if lu.data[lu.index(pivot, k)] == 0.0 {
+				return Err(MathMatrixError::new(
+					FailedToDecompose,
+					"Matrix is singular".to_owned(),
+				));
+			}
+			if pivot != k {
+				for j in k..=last_col {
+					let (a, b) = (lu.index(k, j), lu.index(pivot, j));
+					lu.data.swap(a, b);
+				}
+				for c in 0..cols {
+					x.swap(c * n + k, c * n + pivot);
+				}
+			}
+			let pivot_value = lu.data[lu.index(k, k)];
+			for i in (k + 1)..=last_row {
+				let factor = lu.data[lu.index(i, k)] / pivot_value;
+				if factor == 0.0 {
+					continue;
+				}
+				for j in (k + 1)..=last_col {
+					let update = factor * lu.data[lu.index(k, j)];
+					let index = lu.index(i, j);
+					lu.data[index] -= update;
+				}
+				for c in 0..cols {
+					x[c * n + i] -= factor * x[c * n + k];
+				}
+			}
+		}
+		// Back substitution with U
+		for c in 0..cols {
+			for i in (0..n).rev() {
+				let mut sum = x[c * n + i];
+				for j in (i + 1)..=(i + upper).min(n - 1) {
+					sum -= lu.data[lu.index(i, j)] * x[c * n + j];
+				}
+				x[c * n + i] = sum / lu.data[lu.index(i, i)];
+			}
+		}
+		return Matrix::new(n, cols, x);
+	}
+
+	fn in_band(&self, row: usize, col: usize) -> bool {
+		return row <= col + self.lower && col <= row + self.upper;
+	}
+
+	fn index(&self, row: usize, col: usize) -> usize {
+		return col * (self.lower + self.upper + 1) + self.upper + row - col;
+	}
+
+	// Rows of column `col` inside the band
+	fn band_rows(&self, col: usize) -> std::ops::Range<usize> {
+		return col.saturating_sub(self.upper)..(col + self.lower + 1).min(self.size);
+	}
+
+	fn check_position(&self, row: usize, col: usize) -> Result<(), MathMatrixError> {
+		if row >= self.size || col >= self.size {
+			return Err(MathMatrixError::new(
+				OutOfBoundary,
+				format!(
+					"Entry ({}, {}) outside a {}x{} matrix",
+					row, col, self.size, self.size
+				),
+			));
+		}
+		return Ok(());
+	}
+}
+
+impl LinearOperator for BandedMatrix {
+	fn get_size(&self) -> (usize, usize) {
+		return BandedMatrix::get_size(self);
+	}
+
+	fn apply(&self, v: &Matrix) -> Result<Matrix, MathMatrixError> {
+		return self.multiplied_by_matrix(v);
+	}
+}
+
+/* Thomas algorithm for the tridiagonal system
+	d0 u0
+	l0 d1 u1
+	   l1 d2 ...
+in O(N): `lower` and `upper` hold the N-1 off-diagonal entries. There is no
+pivoting, so it is meant for diagonally dominant or SPD systems such as
+discretized 1D PDEs; a zero pivot fails.
+*/
+pub fn solve_tridiagonal(
+	lower: &[f64],
+	diagonal: &[f64],
+	upper: &[f64],
+	rhs: &[f64],
+) -> Result<Vec<f64>, MathMatrixError> {
+	let n = diagonal.len();
+	if n == 0 || lower.len() + 1 != n || upper.len() + 1 != n || rhs.len() != n {
+		return Err(MathMatrixError::new(
+			SizeMismatch,
+			"Tridiagonal solve requires n diagonal, n-1 off-diagonal and n rhs entries".to_owned(),
+		));
+	}
+	// Forward sweep: c'i = ui / (di - li-1 * c'i-1), y'i = (yi - li-1 * y'i-1) / (di - li-1 * c'i-1)
+	let mut modified_upper = vec![0f64; n];
+	let mut solution = vec![0f64; n];
+	for i in 0..n {
+		let (pivot, y) = if i == 0 {
+			(diagonal[0], rhs[0])
+		} else {
+			(
+				diagonal[i] - lower[i - 1] * modified_upper[i - 1],
+				rhs[i] - lower[i - 1] * solution[i - 1],
+			)
+		};
+		if pivot == 0.0 {
+			return Err(MathMatrixError::new(
+				FailedToDecompose,
+				format!("Zero pivot at row {}", i),
+			));
+		}
+		if i + 1 < n {
+			modified_upper[i] = upper[i] / pivot;
+		}
+		solution[i] = y / pivot;
+	}
+	// Back substitution
+	for i in (0..(n - 1)).rev() {
+		solution[i] -= modified_upper[i] * solution[i + 1];
+	}
+	return Ok(solution);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_banded_matrix() {
+		// Zero diagonal entries force row swaps
+		let dense = Matrix::new(
+			5,
+			5,
+			vec![
+				0.0, 3.0, 1.0, 0.0, 0.0, 2.0, 0.0, 4.0, 1.0, 0.0, 0.0, 1.0, 0.0, 2.0, 5.0, 0.0,
+				0.0, 6.0, 1.0, 3.0, 0.0, 0.0, 0.0, 2.0, 7.0,
+			],
+		)
+		.unwrap();
+		let banded = BandedMatrix::from_dense(&dense, 2, 1).unwrap();
+		assert_eq!(banded.to_dense().unwrap(), dense);
+		assert_eq!(banded.get_value(0, 4).unwrap(), 0.0);
+		assert!(BandedMatrix::from_dense(&dense, 1, 1).is_err());
+		let b = Matrix::new(5, 2, (0..10).map(|k| k as f64 - 4.0).collect()).unwrap();
+		assert_eq!(
+			banded.multiplied_by_matrix(&b).unwrap(),
+			dense.multiplied_by_matrix(&b).unwrap()
+		);
+		let x = banded.solve(&b).unwrap();
+		assert!(dense.multiplied_by_matrix(&x).unwrap().approx_eq(&b, 1e-12));
+		let mut singular = banded.clone();
+		for j in 0..3 {
+			singular.set_value(j + 2, j, 0.0).unwrap();
+			singular.set_value(j + 1, j, 0.0).unwrap();
+			singular.set_value(j, j, 0.0).unwrap();
+		}
+		assert!(singular.solve(&b).is_err());
+		assert!(singular.set_value(4, 0, 1.0).is_err());
+	}
+
+	#[test]
+	fn test_solve_tridiagonal() {
+		// tridiag(-1, 2, -1) * [1 2 3 4]^T = [0 0 0 5]^T
+		let x =
+			solve_tridiagonal(&[-1.0; 3], &[2.0; 4], &[-1.0; 3], &[0.0, 0.0, 0.0, 5.0]).unwrap();
+		for (i, value) in x.iter().enumerate() {
+			assert!((value - (i + 1) as f64).abs() < 1e-12);
+		}
+		assert!(solve_tridiagonal(&[1.0], &[0.0, 1.0], &[1.0], &[1.0, 1.0]).is_err());
+		assert!(solve_tridiagonal(&[], &[2.0; 2], &[], &[1.0; 2]).is_err());
+	}
+}
@@ -0,0 +1,110 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+use super::view::MatrixView;
+
+/* Matrices of identical size stored back to back in one contiguous buffer.
+Matrix k starts at data[k * matrix_stride] and is itself column major.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Batch {
+	rows: usize,
+	cols: usize,
+	count: usize,
+	matrix_stride: usize,
+	data: Vec<f64>,
+}
+
+impl Batch {
+	pub fn from_matrices(matrices: &[Matrix]) -> Result<Self, MathMatrixError> {
+		if matrices.is_empty() {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"At least one matrix is required".to_owned(),
+			));
+		}
+		let (rows, cols) = matrices[0].get_size();
+		let matrix_stride = rows * cols;
+		let mut data = Vec::with_capacity(matrix_stride * matrices.len());
+		for (index, mat) in matrices.iter().enumerate() {
+			if mat.get_size() != (rows, cols) {
+				return Err(MathMatrixError::new(
+					SizeMismatch,
+					format!(
+						"Matrix {} is {}x{}, expected {}x{}",
+						index,
+						mat.get_size().0,
+						mat.get_size().1,
+						rows,
+						cols
+					),
+				));
+			}
+			data.extend(mat.get_data());
+		}
+		Ok(Self {
+			rows,
+			cols,
+			count: matrices.len(),
+			matrix_stride,
+			data,
+		})
+	}
+
+	pub fn get(&self, index: usize) -> Result<MatrixView<'_>, MathMatrixError> {
+		if index >= self.count {
+			return Err(MathMatrixError::new(
+				OutOfBoundary,
+				format!("Index {} >= {}", index, self.count),
+			));
+		}
+		let start = index * self.matrix_stride;
+		return MatrixView::new(
+			&self.data[start..start + self.matrix_stride],
+			self.rows,
+			self.cols,
+			self.rows,
+		);
+	}
+
+	pub fn len(&self) -> usize {
+		return self.count;
+	}
+
+	pub fn is_empty(&self) -> bool {
+		return self.count == 0;
+	}
+
+	// Size of each matrix in the batch
+	pub fn get_size(&self) -> (usize, usize) {
+		return (self.rows, self.cols);
+	}
+
+	// Distance in elements between the start of consecutive matrices
+	pub fn get_matrix_stride(&self) -> usize {
+		return self.matrix_stride;
+	}
+
+	pub fn as_slice(&self) -> &[f64] {
+		return &self.data;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_from_matrices() {
+		let mat1 = Matrix::new(2, 1, vec![1.0, 2.0]).unwrap();
+		let mat2 = Matrix::new(2, 1, vec![3.0, 4.0]).unwrap();
+		let batch = Batch::from_matrices(&[mat1.clone(), mat2.clone()]).unwrap();
+		assert_eq!(batch.len(), 2);
+		assert_eq!(batch.get_matrix_stride(), 2);
+		assert_eq!(batch.as_slice(), &[1.0, 2.0, 3.0, 4.0]);
+		assert_eq!(batch.get(1).unwrap().to_matrix(), mat2);
+		assert!(batch.get(2).is_err());
+		let mat3 = Matrix::new(1, 2, vec![3.0, 4.0]).unwrap();
+		assert!(Batch::from_matrices(&[mat1, mat3]).is_err());
+	}
+}
@@ -0,0 +1,311 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+
+const WORD_BITS: usize = 64;
+
+/* Matrix over GF(2), the field {0, 1} with XOR as addition and AND as
+multiplication. Entries are packed 64 to a u64, row by row (unlike the
+column-major Matrix), so adding one row to another, the step of both products
+and Gaussian elimination, is a word-wise XOR.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitMatrix {
+	rows: usize,
+	cols: usize,
+	words_per_row: usize,
+	// Bits past `cols` in the last word of a row stay zero
+	data: Vec<u64>,
+}
+
+impl BitMatrix {
+	pub fn zeros(rows: usize, cols: usize) -> Result<Self, MathMatrixError> {
+		if rows * cols == 0 {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"Rows and columns must be lager than 0".to_owned(),
+			));
+		}
+		let words_per_row = cols.div_ceil(WORD_BITS);
+		return Ok(Self {
+			rows,
+			cols,
+			words_per_row,
+			data: vec![0; rows * words_per_row],
+		});
+	}
+
+	pub fn identity(size: usize) -> Result<Self, MathMatrixError> {
+		let mut identity = Self::zeros(size, size)?;
+		for i in 0..size {
+			identity.set_value(i, i, true)?;
+		}
+		return Ok(identity);
+	}
+
+	// Row-major booleans
+	pub fn from_bools(rows: usize, cols: usize, bits: &[bool]) -> Result<Self, MathMatrixError> {
+		if rows * cols != bits.len() {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				format!(
+					"Size of data != rows * cols: {} != {}",
+					bits.len(),
+					rows * cols
+				),
+			));
+		}
+		let mut mat = Self::zeros(rows, cols)?;
+		for i in 0..rows {
+			for j in 0..cols {
+				mat.set_value(i, j, bits[i * cols + j])?;
+			}
+		}
+		return Ok(mat);
+	}
+
+	pub fn get_size(&self) -> (usize, usize) {
+		return (self.rows, self.cols);
+	}
+
+	pub fn get_value(&self, row: usize, col: usize) -> Result<bool, MathMatrixError> {
+		self.check_position(row, col)?;
+		return Ok(self.bit(row, col));
+	}
+
+	pub fn set_value(
+		&mut self,
+		row: usize,
+		col: usize,
+		value: bool,
+	) -> Result<(), MathMatrixError> {
+		self.check_position(row, col)?;
+		let word = &mut self.data[row * self.words_per_row + col / WORD_BITS];
+		let mask = 1u64 << (col % WORD_BITS);
+		if value {
+			*word |= mask;
+		} else {
+			*word &= !mask;
+		}
+		return Ok(());
+	}
+
+	// Number of ones
+	pub fn count_ones(&self) -> usize {
+		return self.data.iter().map(|w| w.count_ones() as usize).sum();
+	}
+
+	pub fn transposed(&self) -> BitMatrix {
+		let mut transposed = Self::zeros(self.cols, self.rows).unwrap();
+		for i in 0..self.rows {
+			for j in 0..self.cols {
+				if self.bit(i, j) {
+					transposed.data[j * transposed.words_per_row + i / WORD_BITS] |=
+						1u64 << (i % WORD_BITS);
+				}
+			}
+		}
+		return transposed;
+	}
+
+	// A + B, i.e. A XOR B
+	pub fn added_to(&self, other: &BitMatrix) -> Result<BitMatrix, MathMatrixError> {
+		if self.get_size() != other.get_size() {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Operation not allowed between matrices with different sizes".to_owned(),
+			));
+		}
+		let mut sum = self.clone();
+		for (a, b) in sum.data.iter_mut().zip(other.data.iter()) {
+			*a ^= b;
+		}
+		return Ok(sum);
+	}
+
+	// Row i of A * B is the XOR of the rows k of B with A[i][k] = 1
+	pub fn multiplied_by_matrix(&self, other: &BitMatrix) -> Result<BitMatrix, MathMatrixError> {
+		if self.cols != other.rows {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Multiplication allowed for NxM * MxO".to_owned(),
+			));
+		}
+		let mut product = Self::zeros(self.rows, other.cols)?;
+		let width = other.words_per_row;
+		for i in 0..self.rows {
+			for k in 0..self.cols {
+				if self.bit(i, k) {
+					let (target, source) = (i * width, k * width);
+					for w in 0..width {
+						product.data[target + w] ^= other.data[source + w];
+					}
+				}
+			}
+		}
+		return Ok(product);
+	}
+
+	// Rank over GF(2)
+	pub fn rank(&self) -> usize {
+		let mut reduced = self.clone();
+		return reduced.eliminate(None);
+	}
+
+	// Inverse over GF(2) by Gauss-Jordan elimination on [A | I]
+	pub fn inverse(&self) -> Result<BitMatrix, MathMatrixError> {
+		if self.rows != self.cols {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				"Inverse allowed only for square matrices".to_owned(),
+			));
+		}
+		let mut reduced = self.clone();
+		let mut inverse = Self::identity(self.rows)?;
+		if reduced.eliminate(Some(&mut inverse)) < self.rows {
+			return Err(MathMatrixError::new(
+				FailedToDecompose,
+				"Matrix is singular over GF(2)".to_owned(),
+			));
+		}
+		return Ok(inverse);
+	}
+
+	/* Reduced row echelon form in place, repeating every row operation on
+	`companion` when given. Returns the rank. Over GF(2) any one is a pivot
+	and elimination is an XOR, so there is no rounding and no pivoting
+	strategy to choose.
+	*/
+	fn eliminate(&mut self, mut companion: Option<&mut BitMatrix>) -> usize {
+		let mut rank = 0;
+		for col in 0..self.cols {
+			let pivot = match (rank..self.rows).find(|r| self.bit(*r, col)) {
+				Some(pivot) => pivot,
+				None => continue,
+			};
+			self.swap_rows(rank, pivot);
+			if let Some(other) = companion.as_mut() {
+				other.swap_rows(rank, pivot);
+			}
+			for r in 0..self.rows {
+				if r != rank && self.bit(r, col) {
+					self.xor_row(r, rank);
+					if let Some(other) = companion.as_mut() {
+						other.xor_row(r, rank);
+					}
+				}
+			}
+			rank += 1;
+			if rank == self.rows {
+				break;
+			}
+		}
+		return rank;
+	}
+
+	fn bit(&self, row: usize, col: usize) -> bool {
+		return (self.data[row * self.words_per_row + col / WORD_BITS] >> (col % WORD_BITS)) & 1
+			== 1;
+	}
+
+	fn swap_rows(&mut self, a: usize, b: usize) {
+		for w in 0..self.words_per_row {
+			self.data
+				.swap(a * self.words_per_row + w, b * self.words_per_row + w);
+		}
+	}
+
+	// Row `target` += row `source`
+	fn xor_row(&mut self, target: usize, source: usize) {
+		for w in 0..self.words_per_row {
+			self.data[target * self.words_per_row + w] ^=
+				self.data[source * self.words_per_row + w];
+		}
+	}
+
+	fn check_position(&self, row: usize, col: usize) -> Result<(), MathMatrixError> {
+		if row >= self.rows || col >= self.cols {
+			return Err(MathMatrixError::new(
+				OutOfBoundary,
+				format!(
+					"Entry ({}, {}) outside a {}x{} matrix",
+					row, col, self.rows, self.cols
+				),
+			));
+		}
+		return Ok(());
+	}
+}
+
+impl std::ops::Add for BitMatrix {
+	type Output = Result<BitMatrix, MathMatrixError>;
+
+	fn add(self, other: BitMatrix) -> Result<BitMatrix, MathMatrixError> {
+		return self.added_to(&other);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_bit_matrix_arithmetic() {
+		// Generator matrix of the [7, 4] Hamming code and its parity check
+		let generator = BitMatrix::from_bools(
+			4,
+			7,
+			&[
+				true, false, false, false, true, true, false, //
+				false, true, false, false, true, false, true, //
+				false, false, true, false, false, true, true, //
+				false, false, false, true, true, true, true,
+			],
+		)
+		.unwrap();
+		let parity = BitMatrix::from_bools(
+			3,
+			7,
+			&[
+				true, true, false, true, true, false, false, //
+				true, false, true, true, false, true, false, //
+				false, true, true, true, false, false, true,
+			],
+		)
+		.unwrap();
+		// Every codeword satisfies the parity checks
+		let syndromes = generator
+			.multiplied_by_matrix(&parity.transposed())
+			.unwrap();
+		assert_eq!(syndromes.count_ones(), 0);
+		assert_eq!(generator.rank(), 4);
+		let doubled = (generator.clone() + generator.clone()).unwrap();
+		assert_eq!(doubled.count_ones(), 0);
+		assert!(generator.multiplied_by_matrix(&generator).is_err());
+	}
+
+	#[test]
+	fn test_bit_matrix_inverse() {
+		// Wider than one word, so rows span two u64
+		let n = 70;
+		let mut upper = BitMatrix::identity(n).unwrap();
+		for i in 0..n {
+			for j in (i + 1)..n {
+				upper.set_value(i, j, (i * 7 + j * 3) % 5 == 0).unwrap();
+			}
+		}
+		let mut mixed = upper.transposed().multiplied_by_matrix(&upper).unwrap();
+		let inverse = mixed.inverse().unwrap();
+		assert_eq!(
+			inverse.multiplied_by_matrix(&mixed).unwrap(),
+			BitMatrix::identity(n).unwrap()
+		);
+		// Two equal rows
+		for j in 0..n {
+			let value = mixed.get_value(0, j).unwrap();
+			mixed.set_value(1, j, value).unwrap();
+		}
+		assert_eq!(mixed.rank(), n - 1);
+		assert!(mixed.inverse().is_err());
+		assert!(mixed.get_value(n, 0).is_err());
+	}
+}
@@ -0,0 +1,390 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockDiagonal {
+	blocks: Vec<Matrix>,
+}
+
+impl BlockDiagonal {
+	pub fn new(blocks: Vec<Matrix>) -> Result<Self, MathMatrixError> {
+		if blocks.is_empty() {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"At least one block is required".to_owned(),
+			));
+		}
+		for (index, block) in blocks.iter().enumerate() {
+			let (rows, cols) = block.get_size();
+			if rows != cols {
+				return Err(MathMatrixError::new(
+					FailedToInitialize,
+					format!("Block {} is not square: {}x{}", index, rows, cols),
+				));
+			}
+		}
+		Ok(Self { blocks })
+	}
+
+	pub fn get_size(&self) -> (usize, usize) {
+		let size = self.blocks.iter().map(|block| block.get_size().0).sum();
+		return (size, size);
+	}
+
+	pub fn get_blocks(&self) -> &[Matrix] {
+		return &self.blocks;
+	}
+
+	pub fn to_matrix(&self) -> Result<Matrix, MathMatrixError> {
+		let (size, _) = self.get_size();
+		let mut out_mat = Matrix::zeros(size, size)?;
+		let mut offset = 0;
+		for block in &self.blocks {
+			let (block_size, _) = block.get_size();
+			for j in 0..block_size {
+				for i in 0..block_size {
+					out_mat.set_value(offset + i, offset + j, block.get_value(i, j)?)?;
+				}
+			}
+			offset += block_size;
+		}
+		return Ok(out_mat);
+	}
+
+	pub fn multiplied_by_matrix(&self, other: &Matrix) -> Result<Matrix, MathMatrixError> {
+		return self.apply_per_block(other, |block, rhs| block.multiplied_by_matrix(rhs));
+	}
+
+	// Solve A * X = B one diagonal block at a time
+	pub fn solve(&self, b: &Matrix) -> Result<Matrix, MathMatrixError> {
+		return self.apply_per_block(b, |block, rhs| block.solve(rhs));
+	}
+
+	fn apply_per_block<F>(&self, other: &Matrix, operation: F) -> Result<Matrix, MathMatrixError>
+	where
+		F: Fn(&Matrix, &Matrix) -> Result<Matrix, MathMatrixError>,
+	{
+		let (size, _) = self.get_size();
+		let (other_rows, other_cols) = other.get_size();
+		if other_rows != size {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				format!("Expected {} rows, found {}", size, other_rows),
+			));
+		}
+		let mut out_mat = Matrix::zeros(size, other_cols)?;
+		let mut offset = 0;
+		for block in &self.blocks {
+			let (block_size, _) = block.get_size();
+			let mut rhs = Matrix::zeros(block_size, other_cols)?;
+			for j in 0..other_cols {
+				for i in 0..block_size {
+					rhs.set_value(i, j, other.get_value(offset + i, j)?)?;
+				}
+			}
+			let partial = operation(block, &rhs)?;
+			for j in 0..other_cols {
+				for i in 0..block_size {
+					out_mat.set_value(offset + i, j, partial.get_value(i, j)?)?;
+				}
+			}
+			offset += block_size;
+		}
+		return Ok(out_mat);
+	}
+}
+
+impl Matrix {
+	/* Matrix assembled from a grid of blocks, e.g. the saddle-point matrix
+	[A, B^T; B, 0] from `&[[&a, &b_t], [&b, &zero]]`. Blocks in one block row
+	must have the same number of rows, blocks in one block column the same
+	number of columns.
+	*/
+	pub fn from_blocks<const N: usize>(blocks: &[[&Matrix; N]]) -> Result<Matrix, MathMatrixError> {
+		if blocks.is_empty() || N == 0 {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"At least one block is required".to_owned(),
+			));
+		}
+		let block_rows: Vec<usize> = blocks.iter().map(|row| row[0].get_size().0).collect();
+		let block_cols: Vec<usize> = blocks[0].iter().map(|block| block.get_size().1).collect();
+		for (i, row) in blocks.iter().enumerate() {
+			for (j, block) in row.iter().enumerate() {
+				if block.get_size() != (block_rows[i], block_cols[j]) {
+					return Err(MathMatrixError::new(
+						SizeMismatch,
+						format!(
+							"Block ({}, {}) is {}x{}, expected {}x{}",
+							i,
+							j,
+							block.get_size().0,
+							block.get_size().1,
+							block_rows[i],
+							block_cols[j]
+						),
+					));
+				}
+			}
+		}
+		let mut out_mat = Matrix::zeros(block_rows.iter().sum(), block_cols.iter().sum())?;
+		let mut row_offset = 0;
+		for (i, row) in blocks.iter().enumerate() {
+			let mut col_offset = 0;
+			for (j, block) in row.iter().enumerate() {
+				out_mat.set_block(row_offset, col_offset, block)?;
+				col_offset += block_cols[j];
+			}
+			row_offset += block_rows[i];
+		}
+		return Ok(out_mat);
+	}
+
+	// [A | B | ...]: side by side, all with the same number of rows
+	pub fn hstack(blocks: &[&Matrix]) -> Result<Matrix, MathMatrixError> {
+		let rows = check_stack(blocks, |block| block.get_size().0, "rows")?;
+		let mut data = vec![];
+		for block in blocks.iter() {
+			// Column major: the columns of each block simply follow
+			data.extend(block.get_data());
+		}
+		let cols = data.len() / rows;
+		return Matrix::new(rows, cols, data);
+	}
+
+	// [A; B; ...]: on top of each other, all with the same number of columns
+	pub fn vstack(blocks: &[&Matrix]) -> Result<Matrix, MathMatrixError> {
+		let cols = check_stack(blocks, |block| block.get_size().1, "columns")?;
+		let rows: usize = blocks.iter().map(|block| block.get_size().0).sum();
+		let columns: Vec<Vec<f64>> = blocks.iter().map(|block| block.get_data()).collect();
+		let mut data = Vec::with_capacity(rows * cols);
+		for j in 0..cols {
+			for (block, column) in blocks.iter().zip(columns.iter()) {
+				let block_rows = block.get_size().0;
+				data.extend_from_slice(&column[j * block_rows..(j + 1) * block_rows]);
+			}
+		}
+		return Matrix::new(rows, cols, data);
+	}
+
+	// Copy of the rows x cols block whose top-left entry is (row, col)
+	pub fn get_block(
+		&self,
+		row: usize,
+		col: usize,
+		rows: usize,
+		cols: usize,
+	) -> Result<Matrix, MathMatrixError> {
+		self.check_block(row, col, rows, cols)?;
+		let (self_rows, _) = self.get_size();
+		let data = self.get_data();
+		let mut block = Vec::with_capacity(rows * cols);
+		for j in col..(col + cols) {
+			block.extend_from_slice(&data[j * self_rows + row..j * self_rows + row + rows]);
+		}
+		return Matrix::new(rows, cols, block);
+	}
+
+	// Overwrite the block whose top-left entry is (row, col) with `block`
+	pub fn set_block(
+		&mut self,
+		row: usize,
+		col: usize,
+		block: &Matrix,
+	) -> Result<(), MathMatrixError> {
+		let (rows, cols) = block.get_size();
+		self.check_block(row, col, rows, cols)?;
+		for j in 0..cols {
+			for i in 0..rows {
+				self.set_value(row + i, col + j, block.get_value(i, j)?)?;
+			}
+		}
+		return Ok(());
+	}
+
+	fn check_block(
+		&self,
+		row: usize,
+		col: usize,
+		rows: usize,
+		cols: usize,
+	) -> Result<(), MathMatrixError> {
+		let (self_rows, self_cols) = self.get_size();
+		if rows * cols == 0 || row + rows > self_rows || col + cols > self_cols {
+			return Err(MathMatrixError::new(
+				OutOfBoundary,
+				format!(
+					"Block {}x{} at ({}, {}) outside a {}x{} matrix",
+					rows, cols, row, col, self_rows, self_cols
+				),
+			));
+		}
+		return Ok(());
+	}
+}
+
+// The common size of `blocks` along the dimension that must match
+fn check_stack<F: Fn(&Matrix) -> usize>(
+	blocks: &[&Matrix],
+	size: F,
+	name: &str,
+) -> Result<usize, MathMatrixError> {
+	if blocks.is_empty() {
+		return Err(MathMatrixError::new(
+			FailedToInitialize,
+			"At least one block is required".to_owned(),
+		));
+	}
+	let expected = size(blocks[0]);
+	if let Some(k) = blocks.iter().position(|block| size(block) != expected) {
+		return Err(MathMatrixError::new(
+			SizeMismatch,
+			format!(
+				"Block {} has {} {}, expected {}",
+				k,
+				size(blocks[k]),
+				name,
+				expected
+			),
+		));
+	}
+	return Ok(expected);
+}
+
+/* Block Thomas algorithm for
+	D0 U0
+	L1 D1 U1
+	   L2 D2 ...
+where `lower` holds L1..Ln-1, `upper` holds U0..Un-2 and `rhs` holds one
+block of right-hand sides per block row. Returns the solution block rows.
+*/
+pub fn solve_block_tridiagonal(
+	lower: &[Matrix],
+	diagonal: &[Matrix],
+	upper: &[Matrix],
+	rhs: &[Matrix],
+) -> Result<Vec<Matrix>, MathMatrixError> {
+	let n = diagonal.len();
+	if n == 0 || lower.len() + 1 != n || upper.len() + 1 != n || rhs.len() != n {
+		return Err(MathMatrixError::new(
+			SizeMismatch,
+			"Block tridiagonal solve requires n diagonal, n-1 off-diagonal and n rhs blocks"
+				.to_owned(),
+		));
+	}
+	// Forward elimination: D'i = Di - Li * D'i-1^(-1) * Ui-1, y'i = yi - Li * D'i-1^(-1) * y'i-1
+	let mut modified_diagonal: Vec<Matrix> = Vec::with_capacity(n);
+	let mut modified_rhs: Vec<Matrix> = Vec::with_capacity(n);
+	modified_diagonal.push(diagonal[0].clone());
+	modified_rhs.push(rhs[0].clone());
+	for i in 1..n {
+		let d_inv_u = modified_diagonal[i - 1].solve(&upper[i - 1])?;
+		let d_inv_y = modified_diagonal[i - 1].solve(&modified_rhs[i - 1])?;
+		let d_mat = (diagonal[i].clone() - lower[i - 1].multiplied_by_matrix(&d_inv_u)?)?;
+		let y_mat = (rhs[i].clone() - lower[i - 1].multiplied_by_matrix(&d_inv_y)?)?;
+		modified_diagonal.push(d_mat);
+		modified_rhs.push(y_mat);
+	}
+	// Back substitution
+	let mut solution: Vec<Matrix> = vec![modified_diagonal[n - 1].solve(&modified_rhs[n - 1])?];
+	for i in (0..(n - 1)).rev() {
+		let x_next = &solution[solution.len() - 1];
+		let y_mat = (modified_rhs[i].clone() - upper[i].multiplied_by_matrix(x_next)?)?;
+		solution.push(modified_diagonal[i].solve(&y_mat)?);
+	}
+	solution.reverse();
+	return Ok(solution);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_block_diagonal_to_matrix() {
+		let block_1 = Matrix::new(1, 1, vec![2.0]).unwrap();
+		let block_2 = Matrix::new(2, 2, vec![1.0, 3.0, 2.0, 4.0]).unwrap();
+		let mat = BlockDiagonal::new(vec![block_1, block_2])
+			.unwrap()
+			.to_matrix()
+			.unwrap();
+		assert_eq!(
+			mat.get_data(),
+			vec![2.0, 0.0, 0.0, 0.0, 1.0, 3.0, 0.0, 2.0, 4.0]
+		);
+		assert!(BlockDiagonal::new(vec![Matrix::zeros(1, 2).unwrap()]).is_err());
+	}
+
+	#[test]
+	fn test_block_diagonal_solve() {
+		let block_1 = Matrix::new(1, 1, vec![2.0]).unwrap();
+		let block_2 = Matrix::new(2, 2, vec![4.0, 0.0, 0.0, 8.0]).unwrap();
+		let block_diagonal = BlockDiagonal::new(vec![block_1, block_2]).unwrap();
+		let b = Matrix::new(3, 1, vec![2.0, 4.0, 8.0]).unwrap();
+		let x = block_diagonal.solve(&b).unwrap();
+		assert_eq!(x.get_data(), vec![1.0, 1.0, 1.0]);
+		assert_eq!(block_diagonal.multiplied_by_matrix(&x).unwrap(), b);
+	}
+
+	#[test]
+	fn test_solve_block_tridiagonal() {
+		let diagonal = vec![Matrix::identity(2, 2).unwrap().multiplied_by_scalar(4.0); 3];
+		let lower = vec![Matrix::identity(2, 2).unwrap(); 2];
+		let upper = vec![Matrix::identity(2, 2).unwrap(); 2];
+		// With x = [1 1 1 1 1 1]^T every block row sums to 5, 6, 5
+		let rhs = vec![
+			Matrix::new(2, 1, vec![5.0, 5.0]).unwrap(),
+			Matrix::new(2, 1, vec![6.0, 6.0]).unwrap(),
+			Matrix::new(2, 1, vec![5.0, 5.0]).unwrap(),
+		];
+		let solution = solve_block_tridiagonal(&lower, &diagonal, &upper, &rhs).unwrap();
+		for block in solution {
+			for value in block.get_data() {
+				assert!((value - 1.0).abs() < 1e-12);
+			}
+		}
+	}
+
+	#[test]
+	fn test_from_blocks() {
+		// Saddle-point matrix [A, B^T; B, 0]
+		let a = Matrix::new(2, 2, vec![4.0, 1.0, 1.0, 3.0]).unwrap();
+		let b = Matrix::new(1, 2, vec![1.0, 1.0]).unwrap();
+		let b_t = b.transposed();
+		let zero = Matrix::zeros(1, 1).unwrap();
+		let saddle = Matrix::from_blocks(&[[&a, &b_t], [&b, &zero]]).unwrap();
+		assert_eq!(
+			saddle.get_data(),
+			vec![4.0, 1.0, 1.0, 1.0, 3.0, 1.0, 1.0, 1.0, 0.0]
+		);
+		assert_eq!(saddle.get_block(0, 0, 2, 2).unwrap(), a);
+		assert_eq!(saddle.get_block(2, 0, 1, 2).unwrap(), b);
+		let mut edited = saddle.clone();
+		edited
+			.set_block(0, 2, &Matrix::new(2, 1, vec![5.0, 6.0]).unwrap())
+			.unwrap();
+		assert_eq!(edited.get_value(1, 2).unwrap(), 6.0);
+		assert!(saddle.get_block(2, 2, 2, 1).is_err());
+		assert!(Matrix::from_blocks(&[[&a, &b], [&b, &zero]]).is_err());
+	}
+
+	#[test]
+	fn test_hstack_vstack() {
+		let a = Matrix::new(2, 2, vec![1.0, 3.0, 2.0, 4.0]).unwrap();
+		let b = Matrix::new(2, 1, vec![5.0, 6.0]).unwrap();
+		let augmented = Matrix::hstack(&[&a, &b]).unwrap();
+		assert_eq!(augmented, Matrix::from_blocks(&[[&a, &b]]).unwrap());
+		assert_eq!(augmented.get_size(), (2, 3));
+		let c = Matrix::new(1, 2, vec![7.0, 8.0]).unwrap();
+		let stacked = Matrix::vstack(&[&a, &c, &c]).unwrap();
+		assert_eq!(stacked.get_size(), (4, 2));
+		assert_eq!(
+			stacked.get_data(),
+			vec![1.0, 3.0, 7.0, 7.0, 2.0, 4.0, 8.0, 8.0]
+		);
+		assert!(Matrix::hstack(&[&a, &c]).is_err());
+		assert!(Matrix::vstack(&[&a, &b]).is_err());
+		assert!(Matrix::vstack(&[]).is_err());
+	}
+}
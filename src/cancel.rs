@@ -0,0 +1,78 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/* Shared flag for aborting a long computation from another thread, e.g. a UI
+thread. Clones share the flag. Iterative algorithms check it once per
+iteration and return a Cancelled error when it is set.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+	cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+	pub fn new() -> Self {
+		return Self::default();
+	}
+
+	pub fn cancel(&self) {
+		self.cancelled.store(true, Ordering::Relaxed);
+	}
+
+	pub fn is_cancelled(&self) -> bool {
+		return self.cancelled.load(Ordering::Relaxed);
+	}
+
+	pub(crate) fn to_control_flow(&self) -> ControlFlow<()> {
+		if self.is_cancelled() {
+			return ControlFlow::Break(());
+		}
+		return ControlFlow::Continue(());
+	}
+
+	pub(crate) fn check(&self, operation: &str) -> Result<(), MathMatrixError> {
+		if self.is_cancelled() {
+			return Err(MathMatrixError::new(
+				Cancelled,
+				format!("{} was cancelled", operation),
+			));
+		}
+		return Ok(());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::matrix::Matrix;
+
+	#[test]
+	fn test_cancelled_before_start() {
+		let token = CancellationToken::new();
+		let mat = Matrix::new(3, 3, vec![4.0, 1.0, 2.0, 1.0, 3.0, 0.0, 2.0, 0.0, 5.0]).unwrap();
+		assert!(mat.svd_with_cancellation(&token).is_ok());
+		assert!(mat.eigen_with_cancellation(&token).is_ok());
+		token.clone().cancel();
+		assert!(token.is_cancelled());
+		let err = mat.svd_with_cancellation(&token).unwrap_err();
+		assert_eq!(err.get_kind(), "Cancelled");
+		let err = mat.eigen_with_cancellation(&token).unwrap_err();
+		assert_eq!(err.get_kind(), "Cancelled");
+	}
+
+	#[test]
+	fn test_cancel_from_another_thread() {
+		let token = CancellationToken::new();
+		let a = crate::multigrid::laplacian_1d(31).unwrap();
+		let b = Matrix::new(31, 1, vec![1.0; 31]).unwrap();
+		let canceller = token.clone();
+		std::thread::spawn(move || canceller.cancel())
+			.join()
+			.unwrap();
+		let err = crate::multigrid::solve_with_cancellation(&a, &b, 1e-10, 50, &token).unwrap_err();
+		assert_eq!(err.get_kind(), "Cancelled");
+	}
+}
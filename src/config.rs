@@ -0,0 +1,61 @@
+use std::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/* Upper bound on the threads a single call may use, so a caller that is
+already parallel can avoid oversubscription. Zero means one thread per
+available core. `with_max_threads` overrides the global value for the
+calls made inside its closure on the current thread.
+*/
+
+static MAX_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+	static OVERRIDE: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+pub fn set_max_threads(threads: usize) {
+	MAX_THREADS.store(threads, Ordering::Relaxed);
+}
+
+pub fn max_threads() -> usize {
+	let threads = OVERRIDE
+		.with(|value| value.get())
+		.unwrap_or_else(|| MAX_THREADS.load(Ordering::Relaxed));
+	if threads == 0 {
+		return std::thread::available_parallelism()
+			.map(|cores| cores.get())
+			.unwrap_or(1);
+	}
+	return threads;
+}
+
+pub fn with_max_threads<T, F: FnOnce() -> T>(threads: usize, f: F) -> T {
+	let previous = OVERRIDE.with(|value| value.replace(Some(threads)));
+	// Restore the previous value even if f panics
+	struct Restore(Option<usize>);
+	impl Drop for Restore {
+		fn drop(&mut self) {
+			OVERRIDE.with(|value| value.set(self.0));
+		}
+	}
+	let _restore = Restore(previous);
+	return f();
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_with_max_threads() {
+		let outer = max_threads();
+		assert!(outer >= 1);
+		let inner = with_max_threads(1, || {
+			let nested = with_max_threads(3, max_threads);
+			assert_eq!(nested, 3);
+			return max_threads();
+		});
+		assert_eq!(inner, 1);
+		assert_eq!(max_threads(), outer);
+	}
+}
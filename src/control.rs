@@ -0,0 +1,118 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+
+/* Solves the Sylvester equation A * X + X * B = C for A: MxM, B: NxN and
+C: MxN with the Bartels-Stewart method:
+1. Real Schur forms A = U * T * U^T and B = V * S * V^T
+2. T * Y + Y * S = U^T * C * V, solved column by column (or by pairs of
+   columns for the 2x2 blocks of S) since S is quasi-upper-triangular
+3. X = U * Y * V^T
+The solution is unique when no eigenvalue of A is the negative of an
+eigenvalue of B; otherwise the shifted systems in step 2 are singular and
+this fails.
+*/
+pub fn solve_sylvester(a: &Matrix, b: &Matrix, c: &Matrix) -> Result<Matrix, MathMatrixError> {
+	let (m, a_cols) = a.get_size();
+	let (n, b_cols) = b.get_size();
+	if m != a_cols || n != b_cols || c.get_size() != (m, n) {
+		return Err(MathMatrixError::new(
+			SizeMismatch,
+			"Sylvester equation requires A: MxM, B: NxN and C: MxN".to_owned(),
+		));
+	}
+	let (u, t) = a.schur()?;
+	let (v, s) = b.schur()?;
+	let f = u
+		.transposed()
+		.multiplied_by_matrix(c)?
+		.multiplied_by_matrix(&v)?;
+	let mut y = Matrix::zeros(m, n)?;
+	let mut k = 0;
+	while k < n {
+		let pair = k + 1 < n && s.get_value(k + 1, k)? != 0.0;
+		let width = if pair { 2 } else { 1 };
+		// Right-hand side: f_l - sum_{j < k} s_jl * y_j for the columns l in the block
+		let mut rhs = Matrix::zeros(m * width, 1)?;
+		for l in 0..width {
+			for i in 0..m {
+				let mut elem = f.get_value(i, k + l)?;
+				for j in 0..k {
+					elem -= s.get_value(j, k + l)? * y.get_value(i, j)?;
+				}
+				rhs.set_value(i + l * m, 0, elem)?;
+			}
+		}
+		// Block (l, p) of the shifted system is T * [l == p] + s_{k+p, k+l} * I
+		let mut system = Matrix::zeros(m * width, m * width)?;
+		for l in 0..width {
+			for p in 0..width {
+				for i in 0..m {
+					if l == p {
+						for j in 0..m {
+							system.set_value(i + l * m, j + p * m, t.get_value(i, j)?)?;
+						}
+					}
+					let diagonal = system.get_value(i + l * m, i + p * m)?;
+					let shift = s.get_value(k + p, k + l)?;
+					system.set_value(i + l * m, i + p * m, diagonal + shift)?;
+				}
+			}
+		}
+		let solution = system.solve(&rhs)?;
+		for l in 0..width {
+			for i in 0..m {
+				y.set_value(i, k + l, solution.get_value(i + l * m, 0)?)?;
+			}
+		}
+		k += width;
+	}
+	return u
+		.multiplied_by_matrix(&y)?
+		.multiplied_by_matrix(&v.transposed());
+}
+
+/* Solves the continuous Lyapunov equation A * X + X * A^T + Q = 0 for
+A, Q: NxN, as the Sylvester equation with B = A^T and C = -Q. When A is
+stable (all eigenvalues in the open left half-plane) and Q is symmetric
+positive semidefinite, X is the controllability-style Gramian: symmetric
+positive semidefinite.
+*/
+pub fn solve_lyapunov(a: &Matrix, q: &Matrix) -> Result<Matrix, MathMatrixError> {
+	return solve_sylvester(a, &a.transposed(), &q.multiplied_by_scalar(-1.0));
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_solve_sylvester() {
+		// B has complex eigenvalues 1 +- 2i, so S has a 2x2 block
+		let a = Matrix::new(3, 3, vec![4.0, 1.0, 0.0, 1.0, 3.0, 1.0, 0.0, 2.0, 5.0]).unwrap();
+		let b = Matrix::new(2, 2, vec![1.0, -2.0, 2.0, 1.0]).unwrap();
+		let c = Matrix::new(3, 2, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+		let x = solve_sylvester(&a, &b, &c).unwrap();
+		let lhs =
+			(a.multiplied_by_matrix(&x).unwrap() + x.multiplied_by_matrix(&b).unwrap()).unwrap();
+		assert!(lhs.approx_eq(&c, 1e-10));
+		// 0 is an eigenvalue of both, so lambda + mu = 0 and there is no unique solution
+		let singular = Matrix::new(2, 2, vec![0.0, 0.0, 1.0, 0.0]).unwrap();
+		assert!(solve_sylvester(&singular, &singular, &b).is_err());
+		assert!(solve_sylvester(&a, &b, &b).is_err());
+	}
+
+	#[test]
+	fn test_solve_lyapunov() {
+		let a = Matrix::new(2, 2, vec![-1.0, 1.0, 0.5, -2.0]).unwrap();
+		let q = Matrix::identity(2, 2).unwrap();
+		let x = solve_lyapunov(&a, &q).unwrap();
+		let residual = ((a.multiplied_by_matrix(&x).unwrap()
+			+ x.multiplied_by_matrix(&a.transposed()).unwrap())
+		.unwrap() + q)
+			.unwrap();
+		assert!(residual.approx_eq(&Matrix::zeros(2, 2).unwrap(), 1e-12));
+		assert!(x.is_symmetric(1e-12));
+		assert!(x.cholesky().is_ok());
+	}
+}
@@ -0,0 +1,200 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+
+/* Covariance matrices with known structure, built directly instead of
+estimated from samples. The results are symmetric positive definite (for
+distinct points, in the case of kernels) and can go straight to `cholesky`.
+*/
+
+// Stationary AR(1) process with unit variance: sigma_ij = rho^|i - j|
+pub fn ar1_covariance(size: usize, rho: f64) -> Result<Matrix, MathMatrixError> {
+	if rho.is_nan() || rho.abs() >= 1.0 {
+		return Err(MathMatrixError::new(
+			OperationNotPermitted,
+			format!("AR(1) coefficient must satisfy |rho| < 1, got {}", rho),
+		));
+	}
+	let mut covariance = Matrix::zeros(size, size)?;
+	for j in 0..size {
+		for i in 0..size {
+			covariance.set_value(i, j, rho.powi((i as i32 - j as i32).abs()))?;
+		}
+	}
+	return Ok(covariance);
+}
+
+// Covariance k(x, y) between two points, as a function of their distance
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kernel {
+	// exp(-gamma * |x - y|^2), the squared exponential
+	Rbf { gamma: f64 },
+	// exp(-gamma * |x - y|), the Ornstein-Uhlenbeck covariance
+	Exponential { gamma: f64 },
+}
+
+impl Kernel {
+	pub fn evaluate(&self, x: &[f64], y: &[f64]) -> f64 {
+		let squared: f64 = x.iter().zip(y.iter()).map(|(a, b)| (a - b).powi(2)).sum();
+		return match self {
+			Kernel::Rbf { gamma } => (-gamma * squared).exp(),
+			Kernel::Exponential { gamma } => (-gamma * squared.sqrt()).exp(),
+		};
+	}
+
+	fn check(&self) -> Result<(), MathMatrixError> {
+		let gamma = match self {
+			Kernel::Rbf { gamma } | Kernel::Exponential { gamma } => *gamma,
+		};
+		if gamma <= 0.0 || !gamma.is_finite() {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				format!("Kernel gamma must be positive, got {}", gamma),
+			));
+		}
+		return Ok(());
+	}
+}
+
+// Gram matrix k(x_i, x_j) of the rows of x, NxN for N points
+pub fn kernel_matrix(x: &Matrix, kernel: Kernel) -> Result<Matrix, MathMatrixError> {
+	return cross_kernel_matrix(x, x, kernel);
+}
+
+// k(x_i, y_j) between the rows of x and the rows of y
+pub fn cross_kernel_matrix(
+	x: &Matrix,
+	y: &Matrix,
+	kernel: Kernel,
+) -> Result<Matrix, MathMatrixError> {
+	kernel.check()?;
+	let (x_rows, x_cols) = x.get_size();
+	let (y_rows, y_cols) = y.get_size();
+	if x_cols != y_cols {
+		return Err(MathMatrixError::new(
+			SizeMismatch,
+			format!(
+				"Points of dimension {} and {} cannot be compared",
+				x_cols, y_cols
+			),
+		));
+	}
+	let (x_points, y_points) = (points(x), points(y));
+	let mut data = Vec::with_capacity(x_rows * y_rows);
+	for q in y_points.iter() {
+		data.extend(x_points.iter().map(|p| kernel.evaluate(p, q)));
+	}
+	return Matrix::new(x_rows, y_rows, data);
+}
+
+// Posterior of a Gaussian process at the test points
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpPrediction {
+	// M x K predictive mean, one row per test point
+	pub mean: Matrix,
+	// M x 1 predictive variance of the latent function, without the noise
+	pub variance: Matrix,
+}
+
+/* Gaussian process regression with a zero prior mean. Training inputs are
+the rows of x_train (N x D) with targets y_train (N x K, one column per
+output); `noise` is the observation noise variance added to the diagonal.
+With K = k(X, X) + noise * I = L * L^T and k_* = k(X, x_*):
+mean = k_*^T * K^-1 * y and variance = k(x_*, x_*) - |L^-1 * k_*|^2.
+*/
+pub fn gp_regress(
+	x_train: &Matrix,
+	y_train: &Matrix,
+	x_test: &Matrix,
+	kernel: Kernel,
+	noise: f64,
+) -> Result<GpPrediction, MathMatrixError> {
+	let n = x_train.get_size().0;
+	if y_train.get_size().0 != n {
+		return Err(MathMatrixError::new(
+			SizeMismatch,
+			format!("{} training inputs and {} targets", n, y_train.get_size().0),
+		));
+	}
+	if noise < 0.0 || noise.is_nan() {
+		return Err(MathMatrixError::new(
+			OperationNotPermitted,
+			format!("Noise variance must be non-negative, got {}", noise),
+		));
+	}
+	let mut k = kernel_matrix(x_train, kernel)?;
+	for i in 0..n {
+		k.set_value(i, i, k.get_value(i, i)? + noise)?;
+	}
+	let l = k.cholesky()?;
+	let alpha = l
+		.transposed()
+		.solve_upper_triangular(&l.solve_lower_triangular(y_train)?)?;
+	let k_star = cross_kernel_matrix(x_train, x_test, kernel)?;
+	let mean = k_star.transposed().multiplied_by_matrix(&alpha)?;
+	let v = l.solve_lower_triangular(&k_star)?;
+	let v_data = v.get_data();
+	let variance = points(x_test)
+		.iter()
+		.zip(v_data.chunks(n))
+		.map(|(point, v_col)| {
+			let explained: f64 = v_col.iter().map(|x| x * x).sum();
+			return kernel.evaluate(point, point) - explained;
+		})
+		.collect::<Vec<f64>>();
+	return Ok(GpPrediction {
+		mean,
+		variance: Matrix::new(variance.len(), 1, variance)?,
+	});
+}
+
+// The rows of a matrix as contiguous points
+fn points(x: &Matrix) -> Vec<Vec<f64>> {
+	let (rows, cols) = x.get_size();
+	let data = x.get_data();
+	return (0..rows)
+		.map(|i| (0..cols).map(|j| data[j * rows + i]).collect())
+		.collect();
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_structured_covariances() {
+		let ar1 = ar1_covariance(4, 0.5).unwrap();
+		assert_eq!(ar1.get_value(3, 0).unwrap(), 0.125);
+		assert!(ar1.is_symmetric(0.0));
+		assert!(ar1.cholesky().is_ok());
+		assert!(ar1_covariance(3, 1.0).is_err());
+		let x = Matrix::new(3, 2, vec![0.0, 1.0, 0.0, 0.0, 0.0, 2.0]).unwrap();
+		let rbf = kernel_matrix(&x, Kernel::Rbf { gamma: 0.5 }).unwrap();
+		assert_eq!(rbf.get_value(0, 1).unwrap(), (-0.5f64).exp());
+		assert_eq!(rbf.get_value(1, 1).unwrap(), 1.0);
+		assert!(rbf.cholesky().is_ok());
+		let exponential = kernel_matrix(&x, Kernel::Exponential { gamma: 1.0 }).unwrap();
+		assert_eq!(exponential.get_value(0, 2).unwrap(), (-2.0f64).exp());
+		assert!(kernel_matrix(&x, Kernel::Rbf { gamma: 0.0 }).is_err());
+		let y = Matrix::new(1, 2, vec![1.0, 1.0]).unwrap();
+		let cross = cross_kernel_matrix(&x, &y, Kernel::Rbf { gamma: 1.0 }).unwrap();
+		assert_eq!(cross.get_size(), (3, 1));
+	}
+
+	#[test]
+	fn test_gp_regress() {
+		let x_train = Matrix::new(4, 1, vec![0.0, 1.0, 2.0, 3.0]).unwrap();
+		let y_train = Matrix::new(4, 1, vec![0.0, 0.8, 0.9, 0.1]).unwrap();
+		let x_test = Matrix::new(2, 1, vec![1.0, 10.0]).unwrap();
+		let kernel = Kernel::Rbf { gamma: 0.5 };
+		let prediction = gp_regress(&x_train, &y_train, &x_test, kernel, 1e-8).unwrap();
+		// Nearly noiseless: interpolates at a training point, with no variance
+		assert!((prediction.mean.get_value(0, 0).unwrap() - 0.8).abs() < 1e-6);
+		assert!(prediction.variance.get_value(0, 0).unwrap().abs() < 1e-6);
+		// Far from the data: back to the prior mean 0 and variance 1
+		assert!(prediction.mean.get_value(1, 0).unwrap().abs() < 1e-6);
+		assert!((prediction.variance.get_value(1, 0).unwrap() - 1.0).abs() < 1e-6);
+		assert!(gp_regress(&x_train, &x_test, &x_test, kernel, 0.1).is_err());
+		assert!(gp_regress(&x_train, &y_train, &x_test, kernel, -1.0).is_err());
+	}
+}
@@ -0,0 +1,193 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+use super::operator::LinearOperator;
+use super::reduction;
+
+/* Square diagonal matrix storing only its diagonal. Products with a dense
+matrix scale its rows (D * A) or columns (A * D) in O(N * M) instead of a
+full multiplication, and inversion, solves and determinants are O(N).
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagonalMatrix {
+	values: Vec<f64>,
+}
+
+impl DiagonalMatrix {
+	pub fn new(values: Vec<f64>) -> Result<Self, MathMatrixError> {
+		if values.is_empty() {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"Rows and columns must be lager than 0".to_owned(),
+			));
+		}
+		return Ok(Self { values });
+	}
+
+	pub fn identity(size: usize) -> Result<Self, MathMatrixError> {
+		return Self::new(vec![1.0; size]);
+	}
+
+	pub fn get_size(&self) -> (usize, usize) {
+		return (self.values.len(), self.values.len());
+	}
+
+	pub fn get_values(&self) -> &[f64] {
+		return &self.values;
+	}
+
+	pub fn to_matrix(&self) -> Result<Matrix, MathMatrixError> {
+		let size = self.values.len();
+		let mut mat = Matrix::zeros(size, size)?;
+		for (i, value) in self.values.iter().enumerate() {
+			mat.set_value(i, i, *value)?;
+		}
+		return Ok(mat);
+	}
+
+	// D * A: row i of A scaled by d_i
+	pub fn multiplied_by_matrix(&self, other: &Matrix) -> Result<Matrix, MathMatrixError> {
+		let (rows, cols) = other.get_size();
+		if self.values.len() != rows {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Multiplication allowed for NxM * MxO".to_owned(),
+			));
+		}
+		let mut data = other.get_data();
+		for column in data.chunks_mut(rows) {
+			for (elem, d) in column.iter_mut().zip(self.values.iter()) {
+				*elem *= d;
+			}
+		}
+		return Matrix::new(rows, cols, data);
+	}
+
+	pub fn multiplied_by_diagonal(
+		&self,
+		other: &DiagonalMatrix,
+	) -> Result<DiagonalMatrix, MathMatrixError> {
+		return self.zip_with(other, |a, b| a * b);
+	}
+
+	pub fn added_to(&self, other: &DiagonalMatrix) -> Result<DiagonalMatrix, MathMatrixError> {
+		return self.zip_with(other, |a, b| a + b);
+	}
+
+	pub fn inverse(&self) -> Result<DiagonalMatrix, MathMatrixError> {
+		self.check_nonsingular()?;
+		return Self::new(self.values.iter().map(|d| 1.0 / d).collect());
+	}
+
+	// Solve D * X = B
+	pub fn solve(&self, b: &Matrix) -> Result<Matrix, MathMatrixError> {
+		return self.inverse()?.multiplied_by_matrix(b);
+	}
+
+	pub fn determinant(&self) -> f64 {
+		return reduction::product(self.values.iter().copied());
+	}
+
+	fn zip_with<F: Fn(f64, f64) -> f64>(
+		&self,
+		other: &DiagonalMatrix,
+		f: F,
+	) -> Result<DiagonalMatrix, MathMatrixError> {
+		if self.values.len() != other.values.len() {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Operation not allowed between matrices with different sizes".to_owned(),
+			));
+		}
+		return Self::new(
+			self.values
+				.iter()
+				.zip(other.values.iter())
+				.map(|(a, b)| f(*a, *b))
+				.collect(),
+		);
+	}
+
+	fn check_nonsingular(&self) -> Result<(), MathMatrixError> {
+		if self.values.contains(&0.0) {
+			return Err(MathMatrixError::new(
+				FailedToDecompose,
+				"Matrix is singular".to_owned(),
+			));
+		}
+		return Ok(());
+	}
+}
+
+impl LinearOperator for DiagonalMatrix {
+	fn get_size(&self) -> (usize, usize) {
+		return DiagonalMatrix::get_size(self);
+	}
+
+	fn apply(&self, v: &Matrix) -> Result<Matrix, MathMatrixError> {
+		return self.multiplied_by_matrix(v);
+	}
+}
+
+impl Matrix {
+	// A * D: column j of A scaled by d_j
+	pub fn multiplied_by_diagonal(
+		&self,
+		diagonal: &DiagonalMatrix,
+	) -> Result<Matrix, MathMatrixError> {
+		let (rows, cols) = self.get_size();
+		if cols != diagonal.values.len() {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Multiplication allowed for NxM * MxO".to_owned(),
+			));
+		}
+		let mut data = self.get_data();
+		for (column, d) in data.chunks_mut(rows).zip(diagonal.values.iter()) {
+			column.iter_mut().for_each(|elem| *elem *= d);
+		}
+		return Matrix::new(rows, cols, data);
+	}
+
+	// The main diagonal, min(M, N) entries
+	pub fn diagonal(&self) -> Result<DiagonalMatrix, MathMatrixError> {
+		let (rows, cols) = self.get_size();
+		let values = (0..rows.min(cols))
+			.map(|i| self.get_value(i, i))
+			.collect::<Result<Vec<f64>, MathMatrixError>>()?;
+		return DiagonalMatrix::new(values);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_diagonal_matrix() {
+		let d = DiagonalMatrix::new(vec![2.0, -1.0, 0.5]).unwrap();
+		let dense = d.to_matrix().unwrap();
+		let a = Matrix::new(3, 3, (0..9).map(|k| k as f64 + 1.0).collect()).unwrap();
+		assert_eq!(
+			d.multiplied_by_matrix(&a).unwrap(),
+			dense.multiplied_by_matrix(&a).unwrap()
+		);
+		assert_eq!(
+			a.multiplied_by_diagonal(&d).unwrap(),
+			a.multiplied_by_matrix(&dense).unwrap()
+		);
+		assert_eq!(d.determinant(), -1.0);
+		let x = d.solve(&a).unwrap();
+		assert!(d.multiplied_by_matrix(&x).unwrap().approx_eq(&a, 1e-15));
+		assert_eq!(
+			d.multiplied_by_diagonal(&d.inverse().unwrap()).unwrap(),
+			DiagonalMatrix::identity(3).unwrap()
+		);
+		assert_eq!(a.diagonal().unwrap().get_values(), &[1.0, 5.0, 9.0]);
+		assert!(d.added_to(&DiagonalMatrix::identity(2).unwrap()).is_err());
+		assert!(DiagonalMatrix::new(vec![1.0, 0.0])
+			.unwrap()
+			.inverse()
+			.is_err());
+	}
+}
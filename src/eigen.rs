@@ -0,0 +1,1123 @@
+use super::cancel::CancellationToken;
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+use super::operator::LinearOperator;
+use super::oracle;
+use super::random::Rng;
+use std::ops::ControlFlow;
+
+/* Eigenvalues of a real square matrix, following the EISPACK/JAMA routines:
+1. orthes: Householder reduction to upper Hessenberg form H = Q^T * A * Q
+2. hqr2: Francis double-shift QR iterations reducing H to real Schur form T
+3. hqr2: back substitution on T for the eigenvectors, mapped back through Q
+Symmetric matrices can use the cyclic Jacobi method instead, which always
+gives real eigenvalues and orthonormal eigenvectors.
+*/
+
+const JACOBI_MAX_SWEEPS: usize = 100;
+
+// Called as on_iteration(iteration, residual); Break aborts with a Cancelled error
+type OnIteration<'a> = &'a mut dyn FnMut(usize, f64) -> ControlFlow<()>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Eigen {
+	// Eigenvalue k is real[k] + i * imaginary[k]. Complex conjugate pairs are
+	// adjacent, with the positive imaginary part first.
+	pub real: Vec<f64>,
+	pub imaginary: Vec<f64>,
+	/* Column k is the eigenvector of a real eigenvalue k. For a complex pair
+	(k, k + 1), columns k and k + 1 hold the real and imaginary parts of the
+	eigenvector of real[k] + i * imaginary[k]. Vectors are not normalized.
+	*/
+	pub vectors: Matrix,
+}
+
+impl Matrix {
+	pub fn eigen(&self) -> Result<Eigen, MathMatrixError> {
+		return self.eigen_with_cancellation(&CancellationToken::new());
+	}
+
+	// Checks the token between QR iterations
+	pub fn eigen_with_cancellation(
+		&self,
+		token: &CancellationToken,
+	) -> Result<Eigen, MathMatrixError> {
+		token.check("Eigen decomposition")?;
+		return self.eigen_with_progress(|_, _| token.to_control_flow());
+	}
+
+	/* Calls on_iteration(iteration, residual) before every QR iteration, where
+	the residual is the subdiagonal entry of H being driven to zero. Returning
+	ControlFlow::Break aborts with a Cancelled error.
+	*/
+	pub fn eigen_with_progress<F: FnMut(usize, f64) -> ControlFlow<()>>(
+		&self,
+		mut on_iteration: F,
+	) -> Result<Eigen, MathMatrixError> {
+		let (mut h, mut q) = hessenberg_rows(self)?;
+		let (real, imaginary) = schur_iterations(&mut h, &mut q, &mut on_iteration)?;
+		back_substitute(&mut h, &mut q, &real, &imaginary);
+		let eigen = Eigen {
+			real,
+			imaginary,
+			vectors: from_rows(&q)?,
+		};
+		oracle::check_eigen(self, &eigen);
+		return Ok(eigen);
+	}
+
+	/* Real Schur decomposition A = Q * T * Q^T with Q orthogonal and T
+	quasi-upper-triangular: upper triangular except for 2x2 diagonal blocks,
+	one per complex conjugate pair of eigenvalues. Returns (Q, T).
+	*/
+	pub fn schur(&self) -> Result<(Matrix, Matrix), MathMatrixError> {
+		let (mut t, mut q) = hessenberg_rows(self)?;
+		let (_, imaginary) =
+			schur_iterations(&mut t, &mut q, &mut |_, _| ControlFlow::Continue(()))?;
+		// Clear what the iterations left below the blocks, all negligible by the deflation test
+		for i in 1..t.len() {
+			for j in 0..(i - 1) {
+				t[i][j] = 0.0;
+			}
+			if !(imaginary[i - 1] > 0.0 && imaginary[i] < 0.0) {
+				t[i][i - 1] = 0.0;
+			}
+		}
+		return Ok((from_rows(&q)?, from_rows(&t)?));
+	}
+
+	/* Householder reduction to upper Hessenberg form: A = Q * H * Q^T with Q
+	orthogonal and H zero below the first subdiagonal. Returns (H, Q).
+	*/
+	pub fn hessenberg(&self) -> Result<(Matrix, Matrix), MathMatrixError> {
+		let (h, q) = hessenberg_rows(self)?;
+		return Ok((from_rows(&h)?, from_rows(&q)?));
+	}
+
+	// Eigenvalues only, as (real parts, imaginary parts)
+	pub fn eigenvalues(&self) -> Result<(Vec<f64>, Vec<f64>), MathMatrixError> {
+		let (mut h, mut q) = hessenberg_rows(self)?;
+		return schur_iterations(&mut h, &mut q, &mut |_, _| ControlFlow::Continue(()));
+	}
+
+	/* Eigenvalues in increasing order and the matching orthonormal eigenvectors
+	as columns, for a symmetric matrix (up to rounding relative to its largest
+	entry)
+	*/
+	pub fn eigen_symmetric(&self) -> Result<(Vec<f64>, Matrix), MathMatrixError> {
+		return self.eigen_symmetric_with_progress(|_, _| ControlFlow::Continue(()));
+	}
+
+	/* Calls on_iteration(sweep, off-diagonal norm) before every Jacobi sweep.
+	Returning ControlFlow::Break aborts with a Cancelled error.
+	*/
+	pub fn eigen_symmetric_with_progress<F: FnMut(usize, f64) -> ControlFlow<()>>(
+		&self,
+		mut on_iteration: F,
+	) -> Result<(Vec<f64>, Matrix), MathMatrixError> {
+		let largest = self.get_data().iter().fold(0f64, |acc, x| acc.max(x.abs()));
+		if !self.is_symmetric(largest * 1e-12) {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				"Symmetric eigendecomposition allowed only for symmetric matrices".to_owned(),
+			));
+		}
+		let (n, _) = self.get_size();
+		let mut a: Rows = (0..n)
+			.map(|i| (0..n).map(|j| self.get_value(i, j).unwrap()).collect())
+			.collect();
+		let mut v: Rows = (0..n)
+			.map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+			.collect();
+		jacobi_sweeps(&mut a, &mut v, &mut on_iteration)?;
+		let mut order: Vec<usize> = (0..n).collect();
+		order.sort_by(|i, j| a[*i][*i].partial_cmp(&a[*j][*j]).unwrap());
+		let mut vectors = Matrix::zeros(n, n)?;
+		for (col, k) in order.iter().enumerate() {
+			for i in 0..n {
+				vectors.set_value(i, col, v[i][*k])?;
+			}
+		}
+		return Ok((order.iter().map(|k| a[*k][*k]).collect(), vectors));
+	}
+
+	/* Eigendecomposition of a Hermitian matrix H = real + i * imaginary, given
+	by its parts (real symmetric, imaginary antisymmetric). Returns the real
+	eigenvalues in increasing order and the orthonormal eigenvectors as
+	columns, split into real and imaginary parts.
+	H is embedded as the real symmetric M = [real, -imaginary; imaginary, real],
+	which has every eigenvalue of H twice: [x; y] is an eigenvector of M
+	exactly when x + i * y is one of H.
+	*/
+	pub fn eigen_hermitian(
+		real: &Matrix,
+		imaginary: &Matrix,
+	) -> Result<(Vec<f64>, Matrix, Matrix), MathMatrixError> {
+		let (n, cols) = real.get_size();
+		if imaginary.get_size() != (n, cols) {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Real and imaginary parts must have the same size".to_owned(),
+			));
+		}
+		let largest = real
+			.get_data()
+			.iter()
+			.chain(imaginary.get_data().iter())
+			.fold(0f64, |acc, x| acc.max(x.abs()));
+		let antisymmetric = imaginary
+			.multiplied_by_scalar(-1.0)
+			.approx_eq(&imaginary.transposed(), largest * 1e-12);
+		if !real.is_symmetric(largest * 1e-12) || !antisymmetric {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				"Hermitian eigendecomposition allowed only for Hermitian matrices".to_owned(),
+			));
+		}
+		let mut embedded = Matrix::zeros(2 * n, 2 * n)?;
+		for j in 0..n {
+			for i in 0..n {
+				let (re, im) = (real.get_value(i, j)?, imaginary.get_value(i, j)?);
+				embedded.set_value(i, j, re)?;
+				embedded.set_value(i + n, j + n, re)?;
+				embedded.set_value(i + n, j, im)?;
+				embedded.set_value(i, j + n, -im)?;
+			}
+		}
+		let (values, vectors) = embedded.eigen_symmetric()?;
+		/* Each eigenspace of M is twice as large as the one of H: z and i * z both
+		appear. Keep the candidates that are new in the complex sense, with complex
+		Gram-Schmidt against the vectors kept so far.
+		*/
+		let mut kept_values = Vec::with_capacity(n);
+		let mut kept: Vec<(Vec<f64>, Vec<f64>)> = Vec::with_capacity(n);
+		for k in 0..(2 * n) {
+			if kept.len() == n {
+				break;
+			}
+			let mut x: Vec<f64> = (0..n).map(|i| vectors.get_value(i, k).unwrap()).collect();
+			let mut y: Vec<f64> = (n..2 * n)
+				.map(|i| vectors.get_value(i, k).unwrap())
+				.collect();
+			for (wr, wi) in kept.iter() {
+				// c = <w, z> = sum(conj(w_i) * z_i)
+				let mut c = (0.0, 0.0);
+				for i in 0..n {
+					c.0 += wr[i] * x[i] + wi[i] * y[i];
+					c.1 += wr[i] * y[i] - wi[i] * x[i];
+				}
+				for i in 0..n {
+					x[i] -= c.0 * wr[i] - c.1 * wi[i];
+					y[i] -= c.0 * wi[i] + c.1 * wr[i];
+				}
+			}
+			let norm = x.iter().chain(y.iter()).map(|v| v * v).sum::<f64>().sqrt();
+			if norm < 0.5 {
+				continue;
+			}
+			x.iter_mut().chain(y.iter_mut()).for_each(|v| *v /= norm);
+			kept_values.push(values[k]);
+			kept.push((x, y));
+		}
+		let mut vectors_real = Matrix::zeros(n, n)?;
+		let mut vectors_imaginary = Matrix::zeros(n, n)?;
+		for (j, (x, y)) in kept.iter().enumerate() {
+			for i in 0..n {
+				vectors_real.set_value(i, j, x[i])?;
+				vectors_imaginary.set_value(i, j, y[i])?;
+			}
+		}
+		return Ok((kept_values, vectors_real, vectors_imaginary));
+	}
+
+	/* Whether U = real + i * imaginary satisfies U^H * U = I within tolerance,
+	where U^H * U = (R^T * R + I^T * I) + i * (R^T * I - I^T * R)
+	*/
+	pub fn is_unitary(real: &Matrix, imaginary: &Matrix, tolerance: f64) -> bool {
+		let (rows, cols) = real.get_size();
+		if rows != cols || imaginary.get_size() != (rows, cols) {
+			return false;
+		}
+		let (r_t, i_t) = (real.transposed(), imaginary.transposed());
+		let product = |a: &Matrix, b: &Matrix| a.multiplied_by_matrix(b).unwrap();
+		let gram_real = (product(&r_t, real) + product(&i_t, imaginary)).unwrap();
+		let gram_imaginary = (product(&r_t, imaginary) - product(&i_t, real)).unwrap();
+		return gram_real.approx_eq(&Matrix::identity(rows, cols).unwrap(), tolerance)
+			&& gram_imaginary.approx_eq(&Matrix::zeros(rows, cols).unwrap(), tolerance);
+	}
+}
+
+/* Power iteration for the eigenvalue of largest magnitude of a square
+operator, which only needs products A * v. Returns the eigenvalue and a unit
+eigenvector once ||A * v - lambda * v|| <= tolerance * |lambda|. Requires a
+real eigenvalue strictly larger in magnitude than all the others; the error
+shrinks like |lambda_2 / lambda_1| per iteration.
+*/
+pub fn power_iteration(
+	a: &dyn LinearOperator,
+	tolerance: f64,
+	max_iterations: usize,
+) -> Result<(f64, Matrix), MathMatrixError> {
+	let (n, cols) = a.get_size();
+	if n != cols {
+		return Err(MathMatrixError::new(
+			SizeMismatch,
+			"Power iteration requires a square operator".to_owned(),
+		));
+	}
+	// A random start is almost surely not orthogonal to the eigenvector
+	let mut v = Rng::new(0).normal_matrix(n, 1)?.into_vec();
+	let mut norm = v.iter().map(|x| x * x).sum::<f64>().sqrt();
+	v.iter_mut().for_each(|x| *x /= norm);
+	for _ in 0..max_iterations {
+		let av = a
+			.apply(&Matrix::from_vec_unchecked(n, 1, v.clone()))?
+			.into_vec();
+		// Rayleigh quotient, with ||v|| = 1
+		let value: f64 = v.iter().zip(av.iter()).map(|(x, y)| x * y).sum();
+		let residual = v
+			.iter()
+			.zip(av.iter())
+			.map(|(x, y)| (y - value * x).powi(2))
+			.sum::<f64>()
+			.sqrt();
+		if residual <= tolerance * value.abs() {
+			return Ok((value, Matrix::from_vec_unchecked(n, 1, v)));
+		}
+		norm = av.iter().map(|x| x * x).sum::<f64>().sqrt();
+		if norm == 0.0 {
+			return Ok((0.0, Matrix::from_vec_unchecked(n, 1, v)));
+		}
+		v = av.iter().map(|x| x / norm).collect();
+	}
+	return Err(MathMatrixError::new(
+		FailedToConverge,
+		format!(
+			"Power iteration did not converge in {} iterations",
+			max_iterations
+		),
+	));
+}
+
+// Dense matrix as a vector of rows, which the EISPACK loops index as h[i][j]
+type Rows = Vec<Vec<f64>>;
+
+fn from_rows(data: &[Vec<f64>]) -> Result<Matrix, MathMatrixError> {
+	let size = data.len();
+	let mut mat = Matrix::zeros(size, size)?;
+	for (i, row) in data.iter().enumerate() {
+		for (j, value) in row.iter().enumerate() {
+			mat.set_value(i, j, *value)?;
+		}
+	}
+	return Ok(mat);
+}
+
+// Upper Hessenberg H and orthogonal Q with A = Q * H * Q^T, as rows
+fn hessenberg_rows(mat: &Matrix) -> Result<(Rows, Rows), MathMatrixError> {
+	let (rows, cols) = mat.get_size();
+	if rows != cols {
+		return Err(MathMatrixError::new(
+			OperationNotPermitted,
+			"Eigenvalues allowed only for square matrices".to_owned(),
+		));
+	}
+	let n = rows;
+	let mut h: Vec<Vec<f64>> = (0..n)
+		.map(|i| (0..n).map(|j| mat.get_value(i, j).unwrap()).collect())
+		.collect();
+	let mut ort = vec![0f64; n];
+	let high = n - 1;
+	for m in 1..high {
+		let mut scale = 0.0;
+		for i in m..=high {
+			scale += h[i][m - 1].abs();
+		}
+		if scale == 0.0 {
+			continue;
+		}
+		// Householder vector u = ort[m..=high] with H = (I - u * u^T / s) * H * (I - u * u^T / s)
+		let mut s = 0.0;
+		for i in (m..=high).rev() {
+			ort[i] = h[i][m - 1] / scale;
+			s += ort[i] * ort[i];
+		}
+		let mut g = s.sqrt();
+		if ort[m] > 0.0 {
+			g = -g;
+		}
+		s -= ort[m] * g;
+		ort[m] -= g;
+		for j in m..n {
+			let mut f = 0.0;
+			for i in (m..=high).rev() {
+				f += ort[i] * h[i][j];
+			}
+			f /= s;
+			for i in m..=high {
+				h[i][j] -= f * ort[i];
+			}
+		}
+		for i in 0..=high {
+			let mut f = 0.0;
+			for j in (m..=high).rev() {
+				f += ort[j] * h[i][j];
+			}
+			f /= s;
+			for j in m..=high {
+				h[i][j] -= f * ort[j];
+			}
+		}
+		ort[m] *= scale;
+		h[m][m - 1] = scale * g;
+	}
+
+	// Accumulate the transformations, whose vectors are stored below the subdiagonal
+	let mut q: Vec<Vec<f64>> = (0..n)
+		.map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+		.collect();
+	for m in (1..high).rev() {
+		if h[m][m - 1] == 0.0 {
+			continue;
+		}
+		for i in (m + 1)..=high {
+			ort[i] = h[i][m - 1];
+		}
+		for j in m..=high {
+			let mut g = 0.0;
+			for i in m..=high {
+				g += ort[i] * q[i][j];
+			}
+			// Double division avoids possible underflow
+			g = (g / ort[m]) / h[m][m - 1];
+			for i in m..=high {
+				q[i][j] += g * ort[i];
+			}
+		}
+	}
+	for i in 2..n {
+		for j in 0..(i - 1) {
+			h[i][j] = 0.0;
+		}
+	}
+	return Ok((h, q));
+}
+
+// Rotate A = V^T * A * V until it is diagonal, one (p, q) pair at a time
+fn jacobi_sweeps(
+	a: &mut [Vec<f64>],
+	v: &mut [Vec<f64>],
+	on_iteration: OnIteration,
+) -> Result<(), MathMatrixError> {
+	let n = a.len();
+	let total: f64 = a.iter().flatten().map(|x| x * x).sum();
+	for sweep in 0..JACOBI_MAX_SWEEPS {
+		let mut off_diagonal = 0.0;
+		for p in 0..n {
+			for q in (p + 1)..n {
+				off_diagonal += 2.0 * a[p][q] * a[p][q];
+			}
+		}
+		if off_diagonal <= f64::EPSILON * f64::EPSILON * total {
+			return Ok(());
+		}
+		if on_iteration(sweep, off_diagonal.sqrt()).is_break() {
+			return Err(stopped("Jacobi method"));
+		}
+		for p in 0..n {
+			for q in (p + 1)..n {
+				if a[p][q] == 0.0 {
+					continue;
+				}
+				// Rotation angle that zeroes a[p][q]
+				let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+				let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+				let c = 1.0 / (t * t + 1.0).sqrt();
+				let s = t * c;
+				for row in a.iter_mut() {
+					let (x, y) = (row[p], row[q]);
+					row[p] = c * x - s * y;
+					row[q] = s * x + c * y;
+				}
+				for k in 0..n {
+					let (x, y) = (a[p][k], a[q][k]);
+					a[p][k] = c * x - s * y;
+					a[q][k] = s * x + c * y;
+				}
+				for row in v.iter_mut() {
+					let (x, y) = (row[p], row[q]);
+					row[p] = c * x - s * y;
+					row[q] = s * x + c * y;
+				}
+			}
+		}
+	}
+	return Err(MathMatrixError::new(
+		FailedToConverge,
+		format!(
+			"Jacobi method did not converge in {} sweeps",
+			JACOBI_MAX_SWEEPS
+		),
+	));
+}
+
+/* Reduce the Hessenberg H to real Schur form in place, accumulating the
+rotations in Q. Returns the eigenvalues as (real parts, imaginary parts).
+*/
+fn schur_iterations(
+	h: &mut [Vec<f64>],
+	q: &mut [Vec<f64>],
+	on_iteration: OnIteration,
+) -> Result<(Vec<f64>, Vec<f64>), MathMatrixError> {
+	let nn = h.len();
+	let mut d = vec![0f64; nn];
+	let mut e = vec![0f64; nn];
+	let eps = f64::EPSILON;
+	let mut exshift = 0.0;
+	let mut norm = 0.0;
+	for i in 0..nn {
+		for j in i.saturating_sub(1)..nn {
+			norm += h[i][j].abs();
+		}
+	}
+	let max_iterations = 100 * nn;
+	let mut total_iterations = 0;
+	let mut iter = 0;
+	// Eigenvalues n + 1.. have been found
+	let mut remaining = nn;
+	while remaining > 0 {
+		let n = remaining - 1;
+		// Look for a single small subdiagonal element
+		let mut l = n;
+		while l > 0 {
+			let mut s = h[l - 1][l - 1].abs() + h[l][l].abs();
+			if s == 0.0 {
+				s = norm;
+			}
+			if h[l][l - 1].abs() < eps * s {
+				break;
+			}
+			l -= 1;
+		}
+
+		if l == n {
+			// One root found
+			h[n][n] += exshift;
+			d[n] = h[n][n];
+			e[n] = 0.0;
+			remaining -= 1;
+			iter = 0;
+		} else if l + 1 == n {
+			// Two roots found
+			let w = h[n][n - 1] * h[n - 1][n];
+			let p = (h[n - 1][n - 1] - h[n][n]) / 2.0;
+			let q_disc = p * p + w;
+			let mut z = q_disc.abs().sqrt();
+			h[n][n] += exshift;
+			h[n - 1][n - 1] += exshift;
+			let x = h[n][n];
+			if q_disc >= 0.0 {
+				// Real pair: rotate the 2x2 block to upper triangular form
+				z = if p >= 0.0 { p + z } else { p - z };
+				d[n - 1] = x + z;
+				d[n] = d[n - 1];
+				if z != 0.0 {
+					d[n] = x - w / z;
+				}
+				e[n - 1] = 0.0;
+				e[n] = 0.0;
+				let x = h[n][n - 1];
+				let s = x.abs() + z.abs();
+				let (mut p, mut q_rot) = (x / s, z / s);
+				let r = (p * p + q_rot * q_rot).sqrt();
+				p /= r;
+				q_rot /= r;
+				for j in (n - 1)..nn {
+					let z = h[n - 1][j];
+					h[n - 1][j] = q_rot * z + p * h[n][j];
+					h[n][j] = q_rot * h[n][j] - p * z;
+				}
+				for row in h.iter_mut().take(n + 1) {
+					let z = row[n - 1];
+					row[n - 1] = q_rot * z + p * row[n];
+					row[n] = q_rot * row[n] - p * z;
+				}
+				for row in q.iter_mut() {
+					let z = row[n - 1];
+					row[n - 1] = q_rot * z + p * row[n];
+					row[n] = q_rot * row[n] - p * z;
+				}
+			} else {
+				// Complex pair
+				d[n - 1] = x + p;
+				d[n] = x + p;
+				e[n - 1] = z;
+				e[n] = -z;
+			}
+			remaining -= 2;
+			iter = 0;
+		} else {
+			if on_iteration(total_iterations, h[n][n - 1].abs()).is_break() {
+				return Err(stopped("QR algorithm"));
+			}
+			total_iterations += 1;
+			if total_iterations > max_iterations {
+				return Err(MathMatrixError::new(
+					FailedToConverge,
+					format!(
+						"QR algorithm did not converge in {} iterations",
+						max_iterations
+					),
+				));
+			}
+			// Form the shift
+			let mut x = h[n][n];
+			let mut y = h[n - 1][n - 1];
+			let mut w = h[n][n - 1] * h[n - 1][n];
+			// Wilkinson's original ad hoc shift
+			if iter == 10 {
+				exshift += x;
+				for i in 0..=n {
+					h[i][i] -= x;
+				}
+				let s = h[n][n - 1].abs() + h[n - 1][n - 2].abs();
+				x = 0.75 * s;
+				y = x;
+				w = -0.4375 * s * s;
+			}
+			// MATLAB's ad hoc shift
+			if iter == 30 {
+				let mut s = (y - x) / 2.0;
+				s = s * s + w;
+				if s > 0.0 {
+					s = s.sqrt();
+					if y < x {
+						s = -s;
+					}
+					s = x - w / ((y - x) / 2.0 + s);
+					for i in 0..=n {
+						h[i][i] -= s;
+					}
+					exshift += s;
+					x = 0.964;
+					y = x;
+					w = x;
+				}
+			}
+			iter += 1;
+
+			// Look for two consecutive small subdiagonal elements
+			let mut m = n - 2;
+			let (mut p, mut q_h, mut r);
+			loop {
+				let z = h[m][m];
+				r = x - z;
+				let s = y - z;
+				p = (r * s - w) / h[m + 1][m] + h[m][m + 1];
+				q_h = h[m + 1][m + 1] - z - r - s;
+				r = h[m + 2][m + 1];
+				let s = p.abs() + q_h.abs() + r.abs();
+				p /= s;
+				q_h /= s;
+				r /= s;
+				if m == l {
+					break;
+				}
+				if h[m][m - 1].abs() * (q_h.abs() + r.abs())
+					< eps * (p.abs() * (h[m - 1][m - 1].abs() + z.abs() + h[m + 1][m + 1].abs()))
+				{
+					break;
+				}
+				m -= 1;
+			}
+			for i in (m + 2)..=n {
+				h[i][i - 2] = 0.0;
+				if i > m + 2 {
+					h[i][i - 3] = 0.0;
+				}
+			}
+
+			// Double QR step involving rows l..=n and columns m..=n
+			for k in m..n {
+				let notlast = k != n - 1;
+				if k != m {
+					p = h[k][k - 1];
+					q_h = h[k + 1][k - 1];
+					r = if notlast { h[k + 2][k - 1] } else { 0.0 };
+					x = p.abs() + q_h.abs() + r.abs();
+					if x == 0.0 {
+						continue;
+					}
+					p /= x;
+					q_h /= x;
+					r /= x;
+				}
+				let mut s = (p * p + q_h * q_h + r * r).sqrt();
+				if p < 0.0 {
+					s = -s;
+				}
+				if s == 0.0 {
+					continue;
+				}
+				if k != m {
+					h[k][k - 1] = -s * x;
+				} else if l != m {
+					h[k][k - 1] = -h[k][k - 1];
+				}
+				p += s;
+				x = p / s;
+				y = q_h / s;
+				let z = r / s;
+				q_h /= p;
+				r /= p;
+				// Row modification
+				for j in k..nn {
+					let mut p = h[k][j] + q_h * h[k + 1][j];
+					if notlast {
+						p += r * h[k + 2][j];
+						h[k + 2][j] -= p * z;
+					}
+					h[k][j] -= p * x;
+					h[k + 1][j] -= p * y;
+				}
+				// Column modification
+				for row in h.iter_mut().take(n.min(k + 3) + 1) {
+					let mut p = x * row[k] + y * row[k + 1];
+					if notlast {
+						p += z * row[k + 2];
+						row[k + 2] -= p * r;
+					}
+					row[k] -= p;
+					row[k + 1] -= p * q_h;
+				}
+				// Accumulate transformations
+				for row in q.iter_mut() {
+					let mut p = x * row[k] + y * row[k + 1];
+					if notlast {
+						p += z * row[k + 2];
+						row[k + 2] -= p * r;
+					}
+					row[k] -= p;
+					row[k + 1] -= p * q_h;
+				}
+			}
+		}
+	}
+	return Ok((d, e));
+}
+
+/* Solve for the eigenvectors of the quasi-triangular T (stored in h, which is
+overwritten) and map them back to eigenvectors of A, stored in q.
+*/
+fn back_substitute(h: &mut [Vec<f64>], q: &mut [Vec<f64>], d: &[f64], e: &[f64]) {
+	let nn = h.len();
+	let eps = f64::EPSILON;
+	let mut norm = 0.0;
+	for i in 0..nn {
+		for j in i.saturating_sub(1)..nn {
+			norm += h[i][j].abs();
+		}
+	}
+	if norm == 0.0 {
+		return;
+	}
+	// Carried from the second row of a 2x2 block to the first
+	let (mut z, mut r, mut s) = (0.0, 0.0, 0.0);
+	for n in (0..nn).rev() {
+		let p = d[n];
+		let q_n = e[n];
+		if q_n == 0.0 {
+			// Real vector
+			let mut l = n;
+			h[n][n] = 1.0;
+			for i in (0..n).rev() {
+				let w = h[i][i] - p;
+				let mut ra = 0.0;
+				for j in l..=n {
+					ra += h[i][j] * h[j][n];
+				}
+				if e[i] < 0.0 {
+					z = w;
+					s = ra;
+					continue;
+				}
+				l = i;
+				if e[i] == 0.0 {
+					h[i][n] = if w != 0.0 {
+						-ra / w
+					} else {
+						-ra / (eps * norm)
+					};
+				} else {
+					// Solve the real 2x2 system
+					let x = h[i][i + 1];
+					let y = h[i + 1][i];
+					let q = (d[i] - p) * (d[i] - p) + e[i] * e[i];
+					let t = (x * s - z * ra) / q;
+					h[i][n] = t;
+					h[i + 1][n] = if x.abs() > z.abs() {
+						(-ra - w * t) / x
+					} else {
+						(-s - y * t) / z
+					};
+				}
+				// Overflow control
+				let t = h[i][n].abs();
+				if (eps * t) * t > 1.0 {
+					for row in h.iter_mut().take(n + 1).skip(i) {
+						row[n] /= t;
+					}
+				}
+			}
+		} else if q_n < 0.0 {
+			// Complex vector, stored in columns n - 1 (real) and n (imaginary)
+			let mut l = n - 1;
+			if h[n][n - 1].abs() > h[n - 1][n].abs() {
+				h[n - 1][n - 1] = q_n / h[n][n - 1];
+				h[n - 1][n] = -(h[n][n] - p) / h[n][n - 1];
+			} else {
+				let (re, im) = complex_div(0.0, -h[n - 1][n], h[n - 1][n - 1] - p, q_n);
+				h[n - 1][n - 1] = re;
+				h[n - 1][n] = im;
+			}
+			h[n][n - 1] = 0.0;
+			h[n][n] = 1.0;
+			for i in (0..(n - 1)).rev() {
+				let mut ra = 0.0;
+				let mut sa = 0.0;
+				for j in l..=n {
+					ra += h[i][j] * h[j][n - 1];
+					sa += h[i][j] * h[j][n];
+				}
+				let w = h[i][i] - p;
+				if e[i] < 0.0 {
+					z = w;
+					r = ra;
+					s = sa;
+					continue;
+				}
+				l = i;
+				if e[i] == 0.0 {
+					let (re, im) = complex_div(-ra, -sa, w, q_n);
+					h[i][n - 1] = re;
+					h[i][n] = im;
+				} else {
+					// Solve the complex 2x2 system
+					let x = h[i][i + 1];
+					let y = h[i + 1][i];
+					let mut vr = (d[i] - p) * (d[i] - p) + e[i] * e[i] - q_n * q_n;
+					let vi = (d[i] - p) * 2.0 * q_n;
+					if vr == 0.0 && vi == 0.0 {
+						vr = eps * norm * (w.abs() + q_n.abs() + x.abs() + y.abs() + z.abs());
+					}
+					let (re, im) =
+						complex_div(x * r - z * ra + q_n * sa, x * s - z * sa - q_n * ra, vr, vi);
+					h[i][n - 1] = re;
+					h[i][n] = im;
+					if x.abs() > z.abs() + q_n.abs() {
+						h[i + 1][n - 1] = (-ra - w * h[i][n - 1] + q_n * h[i][n]) / x;
+						h[i + 1][n] = (-sa - w * h[i][n] - q_n * h[i][n - 1]) / x;
+					} else {
+						let (re, im) = complex_div(-r - y * h[i][n - 1], -s - y * h[i][n], z, q_n);
+						h[i + 1][n - 1] = re;
+						h[i + 1][n] = im;
+					}
+				}
+				// Overflow control
+				let t = h[i][n - 1].abs().max(h[i][n].abs());
+				if (eps * t) * t > 1.0 {
+					for row in h.iter_mut().take(n + 1).skip(i) {
+						row[n - 1] /= t;
+						row[n] /= t;
+					}
+				}
+			}
+		}
+	}
+	// Back transformation: eigenvectors of A = Q * eigenvectors of T
+	for j in (0..nn).rev() {
+		for i in 0..nn {
+			let mut sum = 0.0;
+			for k in 0..=j {
+				sum += q[i][k] * h[k][j];
+			}
+			q[i][j] = sum;
+		}
+	}
+}
+
+fn stopped(algorithm: &str) -> MathMatrixError {
+	return MathMatrixError::new(Cancelled, format!("{} was stopped", algorithm));
+}
+
+// (xr + i * xi) / (yr + i * yi)
+fn complex_div(xr: f64, xi: f64, yr: f64, yi: f64) -> (f64, f64) {
+	if yr.abs() > yi.abs() {
+		let r = yi / yr;
+		let d = yr + r * yi;
+		return ((xr + r * xi) / d, (xi - r * xr) / d);
+	}
+	let r = yr / yi;
+	let d = yi + r * yr;
+	return ((r * xr + xi) / d, (r * xi - xr) / d);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Check A * v = lambda * v for every eigenpair, including complex ones
+	fn assert_eigenpairs(mat: &Matrix, eigen: &Eigen) {
+		let n = eigen.real.len();
+		let av = mat.multiplied_by_matrix(&eigen.vectors).unwrap();
+		let v = &eigen.vectors;
+		let mut k = 0;
+		while k < n {
+			let (re, im) = (eigen.real[k], eigen.imaginary[k]);
+			if im == 0.0 {
+				for i in 0..n {
+					let expected = re * v.get_value(i, k).unwrap();
+					assert!((av.get_value(i, k).unwrap() - expected).abs() < 1e-9);
+				}
+				k += 1;
+			} else {
+				// A * (x + iy) = (re + i im) * (x + iy)
+				for i in 0..n {
+					let (x, y) = (v.get_value(i, k).unwrap(), v.get_value(i, k + 1).unwrap());
+					assert!((av.get_value(i, k).unwrap() - (re * x - im * y)).abs() < 1e-9);
+					assert!((av.get_value(i, k + 1).unwrap() - (re * y + im * x)).abs() < 1e-9);
+				}
+				k += 2;
+			}
+		}
+	}
+
+	#[test]
+	fn test_eigen_real() {
+		let mat = Matrix::new(3, 3, vec![2.0, 0.0, 0.0, 1.0, 3.0, 0.0, 4.0, 5.0, 6.0]).unwrap();
+		let eigen = mat.eigen().unwrap();
+		let mut real = eigen.real.clone();
+		real.sort_by(|a, b| a.partial_cmp(b).unwrap());
+		for (value, expected) in real.iter().zip([2.0, 3.0, 6.0].iter()) {
+			assert!((value - expected).abs() < 1e-12);
+		}
+		assert!(eigen.imaginary.iter().all(|im| *im == 0.0));
+		assert_eigenpairs(&mat, &eigen);
+	}
+
+	#[test]
+	fn test_eigen_complex_pair() {
+		// Rotation by 90 degrees scaled by 2: eigenvalues +-2i, plus 1
+		let mat = Matrix::new(3, 3, vec![0.0, 2.0, 0.0, -2.0, 0.0, 0.0, 0.0, 0.0, 1.0]).unwrap();
+		let (real, imaginary) = mat.eigenvalues().unwrap();
+		let mut imaginary_sorted = imaginary.clone();
+		imaginary_sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+		assert!((imaginary_sorted[0] + 2.0).abs() < 1e-12);
+		assert!((imaginary_sorted[2] - 2.0).abs() < 1e-12);
+		assert!((real.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+		assert_eigenpairs(&mat, &mat.eigen().unwrap());
+	}
+
+	#[test]
+	fn test_eigen_general() {
+		let data = (0..36)
+			.map(|i| ((i * 37 % 11) as f64 - 5.0) / 3.0)
+			.collect();
+		let mat = Matrix::new(6, 6, data).unwrap();
+		let eigen = mat.eigen().unwrap();
+		assert_eigenpairs(&mat, &eigen);
+		assert!(Matrix::zeros(2, 3).unwrap().eigen().is_err());
+	}
+
+	#[test]
+	fn test_eigen_symmetric() {
+		let mat = Matrix::new(
+			4,
+			4,
+			vec![
+				4.0, 1.0, -2.0, 2.0, 1.0, 2.0, 0.0, 1.0, -2.0, 0.0, 3.0, -2.0, 2.0, 1.0, -2.0, -1.0,
+			],
+		)
+		.unwrap();
+		assert!(mat.is_symmetric(0.0));
+		let (values, vectors) = mat.eigen_symmetric().unwrap();
+		assert!(values.windows(2).all(|pair| pair[0] <= pair[1]));
+		let identity = Matrix::identity(4, 4).unwrap();
+		let orthogonality = vectors.transposed().multiplied_by_matrix(&vectors).unwrap();
+		assert!(orthogonality.approx_eq(&identity, 1e-12));
+		let mut diagonal = Matrix::zeros(4, 4).unwrap();
+		for (i, value) in values.iter().enumerate() {
+			diagonal.set_value(i, i, *value).unwrap();
+		}
+		let reconstructed = vectors
+			.multiplied_by_matrix(&diagonal)
+			.unwrap()
+			.multiplied_by_matrix(&vectors.transposed())
+			.unwrap();
+		assert!(reconstructed.approx_eq(&mat, 1e-12));
+		// Same spectrum as the general algorithm
+		let mut real = mat.eigenvalues().unwrap().0;
+		real.sort_by(|a, b| a.partial_cmp(b).unwrap());
+		for (a, b) in real.iter().zip(values.iter()) {
+			assert!((a - b).abs() < 1e-10);
+		}
+	}
+
+	#[test]
+	fn test_eigen_symmetric_rejects_asymmetric() {
+		let mat = Matrix::new(2, 2, vec![1.0, 2.0, 3.0, 4.0]).unwrap();
+		assert!(!mat.is_symmetric(0.5));
+		assert!(mat.is_symmetric(1.0));
+		assert_eq!(
+			mat.eigen_symmetric().unwrap_err().get_kind(),
+			"OperationNotPermitted"
+		);
+	}
+
+	#[test]
+	fn test_eigen_progress() {
+		let data = (0..25)
+			.map(|i| ((i * 37 % 11) as f64 - 5.0) / 3.0)
+			.collect();
+		let mat = Matrix::new(5, 5, data).unwrap();
+		let mut iterations = Vec::new();
+		mat.eigen_with_progress(|iteration, residual| {
+			assert!(residual >= 0.0);
+			iterations.push(iteration);
+			return ControlFlow::Continue(());
+		})
+		.unwrap();
+		assert!(!iterations.is_empty());
+		assert_eq!(iterations, (0..iterations.len()).collect::<Vec<usize>>());
+		let err = mat
+			.eigen_with_progress(|_, _| ControlFlow::Break(()))
+			.unwrap_err();
+		assert_eq!(err.get_kind(), "Cancelled");
+
+		let symmetric = (mat.clone() + mat.transposed()).unwrap();
+		let mut residuals = Vec::new();
+		symmetric
+			.eigen_symmetric_with_progress(|_, residual| {
+				residuals.push(residual);
+				return ControlFlow::Continue(());
+			})
+			.unwrap();
+		assert!(residuals.windows(2).all(|pair| pair[1] < pair[0]));
+	}
+
+	#[test]
+	fn test_hessenberg() {
+		let data = (0..25)
+			.map(|i| ((i * 37 % 11) as f64 - 5.0) / 3.0)
+			.collect();
+		let mat = Matrix::new(5, 5, data).unwrap();
+		let (h, q) = mat.hessenberg().unwrap();
+		for j in 0..5 {
+			for i in (j + 2)..5 {
+				assert_eq!(h.get_value(i, j).unwrap(), 0.0);
+			}
+		}
+		let identity = Matrix::identity(5, 5).unwrap();
+		assert!(q
+			.transposed()
+			.multiplied_by_matrix(&q)
+			.unwrap()
+			.approx_eq(&identity, 1e-12));
+		let reconstructed = q
+			.multiplied_by_matrix(&h)
+			.unwrap()
+			.multiplied_by_matrix(&q.transposed())
+			.unwrap();
+		assert!(reconstructed.approx_eq(&mat, 1e-12));
+	}
+
+	#[test]
+	fn test_schur() {
+		let data = (0..36)
+			.map(|i| ((i * 37 % 11) as f64 - 5.0) / 3.0)
+			.collect();
+		let mat = Matrix::new(6, 6, data).unwrap();
+		let (real, imaginary) = mat.eigenvalues().unwrap();
+		// The test matrix has complex pairs as well as real eigenvalues
+		assert!(imaginary.iter().any(|im| *im != 0.0));
+		assert!(imaginary.contains(&0.0));
+		let (q, t) = mat.schur().unwrap();
+		let identity = Matrix::identity(6, 6).unwrap();
+		assert!(q
+			.transposed()
+			.multiplied_by_matrix(&q)
+			.unwrap()
+			.approx_eq(&identity, 1e-12));
+		let reconstructed = q
+			.multiplied_by_matrix(&t)
+			.unwrap()
+			.multiplied_by_matrix(&q.transposed())
+			.unwrap();
+		assert!(reconstructed.approx_eq(&mat, 1e-12));
+		let mut k = 0;
+		while k < 6 {
+			for i in (k + 2)..6 {
+				assert_eq!(t.get_value(i, k).unwrap(), 0.0);
+			}
+			if imaginary[k] == 0.0 {
+				// 1x1 block holding a real eigenvalue
+				assert!((t.get_value(k, k).unwrap() - real[k]).abs() < 1e-10);
+				if k + 1 < 6 {
+					assert_eq!(t.get_value(k + 1, k).unwrap(), 0.0);
+				}
+				k += 1;
+			} else {
+				// 2x2 block whose trace is twice the real part
+				let trace = t.get_value(k, k).unwrap() + t.get_value(k + 1, k + 1).unwrap();
+				assert!((trace - 2.0 * real[k]).abs() < 1e-10);
+				k += 2;
+			}
+		}
+	}
+
+	#[test]
+	fn test_eigen_hermitian() {
+		// [1, i, 0; -i, 1, 0; 0, 0, 2] has eigenvalues 0, 2, 2
+		let real = Matrix::new(3, 3, vec![1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 2.0]).unwrap();
+		let imaginary =
+			Matrix::new(3, 3, vec![0.0, -1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0]).unwrap();
+		let (values, vectors_real, vectors_imaginary) =
+			Matrix::eigen_hermitian(&real, &imaginary).unwrap();
+		assert_eq!(values.len(), 3);
+		for (value, expected) in values.iter().zip([0.0, 2.0, 2.0].iter()) {
+			assert!((value - expected).abs() < 1e-12);
+		}
+		assert!(Matrix::is_unitary(&vectors_real, &vectors_imaginary, 1e-12));
+		// H * (x + iy) = (A x - B y) + i (B x + A y) = lambda * (x + iy)
+		let product = |a: &Matrix, b: &Matrix| a.multiplied_by_matrix(b).unwrap();
+		let hz_real =
+			(product(&real, &vectors_real) - product(&imaginary, &vectors_imaginary)).unwrap();
+		let hz_imaginary =
+			(product(&imaginary, &vectors_real) + product(&real, &vectors_imaginary)).unwrap();
+		for j in 0..3 {
+			for i in 0..3 {
+				let expected = values[j] * vectors_real.get_value(i, j).unwrap();
+				assert!((hz_real.get_value(i, j).unwrap() - expected).abs() < 1e-12);
+				let expected = values[j] * vectors_imaginary.get_value(i, j).unwrap();
+				assert!((hz_imaginary.get_value(i, j).unwrap() - expected).abs() < 1e-12);
+			}
+		}
+		// Not Hermitian: imaginary part is symmetric
+		assert!(Matrix::eigen_hermitian(&real, &real).is_err());
+		assert!(!Matrix::is_unitary(&real, &imaginary, 1e-12));
+	}
+
+	#[test]
+	fn test_power_iteration() {
+		let mat = Matrix::new(3, 3, vec![4.0, 1.0, 0.0, 1.0, 3.0, 1.0, 0.0, 1.0, 2.0]).unwrap();
+		let (values, _) = mat.eigen_symmetric().unwrap();
+		let (value, vector) = power_iteration(&mat, 1e-10, 1000).unwrap();
+		assert!((value - values[2]).abs() < 1e-9);
+		let av = mat.multiplied_by_matrix(&vector).unwrap();
+		assert!(av.approx_eq(&vector.multiplied_by_scalar(value), 1e-9));
+		// Rotation: no dominant real eigenvalue
+		let rotation = Matrix::new(2, 2, vec![0.0, 1.0, -1.0, 0.0]).unwrap();
+		assert!(power_iteration(&rotation, 1e-10, 100).is_err());
+	}
+}
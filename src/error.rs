@@ -5,6 +5,11 @@ pub enum MathMatrixErrorKind {
 	SizeMismatch,
 	FailedToDecompose,
 	OperationNotPermitted,
+	FailedToConverge,
+	NotPositiveDefinite,
+	Cancelled,
+	Overflow,
+	FailedToWrite,
 }
 
 #[derive(Debug)]
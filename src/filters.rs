@@ -0,0 +1,591 @@
+use super::algorithms::CholeskyDecomposition;
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+use super::random::Rng;
+
+/* Linear Kalman filter for x_k+1 = F * x_k + w_k, z_k = H * x_k + v_k with
+w ~ N(0, Q) and v ~ N(0, R). Keeps the Nx1 state estimate and its NxN
+covariance P.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct KalmanFilter {
+	state: Matrix,
+	covariance: Matrix,
+}
+
+impl KalmanFilter {
+	pub fn new(state: Matrix, covariance: Matrix) -> Result<Self, MathMatrixError> {
+		check_state(&state, &covariance)?;
+		return Ok(Self { state, covariance });
+	}
+
+	pub fn get_state(&self) -> &Matrix {
+		return &self.state;
+	}
+
+	pub fn get_covariance(&self) -> &Matrix {
+		return &self.covariance;
+	}
+
+	// x = F * x, P = F * P * F^T + Q
+	pub fn predict(&mut self, f: &Matrix, q: &Matrix) -> Result<(), MathMatrixError> {
+		let state = f.multiplied_by_matrix(&self.state)?;
+		let covariance = (f
+			.multiplied_by_matrix(&self.covariance)?
+			.multiplied_by_matrix(&f.transposed())?
+			+ q.clone())?;
+		check_state(&state, &covariance)?;
+		self.state = state;
+		self.covariance = covariance;
+		return Ok(());
+	}
+
+	/* With S = H * P * H^T + R and gain K = P * H^T * S^-1:
+	x = x + K * (z - H * x) and P = P - K * S * K^T
+	*/
+	pub fn update(&mut self, h: &Matrix, r: &Matrix, z: &Matrix) -> Result<(), MathMatrixError> {
+		let innovation = (z.clone() - h.multiplied_by_matrix(&self.state)?)?;
+		let ph_t = self.covariance.multiplied_by_matrix(&h.transposed())?;
+		let s = (h.multiplied_by_matrix(&ph_t)? + r.clone())?;
+		let gain = s.solve(&ph_t.transposed())?.transposed();
+		let correction = gain
+			.multiplied_by_matrix(&s)?
+			.multiplied_by_matrix(&gain.transposed())?;
+		self.state = (self.state.clone() + gain.multiplied_by_matrix(&innovation)?)?;
+		self.covariance = (self.covariance.clone() - correction)?;
+		return Ok(());
+	}
+}
+
+/* Kalman filter in square-root form: P is kept as its Cholesky factor
+S with P = S * S^T, so it stays symmetric positive definite by construction
+instead of drifting away from it through rounding over long runs. Both steps
+work on S with rank-1 Cholesky updates and downdates; Q and R must be
+positive definite.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct SquareRootKalmanFilter {
+	state: Matrix,
+	factor: CholeskyDecomposition,
+}
+
+impl SquareRootKalmanFilter {
+	pub fn new(state: Matrix, covariance: &Matrix) -> Result<Self, MathMatrixError> {
+		check_state(&state, covariance)?;
+		return Ok(Self {
+			state,
+			factor: covariance.cholesky_decomposition()?,
+		});
+	}
+
+	pub fn get_state(&self) -> &Matrix {
+		return &self.state;
+	}
+
+	pub fn get_factor(&self) -> Matrix {
+		return self.factor.get_l();
+	}
+
+	// P = S * S^T
+	pub fn get_covariance(&self) -> Result<Matrix, MathMatrixError> {
+		let s = self.factor.get_l();
+		return s.multiplied_by_matrix(&s.transposed());
+	}
+
+	// F * P * F^T + Q = Q + sum_k (F * s_k) * (F * s_k)^T over the columns s_k of S
+	pub fn predict(&mut self, f: &Matrix, q: &Matrix) -> Result<(), MathMatrixError> {
+		let state = f.multiplied_by_matrix(&self.state)?;
+		let fs = f.multiplied_by_matrix(&self.factor.get_l())?;
+		check_state(&state, q)?;
+		let mut factor = q.cholesky_decomposition()?;
+		let (rows, cols) = fs.get_size();
+		for k in 0..cols {
+			factor.update(&fs.get_block(0, k, rows, 1)?)?;
+		}
+		self.state = state;
+		self.factor = factor;
+		return Ok(());
+	}
+
+	/* P - K * S_z * K^T with S_z = H * P * H^T + R = L * L^T is
+	P - U * U^T for U = K * L = P * H^T * L^-T: one downdate per column of U
+	*/
+	pub fn update(&mut self, h: &Matrix, r: &Matrix, z: &Matrix) -> Result<(), MathMatrixError> {
+		let innovation = (z.clone() - h.multiplied_by_matrix(&self.state)?)?;
+		let ph_t = self
+			.get_covariance()?
+			.multiplied_by_matrix(&h.transposed())?;
+		let s = (h.multiplied_by_matrix(&ph_t)? + r.clone())?;
+		let l = s.cholesky()?;
+		// U^T = L^-1 * (P * H^T)^T
+		let u = l.solve_lower_triangular(&ph_t.transposed())?.transposed();
+		let gain = l
+			.transposed()
+			.solve_upper_triangular(&u.transposed())?
+			.transposed();
+		let mut factor = self.factor.clone();
+		let (rows, cols) = u.get_size();
+		for k in 0..cols {
+			factor.downdate(&u.get_block(0, k, rows, 1)?)?;
+		}
+		self.state = (self.state.clone() + gain.multiplied_by_matrix(&innovation)?)?;
+		self.factor = factor;
+		return Ok(());
+	}
+}
+
+/* Scaling of the unscented transform (Julier and Uhlmann, in the scaled
+form of Van der Merwe): alpha sets the spread of the sigma points around
+the mean, beta folds in prior knowledge of the distribution (2 is optimal
+for Gaussians) and kappa is a secondary spread parameter, usually 0.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnscentedParams {
+	pub alpha: f64,
+	pub beta: f64,
+	pub kappa: f64,
+}
+
+impl Default for UnscentedParams {
+	fn default() -> Self {
+		return Self {
+			alpha: 1e-3,
+			beta: 2.0,
+			kappa: 0.0,
+		};
+	}
+}
+
+// 2N + 1 sigma points, one per column, with their weights
+#[derive(Debug, Clone, PartialEq)]
+pub struct SigmaPoints {
+	pub points: Matrix,
+	pub mean_weights: Vec<f64>,
+	pub covariance_weights: Vec<f64>,
+}
+
+/* Sigma points mean and mean +- columns of sqrt(N + lambda) * L for
+covariance = L * L^T, with lambda = alpha^2 * (N + kappa) - N. Their weighted
+mean and covariance reproduce `mean` and `covariance` exactly.
+*/
+pub fn unscented_sigma_points(
+	mean: &Matrix,
+	covariance: &Matrix,
+	params: &UnscentedParams,
+) -> Result<SigmaPoints, MathMatrixError> {
+	check_state(mean, covariance)?;
+	let n = mean.get_size().0;
+	let lambda = params.alpha.powi(2) * (n as f64 + params.kappa) - n as f64;
+	let spread = covariance
+		.cholesky()?
+		.multiplied_by_scalar((n as f64 + lambda).sqrt());
+	let (center, l) = (mean.get_data(), spread.get_data());
+	let mut data = center.clone();
+	for sign in [1.0, -1.0].iter() {
+		for column in l.chunks(n) {
+			data.extend(center.iter().zip(column.iter()).map(|(m, s)| m + sign * s));
+		}
+	}
+	let weight = 1.0 / (2.0 * (n as f64 + lambda));
+	let mut mean_weights = vec![weight; 2 * n + 1];
+	mean_weights[0] = lambda / (n as f64 + lambda);
+	let mut covariance_weights = mean_weights.clone();
+	covariance_weights[0] += 1.0 - params.alpha.powi(2) + params.beta;
+	return Ok(SigmaPoints {
+		points: Matrix::new(n, 2 * n + 1, data)?,
+		mean_weights,
+		covariance_weights,
+	});
+}
+
+// sum_k w_k * x_k over the columns x_k of `points`
+fn weighted_mean(points: &Matrix, weights: &[f64]) -> Result<Matrix, MathMatrixError> {
+	let weights = Matrix::new(weights.len(), 1, weights.to_vec())?;
+	return points.multiplied_by_matrix(&weights);
+}
+
+// sum_k w_k * (x_k - x_mean) * (y_k - y_mean)^T over the columns of x and y
+fn weighted_cross_covariance(
+	x: &Matrix,
+	x_mean: &Matrix,
+	y: &Matrix,
+	y_mean: &Matrix,
+	weights: &[f64],
+) -> Result<Matrix, MathMatrixError> {
+	let (x_rows, count) = x.get_size();
+	let y_rows = y.get_size().0;
+	let (x_data, y_data) = (x.get_data(), y.get_data());
+	let (x_center, y_center) = (x_mean.get_data(), y_mean.get_data());
+	let mut x_deviations = Vec::with_capacity(x_rows * count);
+	let mut y_deviations = Vec::with_capacity(y_rows * count);
+	for k in 0..count {
+		let x_col = &x_data[k * x_rows..(k + 1) * x_rows];
+		x_deviations.extend(
+			x_col
+				.iter()
+				.zip(x_center.iter())
+				.map(|(a, m)| (a - m) * weights[k]),
+		);
+		let y_col = &y_data[k * y_rows..(k + 1) * y_rows];
+		y_deviations.extend(y_col.iter().zip(y_center.iter()).map(|(a, m)| a - m));
+	}
+	let x_deviations = Matrix::new(x_rows, count, x_deviations)?;
+	let y_deviations = Matrix::new(y_rows, count, y_deviations)?;
+	return x_deviations.multiplied_by_matrix(&y_deviations.transposed());
+}
+
+/* Unscented Kalman filter for x_k+1 = f(x_k) + w_k, z_k = h(x_k) + v_k with
+nonlinear f and h. The sigma points of the current estimate are pushed
+through f or h, and the weighted statistics of the images replace the
+linearization of an extended filter.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnscentedKalmanFilter {
+	state: Matrix,
+	covariance: Matrix,
+	params: UnscentedParams,
+}
+
+impl UnscentedKalmanFilter {
+	pub fn new(
+		state: Matrix,
+		covariance: Matrix,
+		params: UnscentedParams,
+	) -> Result<Self, MathMatrixError> {
+		check_state(&state, &covariance)?;
+		return Ok(Self {
+			state,
+			covariance,
+			params,
+		});
+	}
+
+	pub fn get_state(&self) -> &Matrix {
+		return &self.state;
+	}
+
+	pub fn get_covariance(&self) -> &Matrix {
+		return &self.covariance;
+	}
+
+	// f maps an Nx1 state to the next Nx1 state
+	pub fn predict<F: Fn(&Matrix) -> Result<Matrix, MathMatrixError>>(
+		&mut self,
+		f: F,
+		q: &Matrix,
+	) -> Result<(), MathMatrixError> {
+		let sigma = unscented_sigma_points(&self.state, &self.covariance, &self.params)?;
+		let images = propagate(&sigma.points, f)?;
+		let state = weighted_mean(&images, &sigma.mean_weights)?;
+		let spread =
+			weighted_cross_covariance(&images, &state, &images, &state, &sigma.covariance_weights)?;
+		let covariance = (spread + q.clone())?;
+		check_state(&state, &covariance)?;
+		self.state = state;
+		self.covariance = covariance;
+		return Ok(());
+	}
+
+	// h maps an Nx1 state to the expected Mx1 measurement z
+	pub fn update<H: Fn(&Matrix) -> Result<Matrix, MathMatrixError>>(
+		&mut self,
+		h: H,
+		r: &Matrix,
+		z: &Matrix,
+	) -> Result<(), MathMatrixError> {
+		let sigma = unscented_sigma_points(&self.state, &self.covariance, &self.params)?;
+		let images = propagate(&sigma.points, h)?;
+		let predicted = weighted_mean(&images, &sigma.mean_weights)?;
+		let w = &sigma.covariance_weights;
+		let s =
+			(weighted_cross_covariance(&images, &predicted, &images, &predicted, w)? + r.clone())?;
+		let cross = weighted_cross_covariance(&sigma.points, &self.state, &images, &predicted, w)?;
+		// K = C * S^-1, from S * K^T = C^T with S symmetric
+		let gain = s.solve(&cross.transposed())?.transposed();
+		let innovation = (z.clone() - predicted)?;
+		let correction = gain
+			.multiplied_by_matrix(&s)?
+			.multiplied_by_matrix(&gain.transposed())?;
+		self.state = (self.state.clone() + gain.multiplied_by_matrix(&innovation)?)?;
+		self.covariance = (self.covariance.clone() - correction)?;
+		return Ok(());
+	}
+}
+
+// f applied to every column of `points`
+fn propagate<F: FnMut(&Matrix) -> Result<Matrix, MathMatrixError>>(
+	points: &Matrix,
+	mut f: F,
+) -> Result<Matrix, MathMatrixError> {
+	let (rows, count) = points.get_size();
+	let mut data = vec![];
+	let mut image_rows = 0;
+	for k in 0..count {
+		let image = f(&points.get_block(0, k, rows, 1)?)?;
+		if k == 0 {
+			image_rows = image.get_size().0;
+		}
+		if image.get_size() != (image_rows, 1) {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				format!(
+					"Expected an {}x1 image of every sigma point, got {:?}",
+					image_rows,
+					image.get_size()
+				),
+			));
+		}
+		data.extend(image.into_vec());
+	}
+	return Matrix::new(image_rows, count, data);
+}
+
+/* Bootstrap particle filter: the state distribution is a weighted set of
+particles, the columns of an N x P matrix. The models are closures, so
+anything that can be simulated and scored can be filtered, linear or not,
+with any noise.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParticleFilter {
+	particles: Matrix,
+	weights: Vec<f64>,
+}
+
+impl ParticleFilter {
+	// Equally weighted particles, e.g. draws from the prior
+	pub fn new(particles: Matrix) -> Self {
+		let count = particles.get_size().1;
+		return Self {
+			particles,
+			weights: vec![1.0 / count as f64; count],
+		};
+	}
+
+	pub fn get_particles(&self) -> &Matrix {
+		return &self.particles;
+	}
+
+	// Normalized to sum to one
+	pub fn get_weights(&self) -> &[f64] {
+		return &self.weights;
+	}
+
+	// Weighted mean of the particles, Nx1
+	pub fn estimate(&self) -> Result<Matrix, MathMatrixError> {
+		return weighted_mean(&self.particles, &self.weights);
+	}
+
+	// 1 / sum w_k^2: P for equal weights, 1 when one particle has all of them
+	pub fn effective_sample_size(&self) -> f64 {
+		return 1.0 / self.weights.iter().map(|w| w * w).sum::<f64>();
+	}
+
+	/* Moves every particle with `f`, which maps an Nx1 state to a sample of
+	the next state, process noise included (`f` may capture an Rng)
+	*/
+	pub fn predict<F: FnMut(&Matrix) -> Result<Matrix, MathMatrixError>>(
+		&mut self,
+		f: F,
+	) -> Result<(), MathMatrixError> {
+		let particles = propagate(&self.particles, f)?;
+		if particles.get_size() != self.particles.get_size() {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				format!(
+					"Expected states of size {}, got {}",
+					self.particles.get_size().0,
+					particles.get_size().0
+				),
+			));
+		}
+		self.particles = particles;
+		return Ok(());
+	}
+
+	/* Multiplies each weight by the likelihood p(z | x) of the measurement
+	given the particle, as computed by `likelihood`, and renormalizes. Fails,
+	leaving the weights unchanged, if every particle gets likelihood 0.
+	*/
+	pub fn update<L: FnMut(&Matrix) -> f64>(
+		&mut self,
+		mut likelihood: L,
+	) -> Result<(), MathMatrixError> {
+		let (rows, count) = self.particles.get_size();
+		let mut weights = Vec::with_capacity(count);
+		for k in 0..count {
+			let particle = self.particles.get_block(0, k, rows, 1)?;
+			weights.push(self.weights[k] * likelihood(&particle));
+		}
+		let total: f64 = weights.iter().sum();
+		if total <= 0.0 || !total.is_finite() {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				format!("Particle weights sum to {}", total),
+			));
+		}
+		self.weights = weights.iter().map(|w| w / total).collect();
+		return Ok(());
+	}
+
+	// Draws P particles by `systematic_resample`, all with weight 1 / P
+	pub fn resample(&mut self, rng: &mut Rng) -> Result<(), MathMatrixError> {
+		let (rows, count) = self.particles.get_size();
+		let data = self.particles.get_data();
+		let mut resampled = Vec::with_capacity(rows * count);
+		for k in systematic_resample(&self.weights, rng) {
+			resampled.extend_from_slice(&data[k * rows..(k + 1) * rows]);
+		}
+		self.particles = Matrix::new(rows, count, resampled)?;
+		self.weights = vec![1.0 / count as f64; count];
+		return Ok(());
+	}
+}
+
+/* Low-variance systematic resampling: one uniform draw u in [0, 1 / P)
+places P equally spaced pointers u + k / P on the cumulative weights, and
+each selects the particle it falls on. A particle of weight w is picked
+floor(w * P) or ceil(w * P) times. `weights` must sum to one.
+*/
+pub fn systematic_resample(weights: &[f64], rng: &mut Rng) -> Vec<usize> {
+	let count = weights.len();
+	let start = rng.uniform() / count as f64;
+	let mut indices = Vec::with_capacity(count);
+	let mut cumulative = weights.first().copied().unwrap_or(0.0);
+	let mut i = 0;
+	for k in 0..count {
+		let pointer = start + k as f64 / count as f64;
+		while pointer > cumulative && i + 1 < count {
+			i += 1;
+			cumulative += weights[i];
+		}
+		indices.push(i);
+	}
+	return indices;
+}
+
+fn check_state(state: &Matrix, covariance: &Matrix) -> Result<(), MathMatrixError> {
+	let n = state.get_size().0;
+	if state.get_size() != (n, 1) || covariance.get_size() != (n, n) {
+		return Err(MathMatrixError::new(
+			SizeMismatch,
+			format!(
+				"Expected an Nx1 state and an NxN covariance, got {:?} and {:?}",
+				state.get_size(),
+				covariance.get_size()
+			),
+		));
+	}
+	return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_square_root_kalman_filter() {
+		// Constant velocity model, position measured
+		let f = Matrix::new(2, 2, vec![1.0, 0.0, 1.0, 1.0]).unwrap();
+		let q = Matrix::new(2, 2, vec![1e-3, 0.0, 0.0, 1e-3]).unwrap();
+		let h = Matrix::new(1, 2, vec![1.0, 0.0]).unwrap();
+		let r = Matrix::new(1, 1, vec![0.25]).unwrap();
+		let state = Matrix::zeros(2, 1).unwrap();
+		let covariance = Matrix::identity(2, 2).unwrap().multiplied_by_scalar(10.0);
+		let mut kalman = KalmanFilter::new(state.clone(), covariance.clone()).unwrap();
+		let mut square_root = SquareRootKalmanFilter::new(state, &covariance).unwrap();
+		for k in 0..20 {
+			let z = Matrix::new(1, 1, vec![2.0 * k as f64 + 0.1 * (k % 3) as f64]).unwrap();
+			kalman.predict(&f, &q).unwrap();
+			kalman.update(&h, &r, &z).unwrap();
+			square_root.predict(&f, &q).unwrap();
+			square_root.update(&h, &r, &z).unwrap();
+		}
+		assert!(square_root.get_state().approx_eq(kalman.get_state(), 1e-9));
+		assert!(square_root
+			.get_covariance()
+			.unwrap()
+			.approx_eq(kalman.get_covariance(), 1e-9));
+		// The velocity estimate settles near the true slope of 2
+		assert!((kalman.get_state().get_value(1, 0).unwrap() - 2.0).abs() < 0.1);
+		assert!(square_root.predict(&h, &q).is_err());
+	}
+
+	#[test]
+	fn test_unscented_kalman_filter() {
+		let mean = Matrix::new(2, 1, vec![1.0, -1.0]).unwrap();
+		let covariance = Matrix::new(2, 2, vec![2.0, 0.5, 0.5, 1.0]).unwrap();
+		let sigma =
+			unscented_sigma_points(&mean, &covariance, &UnscentedParams::default()).unwrap();
+		assert_eq!(sigma.points.get_size(), (2, 5));
+		let center = weighted_mean(&sigma.points, &sigma.mean_weights).unwrap();
+		assert!(center.approx_eq(&mean, 1e-9));
+		let spread = weighted_cross_covariance(
+			&sigma.points,
+			&center,
+			&sigma.points,
+			&center,
+			&sigma.mean_weights,
+		)
+		.unwrap();
+		assert!(spread.approx_eq(&covariance, 1e-6));
+		// With linear models the unscented filter matches the Kalman filter
+		let f = Matrix::new(2, 2, vec![1.0, 0.0, 1.0, 1.0]).unwrap();
+		let q = Matrix::new(2, 2, vec![1e-2, 0.0, 0.0, 1e-2]).unwrap();
+		let h = Matrix::new(1, 2, vec![1.0, 0.0]).unwrap();
+		let r = Matrix::new(1, 1, vec![0.25]).unwrap();
+		let params = UnscentedParams {
+			alpha: 1.0,
+			beta: 2.0,
+			kappa: 0.0,
+		};
+		let mut kalman = KalmanFilter::new(mean.clone(), covariance.clone()).unwrap();
+		let mut unscented = UnscentedKalmanFilter::new(mean, covariance, params).unwrap();
+		for k in 0..5 {
+			let z = Matrix::new(1, 1, vec![k as f64]).unwrap();
+			kalman.predict(&f, &q).unwrap();
+			kalman.update(&h, &r, &z).unwrap();
+			unscented
+				.predict(|x| f.multiplied_by_matrix(x), &q)
+				.unwrap();
+			unscented
+				.update(|x| h.multiplied_by_matrix(x), &r, &z)
+				.unwrap();
+		}
+		assert!(unscented.get_state().approx_eq(kalman.get_state(), 1e-10));
+		assert!(unscented
+			.get_covariance()
+			.approx_eq(kalman.get_covariance(), 1e-10));
+		let mismatched = |_: &Matrix| Matrix::zeros(3, 2);
+		assert!(unscented.predict(mismatched, &q).is_err());
+	}
+
+	#[test]
+	fn test_particle_filter() {
+		let mut rng = Rng::new(7);
+		assert_eq!(
+			systematic_resample(&[0.5, 0.0, 0.25, 0.25], &mut rng),
+			vec![0, 0, 2, 3]
+		);
+		// Static scalar state near 3, measured with unit noise
+		let prior = rng.normal_matrix(1, 500).unwrap().multiplied_by_scalar(5.0);
+		let mut filter = ParticleFilter::new(prior);
+		assert_eq!(filter.effective_sample_size().round(), 500.0);
+		let mut noise = Rng::new(11);
+		for z in [3.2, 2.7, 3.1, 2.9, 3.0].iter() {
+			filter
+				.predict(|x| Matrix::new(1, 1, vec![x.get_value(0, 0)? + 0.05 * noise.normal()]))
+				.unwrap();
+			filter
+				.update(|x| (-0.5 * (x.get_value(0, 0).unwrap() - z).powi(2)).exp())
+				.unwrap();
+			if filter.effective_sample_size() < 250.0 {
+				filter.resample(&mut rng).unwrap();
+			}
+		}
+		let estimate = filter.estimate().unwrap().get_value(0, 0).unwrap();
+		assert!((estimate - 2.98).abs() < 0.3);
+		assert!(filter.update(|_| 0.0).is_err());
+		assert!(filter.predict(|_| Matrix::zeros(2, 1)).is_err());
+	}
+}
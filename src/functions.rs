@@ -0,0 +1,281 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::{Matrix, NormKind};
+
+/* Matrix functions f(A) for a scalar function f, defined through the
+eigenvalues: f(A) = V * f(D) * V^-1 when A = V * D * V^-1.
+- Symmetric A: from the orthonormal eigendecomposition, f(A) = V * f(D) * V^T
+- Otherwise: Schur-Parlett. With A = Q * T * Q^T, f(A) = Q * f(T) * Q^T, and
+  f(T) is upper triangular with f(t_ii) on the diagonal and the entries above
+  given by Parlett's recurrence from T * f(T) = f(T) * T.
+f only sees real arguments, so A must have real eigenvalues. Without
+blocking, the recurrence divides by t_jj - t_ii and needs them distinct.
+*/
+impl Matrix {
+	pub fn apply_function<F: Fn(f64) -> f64>(&self, f: F) -> Result<Matrix, MathMatrixError> {
+		let (rows, cols) = self.get_size();
+		if rows != cols {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Matrix functions require a square NxN matrix".to_owned(),
+			));
+		}
+		let largest = self.get_data().iter().fold(0f64, |acc, x| acc.max(x.abs()));
+		if self.is_symmetric(largest * 1e-12) {
+			let (values, vectors) = self.eigen_symmetric()?;
+			let mut scaled = vectors.clone();
+			for (j, value) in values.iter().enumerate() {
+				let fx = f(*value);
+				for i in 0..rows {
+					scaled.set_value(i, j, scaled.get_value(i, j)? * fx)?;
+				}
+			}
+			return scaled.multiplied_by_matrix(&vectors.transposed());
+		}
+		let (q, t) = self.schur()?;
+		for i in 1..rows {
+			if t.get_value(i, i - 1)? != 0.0 {
+				return Err(MathMatrixError::new(
+					OperationNotPermitted,
+					"Matrix function of a matrix with complex eigenvalues".to_owned(),
+				));
+			}
+		}
+		let separation = f64::EPSILON.sqrt() * largest;
+		let mut ft = Matrix::zeros(rows, rows)?;
+		for j in 0..rows {
+			let t_jj = t.get_value(j, j)?;
+			ft.set_value(j, j, f(t_jj))?;
+			// Upwards along column j, using the entries of f(T) already computed
+			for i in (0..j).rev() {
+				let t_ii = t.get_value(i, i)?;
+				let gap = t_jj - t_ii;
+				if gap.abs() <= separation {
+					return Err(MathMatrixError::new(
+						OperationNotPermitted,
+						"Schur-Parlett requires distinct eigenvalues".to_owned(),
+					));
+				}
+				let mut sum = t.get_value(i, j)? * (ft.get_value(j, j)? - ft.get_value(i, i)?);
+				for k in (i + 1)..j {
+					sum += ft.get_value(i, k)? * t.get_value(k, j)?
+						- t.get_value(i, k)? * ft.get_value(k, j)?;
+				}
+				ft.set_value(i, j, sum / gap)?;
+			}
+		}
+		return q
+			.multiplied_by_matrix(&ft)?
+			.multiplied_by_matrix(&q.transposed());
+	}
+}
+
+// Degree of the diagonal Pade approximant used by `expm`
+const EXPM_PADE_DEGREE: usize = 6;
+// Iteration limit of the Denman-Beavers iteration in `sqrtm`
+const SQRTM_MAX_ITERATIONS: usize = 100;
+// `logm` takes square roots until |A - I| is below this
+const LOGM_SERIES_RADIUS: f64 = 0.25;
+
+impl Matrix {
+	/* e^A by scaling and squaring: A is scaled by 2^-s until its infinity
+	norm is at most 1/2, e^(A / 2^s) is approximated by a diagonal Pade
+	approximant D^-1 * N, and the result squared s times (Golub and Van Loan,
+	algorithm 11.3.1). Unlike `apply_function`, A may have complex or repeated
+	eigenvalues.
+	*/
+	pub fn expm(&self) -> Result<Matrix, MathMatrixError> {
+		let (rows, cols) = self.get_size();
+		if rows != cols {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Matrix functions require a square NxN matrix".to_owned(),
+			));
+		}
+		let norm = self.norm(NormKind::Infinity)?;
+		if !norm.is_finite() {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				"Matrix exponential of a matrix with non-finite entries".to_owned(),
+			));
+		}
+		let squarings = if norm > 0.5 {
+			(norm.log2().floor() as i32 + 2) as u32
+		} else {
+			0
+		};
+		let scaled = self.multiplied_by_scalar(0.5f64.powi(squarings as i32));
+		let q = EXPM_PADE_DEGREE;
+		let identity = Matrix::identity(rows, cols)?;
+		let (mut numerator, mut denominator) = (identity.clone(), identity.clone());
+		let mut power = identity;
+		let mut c = 1.0;
+		for k in 1..=q {
+			c *= (q - k + 1) as f64 / ((2 * q - k + 1) * k) as f64;
+			power = scaled.multiplied_by_matrix(&power)?;
+			let term = power.multiplied_by_scalar(c);
+			numerator = (numerator + term.clone())?;
+			denominator = if k % 2 == 0 {
+				(denominator + term)?
+			} else {
+				(denominator - term)?
+			};
+		}
+		let mut result = denominator.solve(&numerator)?;
+		for _ in 0..squarings {
+			result = result.multiplied_by_matrix(&result)?;
+		}
+		return Ok(result);
+	}
+
+	/* Principal square root by the Denman-Beavers iteration:
+	Y <- (Y + Z^-1) / 2 and Z <- (Z + Y^-1) / 2 from Y = A, Z = I, with Y
+	converging to A^(1/2) and Z to A^(-1/2). A must have no eigenvalues on
+	the closed negative real axis; complex eigenvalues are fine.
+	*/
+	pub fn sqrtm(&self) -> Result<Matrix, MathMatrixError> {
+		let (rows, cols) = self.get_size();
+		if rows != cols {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Matrix functions require a square NxN matrix".to_owned(),
+			));
+		}
+		let mut y = self.clone();
+		let mut z = Matrix::identity(rows, cols)?;
+		for _ in 0..SQRTM_MAX_ITERATIONS {
+			let next_y = (y.clone() + z.invert()?)?.multiplied_by_scalar(0.5);
+			let next_z = (z + y.invert()?)?.multiplied_by_scalar(0.5);
+			let change = (next_y.clone() - y)?.norm(NormKind::Infinity)?;
+			y = next_y;
+			z = next_z;
+			if change <= 4.0 * f64::EPSILON * y.norm(NormKind::Infinity)? {
+				return Ok(y);
+			}
+		}
+		return Err(MathMatrixError::new(
+			FailedToConverge,
+			format!(
+				"Denman-Beavers iteration did not converge in {} iterations",
+				SQRTM_MAX_ITERATIONS
+			),
+		));
+	}
+
+	/* Principal logarithm by inverse scaling and squaring: k square roots
+	bring A close to I, then log(A) = 2^k * log(I + X) with the series
+	X - X^2 / 2 + X^3 / 3 ... for X = A^(1/2^k) - I. Same conditions on the
+	eigenvalues as `sqrtm`.
+	*/
+	pub fn logm(&self) -> Result<Matrix, MathMatrixError> {
+		let (rows, cols) = self.get_size();
+		if rows != cols {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Matrix functions require a square NxN matrix".to_owned(),
+			));
+		}
+		let identity = Matrix::identity(rows, cols)?;
+		let mut root = self.clone();
+		let mut square_roots = 0;
+		let mut x = (root.clone() - identity.clone())?;
+		while x.norm(NormKind::Infinity)? > LOGM_SERIES_RADIUS {
+			root = root.sqrtm()?;
+			square_roots += 1;
+			x = (root.clone() - identity.clone())?;
+		}
+		// |X| <= 1/4: terms fall below the rounding error of the sum by k = 30
+		let mut log = x.clone();
+		let mut power = x.clone();
+		for k in 2..=30 {
+			power = power.multiplied_by_matrix(&x)?;
+			let sign = if k % 2 == 0 { -1.0 } else { 1.0 };
+			log = (log + power.multiplied_by_scalar(sign / k as f64))?;
+		}
+		return Ok(log.multiplied_by_scalar(2f64.powi(square_roots)));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_apply_function_symmetric() {
+		// Square root of an SPD matrix squares back to it
+		let mat = Matrix::new(2, 2, vec![5.0, 2.0, 2.0, 2.0]).unwrap();
+		let root = mat.apply_function(f64::sqrt).unwrap();
+		assert!(root
+			.multiplied_by_matrix(&root)
+			.unwrap()
+			.approx_eq(&mat, 1e-12));
+		assert!(root.is_symmetric(1e-12));
+	}
+
+	#[test]
+	fn test_apply_function_schur_parlett() {
+		// exp of [1, 1; 0, 2] is [e, e^2 - e; 0, e^2]
+		let mat = Matrix::new(2, 2, vec![1.0, 0.0, 1.0, 2.0]).unwrap();
+		let (e, e2) = (1f64.exp(), 2f64.exp());
+		let expected = Matrix::new(2, 2, vec![e, 0.0, e2 - e, e2]).unwrap();
+		assert!(mat
+			.apply_function(f64::exp)
+			.unwrap()
+			.approx_eq(&expected, 1e-12));
+		// A polynomial agrees with the matrix product; eigenvalues 1, 2, 4
+		let mat = Matrix::new(3, 3, vec![1.0, 0.0, 0.0, 1.0, 3.0, 1.0, 1.0, 1.0, 3.0]).unwrap();
+		let squared = mat.multiplied_by_matrix(&mat).unwrap();
+		assert!(mat
+			.apply_function(|x| x * x)
+			.unwrap()
+			.approx_eq(&squared, 1e-10));
+		// Rotation: complex eigenvalues
+		let rotation = Matrix::new(2, 2, vec![0.0, 1.0, -1.0, 0.0]).unwrap();
+		assert!(rotation.apply_function(f64::exp).is_err());
+	}
+
+	#[test]
+	fn test_expm() {
+		// Rotation generator: e^(t * [0, -1; 1, 0]) rotates by t
+		let t = 2.5f64;
+		let generator = Matrix::new(2, 2, vec![0.0, t, -t, 0.0]).unwrap();
+		let expected = Matrix::new(2, 2, vec![t.cos(), t.sin(), -t.sin(), t.cos()]).unwrap();
+		assert!(generator.expm().unwrap().approx_eq(&expected, 1e-12));
+		// Nilpotent: e^N = I + N
+		let nilpotent = Matrix::new(2, 2, vec![0.0, 0.0, 3.0, 0.0]).unwrap();
+		let expected = (Matrix::identity(2, 2).unwrap() + nilpotent.clone()).unwrap();
+		assert!(nilpotent.expm().unwrap().approx_eq(&expected, 1e-14));
+		// Agrees with Schur-Parlett where both apply
+		let mat = Matrix::new(3, 3, vec![1.0, 0.0, 0.0, 1.0, 3.0, 1.0, 1.0, 1.0, 3.0]).unwrap();
+		let reference = mat.apply_function(f64::exp).unwrap();
+		assert!(mat
+			.expm()
+			.unwrap()
+			.approx_eq(&reference, 1e-10 * reference.get_value(1, 1).unwrap()));
+		assert!(Matrix::zeros(2, 3).unwrap().expm().is_err());
+	}
+
+	#[test]
+	fn test_sqrtm_logm() {
+		// Non-symmetric, with complex eigenvalues 2 +- i
+		let mat = Matrix::new(2, 2, vec![2.0, 1.0, -1.0, 2.0]).unwrap();
+		let root = mat.sqrtm().unwrap();
+		assert!(root
+			.multiplied_by_matrix(&root)
+			.unwrap()
+			.approx_eq(&mat, 1e-12));
+		let log = mat.logm().unwrap();
+		assert!(log.expm().unwrap().approx_eq(&mat, 1e-12));
+		// log of a symmetric matrix agrees with the eigendecomposition
+		let spd = Matrix::new(2, 2, vec![5.0, 2.0, 2.0, 2.0]).unwrap();
+		assert!(spd
+			.logm()
+			.unwrap()
+			.approx_eq(&spd.apply_function(f64::ln).unwrap(), 1e-12));
+		// Singular: no logarithm, and the iteration cannot invert
+		assert!(Matrix::zeros(2, 2).unwrap().sqrtm().is_err());
+		assert!(Matrix::zeros(2, 2).unwrap().logm().is_err());
+		let err = Matrix::zeros(2, 3).unwrap().logm().unwrap_err();
+		assert_eq!(err.get_kind(), "SizeMismatch");
+	}
+}
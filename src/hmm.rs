@@ -0,0 +1,217 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+
+/* Hidden Markov models with S hidden states and O observation symbols:
+- initial: Sx1, pi_i = P(x_0 = i)
+- transition: SxS, a_ij = P(x_t+1 = j | x_t = i), rows summing to one
+- emission: SxO, b_ik = P(z_t = k | x_t = i), rows summing to one
+Observations are symbol indices. The recursions run on log probabilities,
+so long sequences do not underflow.
+*/
+
+// Result of `forward_backward`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Posterior {
+	// SxT, entry (i, t) = P(x_t = i | z_0 .. z_T-1)
+	pub posteriors: Matrix,
+	// log P(z_0 .. z_T-1)
+	pub log_likelihood: f64,
+}
+
+// Posterior state probabilities at every step, from the forward and backward passes
+pub fn forward_backward(
+	initial: &Matrix,
+	transition: &Matrix,
+	emission: &Matrix,
+	observations: &[usize],
+) -> Result<Posterior, MathMatrixError> {
+	let model = LogModel::new(initial, transition, emission, observations)?;
+	let (s, t) = (model.states, observations.len());
+	// alpha[t][i] = log P(z_0 .. z_t, x_t = i)
+	let mut alpha = vec![vec![0f64; s]; t];
+	for i in 0..s {
+		alpha[0][i] = model.initial[i] + model.emission(i, observations[0]);
+	}
+	for step in 1..t {
+		for j in 0..s {
+			let incoming = (0..s).map(|i| alpha[step - 1][i] + model.transition(i, j));
+			alpha[step][j] = log_sum_exp(incoming) + model.emission(j, observations[step]);
+		}
+	}
+	// beta[t][i] = log P(z_t+1 .. z_T-1 | x_t = i)
+	let mut beta = vec![vec![0f64; s]; t];
+	for step in (0..t - 1).rev() {
+		for i in 0..s {
+			let outgoing = (0..s).map(|j| {
+				model.transition(i, j)
+					+ model.emission(j, observations[step + 1])
+					+ beta[step + 1][j]
+			});
+			beta[step][i] = log_sum_exp(outgoing);
+		}
+	}
+	let log_likelihood = log_sum_exp(alpha[t - 1].iter().copied());
+	if log_likelihood == f64::NEG_INFINITY {
+		return Err(MathMatrixError::new(
+			OperationNotPermitted,
+			"Observation sequence has probability zero under the model".to_owned(),
+		));
+	}
+	let mut data = Vec::with_capacity(s * t);
+	for step in 0..t {
+		data.extend((0..s).map(|i| (alpha[step][i] + beta[step][i] - log_likelihood).exp()));
+	}
+	return Ok(Posterior {
+		posteriors: Matrix::new(s, t, data)?,
+		log_likelihood,
+	});
+}
+
+/* Most likely state sequence and its joint log probability
+log P(x_0 .. x_T-1, z_0 .. z_T-1). Ties go to the lowest state index.
+*/
+pub fn viterbi(
+	initial: &Matrix,
+	transition: &Matrix,
+	emission: &Matrix,
+	observations: &[usize],
+) -> Result<(Vec<usize>, f64), MathMatrixError> {
+	let model = LogModel::new(initial, transition, emission, observations)?;
+	let (s, t) = (model.states, observations.len());
+	let mut delta: Vec<f64> = (0..s)
+		.map(|i| model.initial[i] + model.emission(i, observations[0]))
+		.collect();
+	// back[t][j]: best predecessor of state j at step t
+	let mut back = vec![vec![0usize; s]; t];
+	for step in 1..t {
+		let mut next = vec![0f64; s];
+		for j in 0..s {
+			let mut best = 0;
+			for i in 1..s {
+				if delta[i] + model.transition(i, j) > delta[best] + model.transition(best, j) {
+					best = i;
+				}
+			}
+			back[step][j] = best;
+			next[j] =
+				delta[best] + model.transition(best, j) + model.emission(j, observations[step]);
+		}
+		delta = next;
+	}
+	let mut last = 0;
+	for i in 1..s {
+		if delta[i] > delta[last] {
+			last = i;
+		}
+	}
+	if delta[last] == f64::NEG_INFINITY {
+		return Err(MathMatrixError::new(
+			OperationNotPermitted,
+			"Observation sequence has probability zero under the model".to_owned(),
+		));
+	}
+	let mut path = vec![last; t];
+	for step in (1..t).rev() {
+		path[step - 1] = back[step][path[step]];
+	}
+	return Ok((path, delta[last]));
+}
+
+// log(sum exp(x)), shifted by the maximum so the largest term is exp(0)
+fn log_sum_exp<I: Iterator<Item = f64>>(values: I) -> f64 {
+	let values = values.collect::<Vec<f64>>();
+	let largest = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+	if largest == f64::NEG_INFINITY {
+		return largest;
+	}
+	return largest + values.iter().map(|x| (x - largest).exp()).sum::<f64>().ln();
+}
+
+// The model parameters as log probabilities, after checking their sizes
+struct LogModel {
+	states: usize,
+	symbols: usize,
+	initial: Vec<f64>,
+	// Column major, like Matrix
+	transition: Vec<f64>,
+	emission: Vec<f64>,
+}
+
+impl LogModel {
+	fn new(
+		initial: &Matrix,
+		transition: &Matrix,
+		emission: &Matrix,
+		observations: &[usize],
+	) -> Result<Self, MathMatrixError> {
+		let (states, symbols) = emission.get_size();
+		if initial.get_size() != (states, 1) || transition.get_size() != (states, states) {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				format!(
+					"Expected an Sx1 initial distribution and an SxS transition matrix for S = {}",
+					states
+				),
+			));
+		}
+		if observations.is_empty() {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				"Observation sequence is empty".to_owned(),
+			));
+		}
+		if let Some(symbol) = observations.iter().find(|symbol| **symbol >= symbols) {
+			return Err(MathMatrixError::new(
+				OutOfBoundary,
+				format!("Observation {} outside {} symbols", symbol, symbols),
+			));
+		}
+		let log = |mat: &Matrix| mat.get_data().iter().map(|p| p.ln()).collect();
+		return Ok(Self {
+			states,
+			symbols,
+			initial: log(initial),
+			transition: log(transition),
+			emission: log(emission),
+		});
+	}
+
+	fn transition(&self, from: usize, to: usize) -> f64 {
+		return self.transition[to * self.states + from];
+	}
+
+	fn emission(&self, state: usize, symbol: usize) -> f64 {
+		debug_assert!(symbol < self.symbols);
+		return self.emission[symbol * self.states + state];
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_hidden_markov_model() {
+		// Rainy (0) / sunny (1) weather observed through walk (0), shop (1), clean (2)
+		let initial = Matrix::new(2, 1, vec![0.6, 0.4]).unwrap();
+		let transition = Matrix::new(2, 2, vec![0.7, 0.4, 0.3, 0.6]).unwrap();
+		let emission = Matrix::new(2, 3, vec![0.1, 0.6, 0.4, 0.3, 0.5, 0.1]).unwrap();
+		let observations = [0, 1, 2];
+		let posterior = forward_backward(&initial, &transition, &emission, &observations).unwrap();
+		// P(walk, shop, clean) summed over all 8 state paths
+		assert!((posterior.log_likelihood.exp() - 0.033612).abs() < 1e-9);
+		let sums = posterior.posteriors.sum_cols();
+		assert!(sums.approx_eq(&Matrix::new(1, 3, vec![1.0; 3]).unwrap(), 1e-12));
+		let (path, log_probability) =
+			viterbi(&initial, &transition, &emission, &observations).unwrap();
+		assert_eq!(path, vec![1, 0, 0]);
+		assert!((log_probability.exp() - 0.01344).abs() < 1e-12);
+		assert!(viterbi(&initial, &transition, &emission, &[3]).is_err());
+		assert!(forward_backward(&initial, &transition, &emission, &[]).is_err());
+		// A long sequence does not underflow
+		let long = vec![1; 2000];
+		let posterior = forward_backward(&initial, &transition, &emission, &long).unwrap();
+		assert!(posterior.log_likelihood.is_finite());
+	}
+}
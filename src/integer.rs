@@ -0,0 +1,254 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+
+// What integer arithmetic does when a result does not fit in an i64
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowMode {
+	// Fail with an Overflow error
+	Checked,
+	// Two's complement wrap-around, i.e. arithmetic modulo 2^64
+	Wrapping,
+	// Clamp to i64::MIN or i64::MAX
+	Saturating,
+}
+
+impl OverflowMode {
+	fn add(&self, a: i64, b: i64) -> Option<i64> {
+		match self {
+			OverflowMode::Checked => a.checked_add(b),
+			OverflowMode::Wrapping => Some(a.wrapping_add(b)),
+			OverflowMode::Saturating => Some(a.saturating_add(b)),
+		}
+	}
+
+	fn mul(&self, a: i64, b: i64) -> Option<i64> {
+		match self {
+			OverflowMode::Checked => a.checked_mul(b),
+			OverflowMode::Wrapping => Some(a.wrapping_mul(b)),
+			OverflowMode::Saturating => Some(a.saturating_mul(b)),
+		}
+	}
+}
+
+/* Dense matrix of i64 entries, column major like Matrix, for exact
+combinatorial computations (path counts, adjacency powers, binomial tables).
+Overflow behaviour is chosen per operation; the plain operations are checked.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegerMatrix {
+	rows: usize,
+	cols: usize,
+	data: Vec<i64>,
+}
+
+impl IntegerMatrix {
+	pub fn new(rows: usize, cols: usize, data: Vec<i64>) -> Result<Self, MathMatrixError> {
+		if rows * cols == 0 {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"Rows and columns must be lager than 0".to_owned(),
+			));
+		}
+		if rows * cols != data.len() {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				format!(
+					"Size of data != rows * cols: {} != {}",
+					data.len(),
+					rows * cols
+				),
+			));
+		}
+		return Ok(Self { rows, cols, data });
+	}
+
+	pub fn zeros(rows: usize, cols: usize) -> Result<Self, MathMatrixError> {
+		return Self::new(rows, cols, vec![0; rows * cols]);
+	}
+
+	pub fn identity(size: usize) -> Result<Self, MathMatrixError> {
+		let mut identity = Self::zeros(size, size)?;
+		for i in 0..size {
+			identity.data[i * size + i] = 1;
+		}
+		return Ok(identity);
+	}
+
+	pub fn get_size(&self) -> (usize, usize) {
+		return (self.rows, self.cols);
+	}
+
+	pub fn get_data(&self) -> &[i64] {
+		return &self.data;
+	}
+
+	pub fn get_value(&self, row: usize, col: usize) -> Result<i64, MathMatrixError> {
+		self.check_position(row, col)?;
+		return Ok(self.data[col * self.rows + row]);
+	}
+
+	pub fn set_value(&mut self, row: usize, col: usize, value: i64) -> Result<(), MathMatrixError> {
+		self.check_position(row, col)?;
+		self.data[col * self.rows + row] = value;
+		return Ok(());
+	}
+
+	// Exact when every entry has magnitude up to 2^53
+	pub fn to_matrix(&self) -> Matrix {
+		return Matrix::from_vec_unchecked(
+			self.rows,
+			self.cols,
+			self.data.iter().map(|x| *x as f64).collect(),
+		);
+	}
+
+	pub fn added_to(&self, other: &IntegerMatrix) -> Result<IntegerMatrix, MathMatrixError> {
+		return self.added_to_with(other, OverflowMode::Checked);
+	}
+
+	pub fn added_to_with(
+		&self,
+		other: &IntegerMatrix,
+		overflow: OverflowMode,
+	) -> Result<IntegerMatrix, MathMatrixError> {
+		if self.get_size() != other.get_size() {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Operation not allowed between matrices with different sizes".to_owned(),
+			));
+		}
+		let mut data = Vec::with_capacity(self.data.len());
+		for (k, (a, b)) in self.data.iter().zip(other.data.iter()).enumerate() {
+			data.push(
+				overflow
+					.add(*a, *b)
+					.ok_or_else(|| self.overflow_error(k % self.rows, k / self.rows))?,
+			);
+		}
+		return Self::new(self.rows, self.cols, data);
+	}
+
+	pub fn multiplied_by_matrix(
+		&self,
+		other: &IntegerMatrix,
+	) -> Result<IntegerMatrix, MathMatrixError> {
+		return self.multiplied_by_matrix_with(other, OverflowMode::Checked);
+	}
+
+	/* Every product and partial sum goes through `overflow`. Checked fails
+	on any intermediate overflow, even if the exact entry would fit; Saturating
+	clamps partial sums, so a clamped entry is only a bound.
+	*/
+	pub fn multiplied_by_matrix_with(
+		&self,
+		other: &IntegerMatrix,
+		overflow: OverflowMode,
+	) -> Result<IntegerMatrix, MathMatrixError> {
+		if self.cols != other.rows {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Multiplication allowed for NxM * MxO".to_owned(),
+			));
+		}
+		let mut product = Self::zeros(self.rows, other.cols)?;
+		for j in 0..other.cols {
+			for i in 0..self.rows {
+				let mut sum = 0i64;
+				for k in 0..self.cols {
+					sum = overflow
+						.mul(self.data[k * self.rows + i], other.data[j * other.rows + k])
+						.and_then(|term| overflow.add(sum, term))
+						.ok_or_else(|| self.overflow_error(i, j))?;
+				}
+				product.data[j * self.rows + i] = sum;
+			}
+		}
+		return Ok(product);
+	}
+
+	fn check_position(&self, row: usize, col: usize) -> Result<(), MathMatrixError> {
+		if row >= self.rows || col >= self.cols {
+			return Err(MathMatrixError::new(
+				OutOfBoundary,
+				format!(
+					"Entry ({}, {}) outside a {}x{} matrix",
+					row, col, self.rows, self.cols
+				),
+			));
+		}
+		return Ok(());
+	}
+
+	fn overflow_error(&self, row: usize, col: usize) -> MathMatrixError {
+		return MathMatrixError::new(
+			Overflow,
+			format!("Integer overflow computing entry ({}, {})", row, col),
+		);
+	}
+}
+
+impl std::ops::Add for IntegerMatrix {
+	type Output = Result<IntegerMatrix, MathMatrixError>;
+
+	fn add(self, other: IntegerMatrix) -> Result<IntegerMatrix, MathMatrixError> {
+		return self.added_to(&other);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_integer_arithmetic() {
+		// Fibonacci numbers from powers of [1, 1; 1, 0]
+		let q = IntegerMatrix::new(2, 2, vec![1, 1, 1, 0]).unwrap();
+		let mut power = IntegerMatrix::identity(2).unwrap();
+		for _ in 0..10 {
+			power = power.multiplied_by_matrix(&q).unwrap();
+		}
+		assert_eq!(power.get_value(0, 1).unwrap(), 55);
+		let sum = (power.clone() + q).unwrap();
+		assert_eq!(sum.get_data(), &[90, 56, 56, 34]);
+		assert_eq!(sum.to_matrix().get_value(0, 0).unwrap(), 90.0);
+		assert!(power.get_value(2, 0).is_err());
+		assert!(power
+			.multiplied_by_matrix(&IntegerMatrix::zeros(3, 1).unwrap())
+			.is_err());
+	}
+
+	#[test]
+	fn test_integer_overflow() {
+		let big = IntegerMatrix::new(1, 2, vec![i64::MAX, 2]).unwrap();
+		let one = IntegerMatrix::new(1, 2, vec![1, -3]).unwrap();
+		let error = big.added_to(&one).unwrap_err();
+		assert_eq!(error.get_kind(), "Overflow");
+		assert_eq!(
+			big.added_to_with(&one, OverflowMode::Wrapping)
+				.unwrap()
+				.get_data(),
+			&[i64::MIN, -1]
+		);
+		assert_eq!(
+			big.added_to_with(&one, OverflowMode::Saturating)
+				.unwrap()
+				.get_data(),
+			&[i64::MAX, -1]
+		);
+		let column = IntegerMatrix::new(2, 1, vec![2, 1]).unwrap();
+		assert!(big.multiplied_by_matrix(&column).is_err());
+		assert_eq!(
+			big.multiplied_by_matrix_with(&column, OverflowMode::Saturating)
+				.unwrap()
+				.get_data(),
+			&[i64::MAX]
+		);
+		assert_eq!(
+			big.multiplied_by_matrix_with(&column, OverflowMode::Wrapping)
+				.unwrap()
+				.get_data(),
+			&[0]
+		);
+	}
+}
@@ -0,0 +1,358 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+use std::collections::HashMap;
+
+// How the values of a group of rows are combined into one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Agg {
+	Sum,
+	Mean,
+	Min,
+	Max,
+	Count,
+}
+
+impl Agg {
+	// `values` is never empty: every group has at least one row
+	pub fn apply(&self, values: &[f64]) -> f64 {
+		return match self {
+			Agg::Sum => values.iter().sum(),
+			Agg::Mean => values.iter().sum::<f64>() / values.len() as f64,
+			Agg::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+			Agg::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+			Agg::Count => values.len() as f64,
+		};
+	}
+}
+
+// One row of a LabeledMatrix, as seen by `filter_rows`
+pub struct LabeledRow<'a> {
+	matrix: &'a LabeledMatrix,
+	position: usize,
+}
+
+impl<'a> LabeledRow<'a> {
+	pub fn get_label(&self) -> &str {
+		return &self.matrix.row_labels[self.position];
+	}
+
+	// None if there is no such column
+	pub fn get(&self, col: &str) -> Option<f64> {
+		let j = *self.matrix.col_index.get(col)?;
+		return self.matrix.matrix.get_value(self.position, j).ok();
+	}
+}
+
+/* Matrix with a name for every row and column. Entries, rows and columns
+are addressed by name, and selections and slices carry their labels along,
+so names cannot drift out of sync with the data. Labels must be unique
+along each axis.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabeledMatrix {
+	matrix: Matrix,
+	row_labels: Vec<String>,
+	col_labels: Vec<String>,
+	row_index: HashMap<String, usize>,
+	col_index: HashMap<String, usize>,
+}
+
+impl LabeledMatrix {
+	pub fn new(
+		matrix: Matrix,
+		row_labels: Vec<String>,
+		col_labels: Vec<String>,
+	) -> Result<Self, MathMatrixError> {
+		let (rows, cols) = matrix.get_size();
+		if row_labels.len() != rows || col_labels.len() != cols {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				format!(
+					"{} row and {} column labels for a {}x{} matrix",
+					row_labels.len(),
+					col_labels.len(),
+					rows,
+					cols
+				),
+			));
+		}
+		let row_index = index_labels(&row_labels)?;
+		let col_index = index_labels(&col_labels)?;
+		return Ok(Self {
+			matrix,
+			row_labels,
+			col_labels,
+			row_index,
+			col_index,
+		});
+	}
+
+	// Rows labelled "0", "1", ... for data whose rows are just observations
+	pub fn with_col_labels(
+		matrix: Matrix,
+		col_labels: Vec<String>,
+	) -> Result<Self, MathMatrixError> {
+		let row_labels = (0..matrix.get_size().0).map(|i| i.to_string()).collect();
+		return Self::new(matrix, row_labels, col_labels);
+	}
+
+	pub fn get_matrix(&self) -> &Matrix {
+		return &self.matrix;
+	}
+
+	pub fn into_matrix(self) -> Matrix {
+		return self.matrix;
+	}
+
+	pub fn get_row_labels(&self) -> &[String] {
+		return &self.row_labels;
+	}
+
+	pub fn get_col_labels(&self) -> &[String] {
+		return &self.col_labels;
+	}
+
+	pub fn row_position(&self, label: &str) -> Result<usize, MathMatrixError> {
+		return find(&self.row_index, label, "row");
+	}
+
+	pub fn col_position(&self, label: &str) -> Result<usize, MathMatrixError> {
+		return find(&self.col_index, label, "column");
+	}
+
+	pub fn get(&self, row: &str, col: &str) -> Result<f64, MathMatrixError> {
+		return self
+			.matrix
+			.get_value(self.row_position(row)?, self.col_position(col)?);
+	}
+
+	pub fn set(&mut self, row: &str, col: &str, value: f64) -> Result<(), MathMatrixError> {
+		let (i, j) = (self.row_position(row)?, self.col_position(col)?);
+		return self.matrix.set_value(i, j, value);
+	}
+
+	// The named rows and columns, in the order given
+	pub fn select(&self, rows: &[&str], cols: &[&str]) -> Result<LabeledMatrix, MathMatrixError> {
+		let row_positions = rows
+			.iter()
+			.map(|label| self.row_position(label))
+			.collect::<Result<Vec<usize>, MathMatrixError>>()?;
+		let col_positions = cols
+			.iter()
+			.map(|label| self.col_position(label))
+			.collect::<Result<Vec<usize>, MathMatrixError>>()?;
+		return self.take(&row_positions, &col_positions);
+	}
+
+	// All rows, the named columns only
+	pub fn select_cols(&self, cols: &[&str]) -> Result<LabeledMatrix, MathMatrixError> {
+		let col_positions = cols
+			.iter()
+			.map(|label| self.col_position(label))
+			.collect::<Result<Vec<usize>, MathMatrixError>>()?;
+		let row_positions = (0..self.row_labels.len()).collect::<Vec<usize>>();
+		return self.take(&row_positions, &col_positions);
+	}
+
+	// The rows for which `predicate` holds, in their original order
+	pub fn filter_rows<F: Fn(&LabeledRow) -> bool>(
+		&self,
+		predicate: F,
+	) -> Result<LabeledMatrix, MathMatrixError> {
+		let row_positions = (0..self.row_labels.len())
+			.filter(|position| {
+				predicate(&LabeledRow {
+					matrix: self,
+					position: *position,
+				})
+			})
+			.collect::<Vec<usize>>();
+		if row_positions.is_empty() {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"No row satisfies the filter".to_owned(),
+			));
+		}
+		let col_positions = (0..self.col_labels.len()).collect::<Vec<usize>>();
+		return self.take(&row_positions, &col_positions);
+	}
+
+	/* One row per distinct value of column `key`, in order of first
+	appearance and labelled with that value; the other columns are combined
+	with `agg` over the rows of each group.
+	*/
+	pub fn group_by(&self, key: &str, agg: Agg) -> Result<LabeledMatrix, MathMatrixError> {
+		let key_col = self.col_position(key)?;
+		let mut group_labels: Vec<String> = vec![];
+		let mut groups: Vec<Vec<usize>> = vec![];
+		let mut group_index: HashMap<String, usize> = HashMap::new();
+		for i in 0..self.row_labels.len() {
+			let label = self.matrix.get_value(i, key_col)?.to_string();
+			let group = *group_index.entry(label.clone()).or_insert_with(|| {
+				group_labels.push(label);
+				groups.push(vec![]);
+				return groups.len() - 1;
+			});
+			groups[group].push(i);
+		}
+		let value_cols = (0..self.col_labels.len())
+			.filter(|j| *j != key_col)
+			.collect::<Vec<usize>>();
+		if value_cols.is_empty() {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				"Grouping requires at least one column besides the key".to_owned(),
+			));
+		}
+		let mut data = Vec::with_capacity(groups.len() * value_cols.len());
+		for j in value_cols.iter() {
+			for rows in groups.iter() {
+				let values = rows
+					.iter()
+					.map(|i| self.matrix.get_value(*i, *j))
+					.collect::<Result<Vec<f64>, MathMatrixError>>()?;
+				data.push(agg.apply(&values));
+			}
+		}
+		return Self::new(
+			Matrix::new(groups.len(), value_cols.len(), data)?,
+			group_labels,
+			value_cols
+				.iter()
+				.map(|j| self.col_labels[*j].clone())
+				.collect(),
+		);
+	}
+
+	// Rows row..row + rows and columns col..col + cols, with their labels
+	pub fn slice(
+		&self,
+		row: usize,
+		col: usize,
+		rows: usize,
+		cols: usize,
+	) -> Result<LabeledMatrix, MathMatrixError> {
+		let block = self.matrix.get_block(row, col, rows, cols)?;
+		return Self::new(
+			block,
+			self.row_labels[row..row + rows].to_vec(),
+			self.col_labels[col..col + cols].to_vec(),
+		);
+	}
+
+	// Rows and columns at the given positions, with their labels
+	fn take(
+		&self,
+		row_positions: &[usize],
+		col_positions: &[usize],
+	) -> Result<LabeledMatrix, MathMatrixError> {
+		let mut data = Vec::with_capacity(row_positions.len() * col_positions.len());
+		for j in col_positions.iter() {
+			for i in row_positions.iter() {
+				data.push(self.matrix.get_value(*i, *j)?);
+			}
+		}
+		return Self::new(
+			Matrix::new(row_positions.len(), col_positions.len(), data)?,
+			row_positions
+				.iter()
+				.map(|i| self.row_labels[*i].clone())
+				.collect(),
+			col_positions
+				.iter()
+				.map(|j| self.col_labels[*j].clone())
+				.collect(),
+		);
+	}
+}
+
+fn index_labels(labels: &[String]) -> Result<HashMap<String, usize>, MathMatrixError> {
+	let mut index = HashMap::with_capacity(labels.len());
+	for (position, label) in labels.iter().enumerate() {
+		if index.insert(label.clone(), position).is_some() {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				format!("Duplicate label {:?}", label),
+			));
+		}
+	}
+	return Ok(index);
+}
+
+fn find(index: &HashMap<String, usize>, label: &str, axis: &str) -> Result<usize, MathMatrixError> {
+	return index.get(label).copied().ok_or_else(|| {
+		MathMatrixError::new(OutOfBoundary, format!("No {} labelled {:?}", axis, label))
+	});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn labels(names: &[&str]) -> Vec<String> {
+		return names.iter().map(|name| name.to_string()).collect();
+	}
+
+	#[test]
+	fn test_labeled_matrix() {
+		let data = Matrix::new(3, 2, vec![1.0, 2.0, 3.0, 10.0, 20.0, 30.0]).unwrap();
+		let mut labeled =
+			LabeledMatrix::new(data, labels(&["a", "b", "c"]), labels(&["x", "y"])).unwrap();
+		assert_eq!(labeled.get("b", "y").unwrap(), 20.0);
+		labeled.set("c", "x", -3.0).unwrap();
+		let selected = labeled.select(&["c", "a"], &["y", "x"]).unwrap();
+		assert_eq!(selected.get_row_labels(), &labels(&["c", "a"])[..]);
+		assert_eq!(
+			selected.get_matrix().get_data(),
+			vec![30.0, 10.0, -3.0, 1.0]
+		);
+		let sliced = labeled.slice(1, 1, 2, 1).unwrap();
+		assert_eq!(sliced.get("c", "y").unwrap(), 30.0);
+		assert!(sliced.get("a", "y").is_err());
+		assert!(labeled.get("b", "z").is_err());
+		let data = Matrix::zeros(2, 1).unwrap();
+		assert!(LabeledMatrix::new(data.clone(), labels(&["a", "a"]), labels(&["x"])).is_err());
+		assert!(LabeledMatrix::with_col_labels(data, labels(&["x", "y"])).is_err());
+	}
+
+	#[test]
+	fn test_select_filter_group() {
+		// Columns: store, price, quantity
+		let data = Matrix::new(
+			4,
+			3,
+			vec![1.0, 2.0, 1.0, 2.0, 10.0, 4.0, 6.0, 8.0, 3.0, 5.0, 1.0, 7.0],
+		)
+		.unwrap();
+		let sales =
+			LabeledMatrix::with_col_labels(data, labels(&["store", "price", "quantity"])).unwrap();
+		let selected = sales.select_cols(&["quantity", "store"]).unwrap();
+		assert_eq!(
+			selected.get_col_labels(),
+			&labels(&["quantity", "store"])[..]
+		);
+		assert_eq!(selected.get("3", "quantity").unwrap(), 7.0);
+		let cheap = sales
+			.filter_rows(|row| row.get("price").unwrap() < 7.0)
+			.unwrap();
+		assert_eq!(cheap.get_row_labels(), &labels(&["1", "2"])[..]);
+		assert!(sales
+			.filter_rows(|row| row.get("missing").is_some())
+			.is_err());
+		let totals = sales.group_by("store", Agg::Sum).unwrap();
+		assert_eq!(totals.get_row_labels(), &labels(&["1", "2"])[..]);
+		assert_eq!(totals.get("1", "price").unwrap(), 16.0);
+		assert_eq!(totals.get("2", "quantity").unwrap(), 12.0);
+		let counts = sales.group_by("store", Agg::Count).unwrap();
+		assert_eq!(counts.get("2", "price").unwrap(), 2.0);
+		assert_eq!(
+			sales
+				.group_by("store", Agg::Max)
+				.unwrap()
+				.get("1", "price")
+				.unwrap(),
+			10.0
+		);
+	}
+}
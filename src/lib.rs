@@ -1,2 +1,56 @@
+#![allow(
+	clippy::needless_return,
+	clippy::needless_range_loop,
+	clippy::to_string_trait_impl
+)]
+
+#[macro_use]
+mod logging;
+
+pub mod algorithms;
+pub mod arena;
+pub mod axis;
+pub mod banded;
+pub mod batch;
+pub mod bit;
+pub mod block;
+pub mod cancel;
+pub mod config;
+pub mod control;
+pub mod covariance;
+pub mod diagonal;
+pub mod eigen;
 pub mod error;
+pub mod filters;
+pub mod functions;
+pub mod hmm;
+pub mod integer;
+pub mod labeled;
 pub mod matrix;
+pub mod modular;
+pub mod multigrid;
+mod multiplication;
+pub mod operator;
+pub mod oracle;
+pub mod permutation;
+pub mod policy;
+pub mod polynomial;
+pub mod random;
+mod reduction;
+mod rolling;
+pub mod rounding;
+pub mod solvers;
+pub mod sparse;
+pub mod sparse_cholesky;
+#[cfg(feature = "spy")]
+mod spy;
+pub mod stats;
+mod storage;
+pub mod svd;
+pub mod symmetric;
+pub mod timeseries;
+pub mod toeplitz;
+pub mod triangular;
+pub mod vector;
+pub mod view;
+pub mod workspace;
@@ -0,0 +1,69 @@
+/* Warnings about numerical trouble that does not make an operation fail,
+such as a near-singular pivot or an iterative solver stopping at its
+iteration limit. With the `log` feature they are emitted as `log` records
+at Warn level, targeted at the module that raised them; without it they
+compile to nothing.
+*/
+
+#[cfg(feature = "log")]
+macro_rules! numerical_warning {
+	($($arg:tt)+) => {
+		log::warn!($($arg)+)
+	};
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! numerical_warning {
+	($($arg:tt)+) => {
+		// Type-checks the arguments without formatting them
+		if false {
+			let _ = format_args!($($arg)+);
+		}
+	};
+}
+
+#[cfg(all(test, feature = "log"))]
+mod tests {
+	use crate::matrix::Matrix;
+	use crate::solvers;
+	use std::sync::Mutex;
+
+	struct Capture(Mutex<Vec<(String, String)>>);
+
+	impl log::Log for Capture {
+		fn enabled(&self, metadata: &log::Metadata) -> bool {
+			return metadata.level() <= log::Level::Warn;
+		}
+
+		fn log(&self, record: &log::Record) {
+			let entry = (record.target().to_owned(), record.args().to_string());
+			self.0.lock().unwrap().push(entry);
+		}
+
+		fn flush(&self) {}
+	}
+
+	static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+
+	#[test]
+	fn test_numerical_warnings() {
+		log::set_logger(&CAPTURE).unwrap();
+		log::set_max_level(log::LevelFilter::Warn);
+		let singular = Matrix::new(2, 2, vec![1.0, 2.0, 2.0, 4.0]).unwrap();
+		assert!(singular.solve(&Matrix::identity(2, 2).unwrap()).is_err());
+		let a = crate::multigrid::laplacian_1d(10).unwrap();
+		let b = Matrix::new(10, 1, vec![1.0; 10]).unwrap();
+		assert!(
+			!solvers::conjugate_gradient(&a, &b, 1e-12, 2)
+				.unwrap()
+				.converged
+		);
+		let records = CAPTURE.0.lock().unwrap();
+		assert!(records.iter().any(|(target, message)| {
+			return target == "math::matrix" && message.contains("pivot");
+		}));
+		assert!(records.iter().any(|(target, message)| {
+			return target == "math::solvers" && message.contains("did not converge");
+		}));
+	}
+}
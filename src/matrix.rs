@@ -1,11 +1,37 @@
+use super::arena::Arena;
+use super::config;
 use super::error::MathMatrixError;
 use super::error::MathMatrixErrorKind::*;
+use super::multiplication;
+use super::oracle;
+use super::permutation::Permutation;
+use super::policy::{self, MultiplicationAlgorithm};
+use super::reduction;
+use super::stats::{Operation, Probe};
+use super::storage::Storage;
+use super::view::MatrixView;
+use super::workspace::Workspace;
+
+// Matrix norms computed by `Matrix::norm`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormKind {
+	// Square root of the sum of squared entries
+	Frobenius,
+	// Largest absolute column sum
+	One,
+	// Largest absolute row sum
+	Infinity,
+	// Largest singular value, the 2-norm
+	Spectral,
+	// Largest absolute entry, not submultiplicative
+	Max,
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Matrix {
 	rows: usize,
 	cols: usize,
-	data: Vec<f64>,
+	data: Storage,
 }
 
 impl std::ops::Add for Matrix {
@@ -13,9 +39,10 @@ impl std::ops::Add for Matrix {
 
 	fn add(self, other: Matrix) -> Result<Matrix, MathMatrixError> {
 		if self.get_size() == other.get_size() {
-			let mut new_data = vec![0f64; self.rows * self.cols];
+			// Reuse the buffer of self
+			let mut new_data = self.data;
 			for i in 0..(self.rows * self.cols) {
-				new_data[i] = self.data[i] + other.data[i];
+				new_data[i] += other.data[i];
 			}
 			Ok(Matrix {
 				rows: self.rows,
@@ -36,9 +63,10 @@ impl std::ops::Sub for Matrix {
 
 	fn sub(self, other: Matrix) -> Result<Matrix, MathMatrixError> {
 		if self.get_size() == other.get_size() {
-			let mut new_data = vec![0f64; self.rows * self.cols];
+			// Reuse the buffer of self
+			let mut new_data = self.data;
 			for i in 0..(self.rows * self.cols) {
-				new_data[i] = self.data[i] - other.data[i];
+				new_data[i] -= other.data[i];
 			}
 			Ok(Matrix {
 				rows: self.rows,
@@ -71,7 +99,11 @@ impl Matrix {
 			));
 		}
 		if rows * cols == data.len() {
-			Ok(Self { rows, cols, data })
+			Ok(Self {
+				rows,
+				cols,
+				data: Storage::from(data),
+			})
 		} else {
 			Err(MathMatrixError::new(
 				FailedToInitialize,
@@ -84,18 +116,122 @@ impl Matrix {
 		}
 	}
 
+	// Take ownership of `data` without copying it or checking its length.
+	// The caller guarantees data.len() == rows * cols with rows, cols > 0.
+	pub fn from_vec_unchecked(rows: usize, cols: usize, data: Vec<f64>) -> Self {
+		debug_assert!(rows * cols > 0 && rows * cols == data.len());
+		return Self {
+			rows,
+			cols,
+			data: Storage::from(data),
+		};
+	}
+
+	// Take ownership of a boxed slice without copying it
+	pub fn from_boxed_slice(
+		rows: usize,
+		cols: usize,
+		data: Box<[f64]>,
+	) -> Result<Self, MathMatrixError> {
+		return Self::new(rows, cols, Vec::from(data));
+	}
+
+	// Give back the column-major buffer, copying it unless the storage is on the heap
+	pub fn into_vec(self) -> Vec<f64> {
+		return self.data.into_vec();
+	}
+
+	// Matrices with up to INLINE_CAPACITY elements are stored without a heap allocation
 	pub fn zeros(rows: usize, cols: usize) -> Result<Self, MathMatrixError> {
-		return Self::new(rows, cols, vec![0f64; rows * cols]);
+		if rows * cols == 0 {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"Rows and columns must be lager than 0".to_owned(),
+			));
+		}
+		return Ok(Self {
+			rows,
+			cols,
+			data: Storage::zeros(rows * cols),
+		});
+	}
+
+	// Zero matrix whose buffer starts at a multiple of `alignment` bytes (a power of two >= 8).
+	// Matrices produced by operations on it use regular heap storage.
+	pub fn zeros_aligned(
+		rows: usize,
+		cols: usize,
+		alignment: usize,
+	) -> Result<Self, MathMatrixError> {
+		if rows * cols == 0 {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"Rows and columns must be lager than 0".to_owned(),
+			));
+		}
+		match Storage::aligned_zeros(rows * cols, alignment) {
+			Some(data) => Ok(Self { rows, cols, data }),
+			None => Err(MathMatrixError::new(
+				FailedToInitialize,
+				format!("Invalid alignment: {}", alignment),
+			)),
+		}
+	}
+
+	// Zero matrix whose buffer is bump-allocated from the arena
+	pub fn zeros_in(arena: &Arena, rows: usize, cols: usize) -> Result<Self, MathMatrixError> {
+		if rows * cols == 0 {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"Rows and columns must be lager than 0".to_owned(),
+			));
+		}
+		return Ok(Self {
+			rows,
+			cols,
+			data: Storage::Arena(arena.take(rows * cols)),
+		});
+	}
+
+	// Copy of column-major `data` in a buffer from the arena
+	pub fn new_in(
+		arena: &Arena,
+		rows: usize,
+		cols: usize,
+		data: &[f64],
+	) -> Result<Self, MathMatrixError> {
+		if rows * cols != data.len() {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				format!(
+					"Size of data != rows * cols: {} != {}",
+					data.len(),
+					rows * cols
+				),
+			));
+		}
+		let mut mat = Self::zeros_in(arena, rows, cols)?;
+		mat.data.copy_from_slice(data);
+		return Ok(mat);
+	}
+
+	// Copy of this matrix in aligned storage
+	pub fn to_aligned(&self, alignment: usize) -> Result<Self, MathMatrixError> {
+		let mut aligned = Self::zeros_aligned(self.rows, self.cols, alignment)?;
+		aligned.data.copy_from_slice(&self.data);
+		return Ok(aligned);
+	}
+
+	pub fn is_aligned_to(&self, alignment: usize) -> bool {
+		return alignment != 0 && (self.data.as_ptr() as usize).is_multiple_of(alignment);
 	}
 
 	pub fn identity(rows: usize, cols: usize) -> Result<Self, MathMatrixError> {
-		let mut data = vec![0f64; rows * cols];
-		for j in 0..cols {
-			for i in 0..rows {
-				data[i + rows * j] = if i == j { 1.0 } else { 0.0 }
-			}
+		let mut identity = Self::zeros(rows, cols)?;
+		for i in 0..rows.min(cols) {
+			identity.data[i + rows * i] = 1.0;
 		}
-		return Self::new(rows, cols, data);
+		return Ok(identity);
 	}
 
 	pub fn set_value(&mut self, row: usize, col: usize, value: f64) -> Result<(), MathMatrixError> {
@@ -134,25 +270,54 @@ impl Matrix {
 	}
 
 	pub fn multiplied_by_matrix(&self, other: &Matrix) -> Result<Self, MathMatrixError> {
+		let algorithm =
+			policy::global_policy().multiplication_for(self.rows, self.cols, other.cols);
+		return self.multiplied_by_matrix_with(other, algorithm);
+	}
+
+	pub fn multiplied_by_matrix_with(
+		&self,
+		other: &Matrix,
+		algorithm: MultiplicationAlgorithm,
+	) -> Result<Self, MathMatrixError> {
 		if self.cols != other.rows {
 			return Err(MathMatrixError::new(
 				SizeMismatch,
 				"Multiplication allowed for NxM * MxO".to_owned(),
 			));
 		}
-		let rows = self.rows;
-		let cols = other.cols;
-		let mut out_mat = Matrix::new(rows, cols, vec![0f64; rows * cols]).unwrap();
-		for i in 0..self.rows {
-			for j in 0..other.cols {
-				let mut sum: f64 = 0.;
-				for k in 0..self.cols {
-					sum += self.get_value(i, k)? * other.get_value(k, j)?;
-				}
-				out_mat.set_value(i, j, sum).unwrap();
+		let probe = Probe::start(Operation::MatrixMultiplication);
+		let (rows, inner, cols) = (self.rows, self.cols, other.cols);
+		let size = (rows, inner, cols);
+		let threads = config::max_threads();
+		let mut data = Storage::zeros(rows * cols);
+		match algorithm {
+			MultiplicationAlgorithm::Naive => multiplication::by_columns(
+				multiplication::naive,
+				&self.data,
+				&other.data,
+				size,
+				threads,
+				&mut data,
+			),
+			MultiplicationAlgorithm::Blocked => multiplication::by_columns(
+				multiplication::blocked,
+				&self.data,
+				&other.data,
+				size,
+				threads,
+				&mut data,
+			),
+			// The recursion pads to a square power of two, so it stays on one thread
+			MultiplicationAlgorithm::Strassen => {
+				multiplication::strassen(&self.data, &other.data, rows, inner, cols, &mut data)
 			}
-		}
-		return Ok(out_mat);
+		};
+		probe.finish(
+			2 * (rows * cols * inner) as u64,
+			u64::from(!data.is_inline()),
+		);
+		return Ok(Matrix { rows, cols, data });
 	}
 
 	pub fn multiplied_by_scalar(&self, scalar: f64) -> Self {
@@ -167,6 +332,34 @@ impl Matrix {
 		return output_matrix;
 	}
 
+	// Hadamard product: entry (i, j) is a_ij * b_ij
+	pub fn elementwise_mul(&self, other: &Matrix) -> Result<Matrix, MathMatrixError> {
+		return self.zip_with(other, |a, b| a * b);
+	}
+
+	// Entry (i, j) is a_ij / b_ij; zero entries of `other` give inf or NaN
+	pub fn elementwise_div(&self, other: &Matrix) -> Result<Matrix, MathMatrixError> {
+		return self.zip_with(other, |a, b| a / b);
+	}
+
+	fn zip_with<F: Fn(f64, f64) -> f64>(
+		&self,
+		other: &Matrix,
+		f: F,
+	) -> Result<Matrix, MathMatrixError> {
+		if self.get_size() != other.get_size() {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Operation not allowed between matrices with different sizes".to_owned(),
+			));
+		}
+		let mut output_matrix = self.clone();
+		for i in 0..(self.rows * self.cols) {
+			output_matrix.data[i] = f(self.data[i], other.data[i]);
+		}
+		return Ok(output_matrix);
+	}
+
 	pub fn transposed(&self) -> Self {
 		// Create an empty matrix with transposed size
 		let mut transposed_matrix = Self::zeros(self.cols, self.rows).unwrap();
@@ -180,6 +373,190 @@ impl Matrix {
 		return transposed_matrix;
 	}
 
+	// vec(A): the columns of A stacked into an (M * N)x1 matrix
+	pub fn vec(&self) -> Self {
+		return Self {
+			rows: self.rows * self.cols,
+			cols: 1,
+			data: self.data.clone(),
+		};
+	}
+
+	// Inverse of `vec`: reshapes an Nx1 matrix into rows x cols, column by column
+	pub fn unvec(&self, rows: usize, cols: usize) -> Result<Self, MathMatrixError> {
+		if self.cols != 1 || self.rows != rows * cols {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				format!(
+					"Cannot reshape a {}x{} matrix into {}x{}",
+					self.rows, self.cols, rows, cols
+				),
+			));
+		}
+		return Self::new(rows, cols, self.get_data());
+	}
+
+	/* Kronecker product A (x) B: (M * P)x(N * Q) for A: MxN and B: PxQ, made
+	of the blocks a_ij * B. With it, vec(A * X * B) = (B^T (x) A) * vec(X).
+	*/
+	pub fn kronecker(&self, other: &Matrix) -> Matrix {
+		let (rows, cols) = (self.rows * other.rows, self.cols * other.cols);
+		let mut data = Vec::with_capacity(rows * cols);
+		for j in 0..self.cols {
+			for l in 0..other.cols {
+				for i in 0..self.rows {
+					let a_ij = self.data[j * self.rows + i];
+					let b_col = &other.data[l * other.rows..(l + 1) * other.rows];
+					data.extend(b_col.iter().map(|b| a_ij * b));
+				}
+			}
+		}
+		return Self::from_vec_unchecked(rows, cols, data);
+	}
+
+	/* Commutation matrix K: (M * N)x(M * N) such that K * vec(A) = vec(A^T)
+	for any A: MxN. It is a permutation matrix.
+	*/
+	pub fn commutation(rows: usize, cols: usize) -> Result<Self, MathMatrixError> {
+		let size = rows * cols;
+		let mut commutation = Self::zeros(size, size)?;
+		for i in 0..rows {
+			for j in 0..cols {
+				// a_ij is entry i + j * M of vec(A) and entry j + i * N of vec(A^T)
+				commutation.set_value(j + i * cols, i + j * rows, 1.0)?;
+			}
+		}
+		return Ok(commutation);
+	}
+
+	/* Duplication matrix D: N^2 x N(N + 1)/2 such that D * vech(A) = vec(A)
+	for any symmetric A: NxN, where vech(A) stacks the columns of the lower
+	triangle (diagonal included)
+	*/
+	pub fn duplication(size: usize) -> Result<Self, MathMatrixError> {
+		let mut duplication = Self::zeros(size * size, size * (size + 1) / 2)?;
+		let mut k = 0;
+		for j in 0..size {
+			for i in j..size {
+				// vech entry k is a_ij = a_ji
+				duplication.set_value(i + j * size, k, 1.0)?;
+				duplication.set_value(j + i * size, k, 1.0)?;
+				k += 1;
+			}
+		}
+		return Ok(duplication);
+	}
+
+	// Build a new matrix from f(i, row_i). All returned rows must have the same length.
+	pub fn map_rows<F>(&self, f: F) -> Result<Self, MathMatrixError>
+	where
+		F: Fn(usize, &[f64]) -> Vec<f64>,
+	{
+		let mut new_rows: Vec<Vec<f64>> = Vec::with_capacity(self.rows);
+		for i in 0..self.rows {
+			new_rows.push(f(i, &self.get_row(i)));
+		}
+		let cols = new_rows[0].len();
+		if let Some(i) = new_rows.iter().position(|row| row.len() != cols) {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				format!("Row {} has length {} != {}", i, new_rows[i].len(), cols),
+			));
+		}
+		let mut data = vec![0f64; self.rows * cols];
+		for (i, row) in new_rows.iter().enumerate() {
+			for (j, value) in row.iter().enumerate() {
+				data[j * self.rows + i] = *value;
+			}
+		}
+		return Self::new(self.rows, cols, data);
+	}
+
+	// Build a new matrix from f(j, col_j). All returned columns must have the same length.
+	pub fn map_cols<F>(&self, f: F) -> Result<Self, MathMatrixError>
+	where
+		F: Fn(usize, &[f64]) -> Vec<f64>,
+	{
+		let mut data: Vec<f64> = Vec::with_capacity(self.rows * self.cols);
+		let mut rows = 0;
+		for j in 0..self.cols {
+			let new_col = f(j, &self.data[j * self.rows..(j + 1) * self.rows]);
+			if j == 0 {
+				rows = new_col.len();
+			} else if new_col.len() != rows {
+				return Err(MathMatrixError::new(
+					SizeMismatch,
+					format!("Column {} has length {} != {}", j, new_col.len(), rows),
+				));
+			}
+			data.extend(new_col);
+		}
+		return Self::new(rows, self.cols, data);
+	}
+
+	// Apply f(i, row_i) in place, row by row
+	pub fn map_rows_mut<F>(&mut self, mut f: F)
+	where
+		F: FnMut(usize, &mut [f64]),
+	{
+		for i in 0..self.rows {
+			let mut row = self.get_row(i);
+			f(i, &mut row);
+			for (j, value) in row.iter().enumerate() {
+				self.data[j * self.rows + i] = *value;
+			}
+		}
+	}
+
+	// Apply f(j, col_j) in place, column by column
+	pub fn map_cols_mut<F>(&mut self, mut f: F)
+	where
+		F: FnMut(usize, &mut [f64]),
+	{
+		for (j, col) in self.data.chunks_mut(self.rows).enumerate() {
+			f(j, col);
+		}
+	}
+
+	// Matrix of f(a_ij), entry by entry
+	pub fn map<F: Fn(f64) -> f64>(&self, f: F) -> Self {
+		let mut output_matrix = self.clone();
+		for i in 0..(self.rows * self.cols) {
+			output_matrix.data[i] = f(self.data[i]);
+		}
+		return output_matrix;
+	}
+
+	/* Common element-wise functions. Unlike `expm`, `pow` or `apply_function`,
+	these act on each entry separately, not on the matrix as a linear map.
+	*/
+	pub fn abs(&self) -> Self {
+		return self.map(f64::abs);
+	}
+
+	pub fn exp(&self) -> Self {
+		return self.map(f64::exp);
+	}
+
+	// Natural logarithm; NaN for negative entries and -inf for zeros
+	pub fn ln(&self) -> Self {
+		return self.map(f64::ln);
+	}
+
+	pub fn powf(&self, exponent: f64) -> Self {
+		return self.map(|x| x.powf(exponent));
+	}
+
+	pub fn signum(&self) -> Self {
+		return self.map(f64::signum);
+	}
+
+	fn get_row(&self, row: usize) -> Vec<f64> {
+		return (0..self.cols)
+			.map(|j| self.data[j * self.rows + row])
+			.collect();
+	}
+
 	pub fn decompose(&self) -> Result<(Matrix, Matrix), MathMatrixError> {
 		let (rows, cols) = self.get_size();
 		if rows != cols {
@@ -210,84 +587,399 @@ impl Matrix {
 		return Ok((l, u));
 	}
 
-	pub fn invert(&self) -> Result<Matrix, MathMatrixError> {
+	// LU decomposition with partial pivoting: P * A = L * U
+	pub fn plu_decompose(&self) -> Result<(Matrix, Matrix, Matrix), MathMatrixError> {
+		let (permutation, l_mat, u_mat) = self.plu_permutation()?;
+		return Ok((permutation.to_matrix()?, l_mat, u_mat));
+	}
+
+	// `plu_decompose` with P as an index vector, applied in O(N^2) without multiplying
+	pub fn plu_permutation(&self) -> Result<(Permutation, Matrix, Matrix), MathMatrixError> {
+		let (permutation, l_mat, u_mat) = self.pivoted_lu(&mut Workspace::new())?;
+		return Ok((Permutation::new(permutation)?, l_mat, u_mat));
+	}
+
+	// det(A) = det(P) * prod(diag(U)), where det(P) is the sign of the permutation
+	pub fn determinant(&self) -> Result<f64, MathMatrixError> {
+		let (permutation, _, u_mat) = self.plu_permutation()?;
+		let size = self.rows;
+		let determinant = reduction::product((0..size).map(|i| u_mat.data[i * size + i]));
+		return Ok(permutation.sign() * determinant);
+	}
+
+	// Sum of the diagonal entries, defined for square matrices only
+	pub fn trace(&self) -> Result<f64, MathMatrixError> {
+		if self.rows != self.cols {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				"Trace allowed only for square matrices".to_owned(),
+			));
+		}
+		return Ok((0..self.rows).map(|i| self.data[i * self.rows + i]).sum());
+	}
+
+	// Spectral needs an SVD and can fail; the others cannot
+	pub fn norm(&self, kind: NormKind) -> Result<f64, MathMatrixError> {
+		let data = self.get_data();
+		return match kind {
+			NormKind::Frobenius => Ok(reduction::norm(&data)),
+			NormKind::One => Ok(data
+				.chunks(self.rows)
+				.map(|column| column.iter().map(|x| x.abs()).sum::<f64>())
+				.fold(0f64, f64::max)),
+			NormKind::Infinity => Ok((0..self.rows)
+				.map(|i| {
+					(0..self.cols)
+						.map(|j| data[j * self.rows + i].abs())
+						.sum::<f64>()
+				})
+				.fold(0f64, f64::max)),
+			NormKind::Spectral => self.svd()?.1.get_value(0, 0),
+			NormKind::Max => Ok(data.iter().fold(0f64, |acc, x| acc.max(x.abs()))),
+		};
+	}
+
+	/* Condition number in the 2-norm, sigma_max / sigma_min from the singular
+	values. Infinite for a singular matrix. The number of significant digits
+	lost when solving A * x = b is roughly log10 of this.
+	*/
+	pub fn condition_number(&self) -> Result<f64, MathMatrixError> {
+		let (rows, cols) = self.get_size();
+		if rows != cols {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				"Condition number allowed only for square matrices".to_owned(),
+			));
+		}
+		let (_, s, _) = self.svd()?;
+		let smallest = s.get_value(rows - 1, rows - 1)?;
+		if smallest == 0.0 {
+			return Ok(f64::INFINITY);
+		}
+		return Ok(s.get_value(0, 0)? / smallest);
+	}
+
+	/* Estimate of the 1-norm condition number ||A||_1 * ||A^-1||_1 from one LU
+	factorization, without forming A^-1 (Hager's method, as in LAPACK's
+	xGECON). ||A^-1||_1 is estimated from below, usually exactly or within a
+	small factor.
+	*/
+	pub fn condition_number_1(&self) -> Result<f64, MathMatrixError> {
+		let (permutation, l_mat, u_mat) = self.pivoted_lu(&mut Workspace::new())?;
 		let size = self.rows;
-		let (l_mat, u_mat) = self.decompose()?;
+		if (0..size).any(|i| u_mat.data[i * size + i] == 0.0) {
+			return Ok(f64::INFINITY);
+		}
+		let (l_t, u_t) = (l_mat.transposed(), u_mat.transposed());
+		// A^-1 * x = U^-1 * L^-1 * P * x
+		let solve = |x: &[f64]| -> Result<Vec<f64>, MathMatrixError> {
+			let px = permutation.iter().map(|i| x[*i]).collect();
+			let y_mat = l_mat.solve_lower_triangular(&Matrix::from_vec_unchecked(size, 1, px))?;
+			return Ok(u_mat.solve_upper_triangular(&y_mat)?.into_vec());
+		};
+		// A^-T * x = P^T * L^-T * U^-T * x
+		let solve_transposed = |x: &[f64]| -> Result<Vec<f64>, MathMatrixError> {
+			let x_mat = Matrix::from_vec_unchecked(size, 1, x.to_vec());
+			let y = l_t.solve_upper_triangular(&u_t.solve_lower_triangular(&x_mat)?)?;
+			let mut z = vec![0f64; size];
+			for (row, original_row) in permutation.iter().enumerate() {
+				z[*original_row] = y.data[row];
+			}
+			return Ok(z);
+		};
+		let mut x = vec![1.0 / size as f64; size];
+		let mut estimate = 0f64;
+		for _ in 0..5 {
+			let y = solve(&x)?;
+			estimate = y.iter().map(|v| v.abs()).sum();
+			let signs: Vec<f64> = y
+				.iter()
+				.map(|v| if *v >= 0.0 { 1.0 } else { -1.0 })
+				.collect();
+			let z = solve_transposed(&signs)?;
+			let mut j = 0;
+			for i in 1..size {
+				if z[i].abs() > z[j].abs() {
+					j = i;
+				}
+			}
+			let z_x: f64 = z.iter().zip(x.iter()).map(|(a, b)| a * b).sum();
+			if z[j].abs() <= z_x {
+				break;
+			}
+			x = vec![0f64; size];
+			x[j] = 1.0;
+		}
+		// Alternative lower bound from LAPACK's xLACN2, for matrices that fool the iteration
+		if size > 1 {
+			let alternating: Vec<f64> = (0..size)
+				.map(|i| (1.0 + i as f64 / (size - 1) as f64) * if i % 2 == 0 { 1.0 } else { -1.0 })
+				.collect();
+			let y: f64 = solve(&alternating)?.iter().map(|v| v.abs()).sum();
+			estimate = estimate.max(2.0 * y / (3 * size) as f64);
+		}
+		let norm_1 = (0..size)
+			.map(|j| {
+				self.data[j * size..(j + 1) * size]
+					.iter()
+					.map(|v| v.abs())
+					.sum()
+			})
+			.fold(0f64, f64::max);
+		return Ok(norm_1 * estimate);
+	}
+
+	// Estimate of the infinity-norm condition number, equal to the 1-norm one of A^T
+	pub fn condition_number_inf(&self) -> Result<f64, MathMatrixError> {
+		return self.transposed().condition_number_1();
+	}
+
+	/* Gaussian elimination choosing the largest pivot in each column.
+	Returns the row permutation (row i of P * A is row permutation[i] of A),
+	the unit lower-triangular L and the upper-triangular U. A singular matrix
+	yields a zero on the diagonal of U instead of an error.
+	*/
+	fn pivoted_lu(
+		&self,
+		workspace: &mut Workspace,
+	) -> Result<(Vec<usize>, Matrix, Matrix), MathMatrixError> {
+		let (rows, cols) = self.get_size();
+		if rows != cols {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				"LU decomposition allowed only for square matrices".to_owned(),
+			));
+		}
+		let probe = Probe::start(Operation::LuDecomposition);
+		let size = rows;
+		// Pivots this small relative to the entries of A leave few correct digits
+		let tolerance =
+			size as f64 * f64::EPSILON * self.data.iter().fold(0f64, |acc, x| acc.max(x.abs()));
+		let mut permutation: Vec<usize> = (0..size).collect();
+		let mut u = Matrix::from_vec_unchecked(size, size, workspace.take(size * size));
+		u.data.copy_from_slice(&self.data);
+		let mut l = Matrix::from_vec_unchecked(size, size, workspace.take(size * size));
+		for i in 0..size {
+			l.set_value(i, i, 1.0)?;
+		}
+		for j in 0..size {
+			let mut pivot_row = j;
+			for i in (j + 1)..size {
+				if u.get_value(i, j)?.abs() > u.get_value(pivot_row, j)?.abs() {
+					pivot_row = i;
+				}
+			}
+			if pivot_row != j {
+				permutation.swap(j, pivot_row);
+				u.swap_rows(j, pivot_row, 0..size);
+				l.swap_rows(j, pivot_row, 0..j);
+			}
+			let pivot = u.get_value(j, j)?;
+			if pivot == 0.0 {
+				numerical_warning!("LU pivot in column {} is zero: the matrix is singular", j);
+				continue;
+			}
+			if pivot.abs() <= tolerance {
+				numerical_warning!(
+					"LU pivot {:e} in column {} below tolerance {:e}: result may be inaccurate",
+					pivot,
+					j,
+					tolerance
+				);
+			}
+			for i in (j + 1)..size {
+				let multiplier = u.get_value(i, j)? / pivot;
+				l.set_value(i, j, multiplier)?;
+				u.set_value(i, j, 0.0)?;
+				for k in (j + 1)..size {
+					let elem = u.get_value(i, k)? - multiplier * u.get_value(j, k)?;
+					u.set_value(i, k, elem)?;
+				}
+			}
+		}
+		probe.finish((2 * size * size * size / 3) as u64, 2);
+		return Ok((permutation, l, u));
+	}
+
+	fn swap_rows(&mut self, row_1: usize, row_2: usize, cols: std::ops::Range<usize>) {
+		for j in cols {
+			self.data.swap(j * self.rows + row_1, j * self.rows + row_2);
+		}
+	}
+
+	pub fn invert(&self) -> Result<Matrix, MathMatrixError> {
 		/*
 		Resource: https://www.youtube.com/watch?v=dza5JTvMpzk
 		- Create one column at a time of the identity matrix.
 		- Find the corresponding column of the inverse matrix.
 		- Combine all the resulting columns.
 		*/
-		// Solve for y L*Y = I using "forward substitution"
-		let mut y_mat = Matrix::identity(size, size)?;
-		for col in 0..size {
-			for row in (col + 1)..size {
-				let mut elem = -l_mat.get_value(row, col)?;
-				let mut computation_message = format!(
-					"Y{row},{col} = L{row},{col} [{l_row_col}]",
-					row = row,
-					col = col,
-					l_row_col = elem
-				);
-				for i in (col + 1)..row {
-					let l_row_i = l_mat.get_value(row, i)?;
-					let y_i_col = y_mat.get_value(i, col)?;
-					elem += -l_row_i * y_i_col;
-					computation_message = format!(
-						"{} - L{row},{i}[{l_row_i}] * Y{i},{col}[{y_i_col}]",
-						computation_message,
-						row = row,
-						col = col,
-						i = i,
-						l_row_i = l_row_i,
-						y_i_col = y_i_col,
-					);
+		let size = self.rows;
+		return self.solve(&Matrix::identity(size, size)?);
+	}
+
+	/* A^n by repeated squaring: about 2 * log2(n) products instead of n - 1.
+	A^0 is the identity.
+	*/
+	pub fn pow(&self, n: u32) -> Result<Matrix, MathMatrixError> {
+		if self.rows != self.cols {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				"Power allowed only for square matrices".to_owned(),
+			));
+		}
+		let mut result = Matrix::identity(self.rows, self.cols)?;
+		let mut square = self.clone();
+		let mut exponent = n;
+		while exponent > 0 {
+			if exponent & 1 == 1 {
+				result = result.multiplied_by_matrix(&square)?;
+			}
+			exponent >>= 1;
+			if exponent > 0 {
+				square = square.multiplied_by_matrix(&square)?;
+			}
+		}
+		return Ok(result);
+	}
+
+	// A^n for any integer n, with A^-n = (A^-1)^n; fails if A is singular
+	pub fn pow_i(&self, n: i32) -> Result<Matrix, MathMatrixError> {
+		if n >= 0 {
+			return self.pow(n as u32);
+		}
+		return self.invert()?.pow(n.unsigned_abs());
+	}
+
+	// Solve A * X = B via LU factorization with partial pivoting.
+	// Each column of B is a right-hand side.
+	pub fn solve(&self, b: &Matrix) -> Result<Matrix, MathMatrixError> {
+		return self.solve_with_workspace(b, &mut Workspace::new());
+	}
+
+	// Same as `solve`, borrowing the L and U factors from `workspace`
+	pub fn solve_with_workspace(
+		&self,
+		b: &Matrix,
+		workspace: &mut Workspace,
+	) -> Result<Matrix, MathMatrixError> {
+		if b.rows != self.rows {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				format!(
+					"Right-hand side has {} rows, expected {}",
+					b.rows, self.rows
+				),
+			));
+		}
+		let probe = Probe::start(Operation::Solve);
+		let size = self.rows;
+		let (permutation, l_mat, u_mat) = self.pivoted_lu(workspace)?;
+		let mut pb_mat = Matrix::zeros(size, b.cols)?;
+		for col in 0..b.cols {
+			for row in 0..size {
+				pb_mat.set_value(row, col, b.get_value(permutation[row], col)?)?;
+			}
+		}
+		// L * Y = P * B, then U * X = Y
+		let x_mat = l_mat
+			.solve_lower_triangular(&pb_mat)
+			.and_then(|y_mat| u_mat.solve_upper_triangular(&y_mat))?;
+		workspace.recycle(l_mat.into_vec());
+		workspace.recycle(u_mat.into_vec());
+		probe.finish((2 * size * size * b.cols) as u64, 1);
+		oracle::check_solve(self, b, &x_mat);
+		return Ok(x_mat);
+	}
+
+	/* Solve L * X = B by forward substitution, reading only the lower triangle
+	of self (diagonal included). Reuses an existing factorization, e.g. the L
+	of `plu_decompose` or `cholesky`, for any number of right-hand sides.
+	*/
+	pub fn solve_lower_triangular(&self, b: &Matrix) -> Result<Matrix, MathMatrixError> {
+		self.check_triangular_system(b)?;
+		let size = self.rows;
+		let mut x_mat = Matrix::zeros(size, b.cols)?;
+		for col in 0..b.cols {
+			for row in 0..size {
+				let mut elem = b.get_value(row, col)?;
+				for i in 0..row {
+					elem -= self.get_value(row, i)? * x_mat.get_value(i, col)?;
 				}
-				y_mat.set_value(row, col, elem).ok();
-				println!("{}", computation_message);
-				println!("Elem: {}", elem);
+				x_mat.set_value(row, col, elem / self.get_value(row, row)?)?;
 			}
 		}
+		return Ok(x_mat);
+	}
 
-		// Solve for A (= mat^(-1)) U*A = Y using "back substitution"
-		// 	for row in (0..rows).rev() {
-		// //
-		// 	}
-		// let mut inverted_matrix = Matrix::zeros(cols, rows)?;
-		let mut x_mat = Matrix::zeros(size, size)?;
-		for col in 0..size {
+	// Solve U * X = B by back substitution, reading only the upper triangle of self
+	pub fn solve_upper_triangular(&self, b: &Matrix) -> Result<Matrix, MathMatrixError> {
+		self.check_triangular_system(b)?;
+		let size = self.rows;
+		let mut x_mat = Matrix::zeros(size, b.cols)?;
+		for col in 0..b.cols {
 			for row in (0..size).rev() {
-				let mut elem = y_mat.get_value(row, col)?;
-				let divider = u_mat.get_value(row, row)?;
-				let mut computation_message = format!(
-					"X{row},{col} = 1/U{row},{row}*(Y{row},{col}",
-					row = row,
-					col = col
-				);
+				let mut elem = b.get_value(row, col)?;
 				for i in (row + 1)..size {
-					computation_message = format!(
-						"{} - U{row},{i} * X{i},{col}",
-						computation_message,
-						row = row,
-						col = col,
-						i = i
-					);
-					elem += -u_mat.get_value(row, i)? * x_mat.get_value(i, col)?;
+					elem -= self.get_value(row, i)? * x_mat.get_value(i, col)?;
 				}
-				x_mat.set_value(row, col, elem / divider)?;
-				println!("{})", computation_message);
+				x_mat.set_value(row, col, elem / self.get_value(row, row)?)?;
 			}
 		}
 		return Ok(x_mat);
 	}
 
+	fn check_triangular_system(&self, b: &Matrix) -> Result<(), MathMatrixError> {
+		if self.rows != self.cols || b.rows != self.rows {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Triangular solve requires a square NxN matrix and an NxK right-hand side"
+					.to_owned(),
+			));
+		}
+		if (0..self.rows).any(|i| self.data[i * self.rows + i] == 0.0) {
+			return Err(MathMatrixError::new(
+				FailedToDecompose,
+				"Matrix is singular".to_owned(),
+			));
+		}
+		return Ok(());
+	}
+
+	// Same size and every element within `tolerance`
+	pub fn approx_eq(&self, other: &Matrix, tolerance: f64) -> bool {
+		return self.get_size() == other.get_size()
+			&& self
+				.data
+				.iter()
+				.zip(other.data.iter())
+				.all(|(a, b)| (a - b).abs() <= tolerance);
+	}
+
+	pub fn is_symmetric(&self, tolerance: f64) -> bool {
+		if self.rows != self.cols {
+			return false;
+		}
+		for j in 0..self.cols {
+			for i in (j + 1)..self.rows {
+				if (self.data[j * self.rows + i] - self.data[i * self.rows + j]).abs() > tolerance {
+					return false;
+				}
+			}
+		}
+		return true;
+	}
+
 	pub fn get_size(&self) -> (usize, usize) {
 		return (self.rows, self.cols);
 	}
 
 	pub fn get_data(&self) -> Vec<f64> {
-		return self.data.clone();
+		return self.data.to_vec();
+	}
+
+	pub fn as_view(&self) -> MatrixView<'_> {
+		return MatrixView::new(&self.data, self.rows, self.cols, self.rows).unwrap();
 	}
 
 	pub fn print(&self) {
@@ -299,6 +991,105 @@ impl Matrix {
 		}
 		println!();
 	}
+
+	/* Sign pattern, one character per entry and one line per row: '+' and
+	'-' for the sign, '·' for an exact zero and '?' for NaN. Shows the shape
+	of a large matrix (triangular, banded, block structure) at a glance.
+	*/
+	pub fn structure_string(&self) -> String {
+		return self.structure_string_with(0.0);
+	}
+
+	// As `structure_string`, with '0' for nonzero entries of magnitude up to `tolerance`
+	pub fn structure_string_with(&self, tolerance: f64) -> String {
+		let mut text = String::with_capacity(self.rows * (self.cols + 1) * 2);
+		for i in 0..self.rows {
+			for j in 0..self.cols {
+				let value = self.data[j * self.rows + i];
+				text.push(if value.is_nan() {
+					'?'
+				} else if value == 0.0 {
+					'·'
+				} else if value.abs() <= tolerance {
+					'0'
+				} else if value > 0.0 {
+					'+'
+				} else {
+					'-'
+				});
+			}
+			text.push('\n');
+		}
+		return text;
+	}
+}
+
+// diag(A * B) for A: NxM and B: MxN, without forming the NxN product
+pub fn diag_of_product(a: &Matrix, b: &Matrix) -> Result<Vec<f64>, MathMatrixError> {
+	if a.cols != b.rows || a.rows != b.cols {
+		return Err(MathMatrixError::new(
+			SizeMismatch,
+			"Diagonal of product allowed for NxM * MxN".to_owned(),
+		));
+	}
+	let mut diagonal = vec![0f64; a.rows];
+	for i in 0..a.rows {
+		for k in 0..a.cols {
+			diagonal[i] += a.data[k * a.rows + i] * b.data[i * b.rows + k];
+		}
+	}
+	return Ok(diagonal);
+}
+
+// trace(A * B) for A: NxM and B: MxN, without forming the NxN product
+pub fn trace_of_product(a: &Matrix, b: &Matrix) -> Result<f64, MathMatrixError> {
+	return Ok(diag_of_product(a, b)?.iter().sum());
+}
+
+// [A, B] = A * B - B * A for A, B: NxN
+pub fn commutator(a: &Matrix, b: &Matrix) -> Result<Matrix, MathMatrixError> {
+	check_same_square(a, b, "Commutator")?;
+	return a.multiplied_by_matrix(b)? - b.multiplied_by_matrix(a)?;
+}
+
+// {A, B} = A * B + B * A for A, B: NxN
+pub fn anticommutator(a: &Matrix, b: &Matrix) -> Result<Matrix, MathMatrixError> {
+	check_same_square(a, b, "Anticommutator")?;
+	return a.multiplied_by_matrix(b)? + b.multiplied_by_matrix(a)?;
+}
+
+/* P^-1 * A * P for P, A: NxN, computed by solving P * X = A * P rather than
+inverting P. Fails if P is singular.
+*/
+pub fn similarity_transform(p: &Matrix, a: &Matrix) -> Result<Matrix, MathMatrixError> {
+	check_same_square(p, a, "Similarity transform")?;
+	return p.solve(&a.multiplied_by_matrix(p)?);
+}
+
+// P * A * P^T for P: MxN and A: NxN, giving an MxM matrix
+pub fn congruence(p: &Matrix, a: &Matrix) -> Result<Matrix, MathMatrixError> {
+	if a.rows != a.cols || p.cols != a.rows {
+		return Err(MathMatrixError::new(
+			SizeMismatch,
+			"Congruence allowed for P: MxN and A: NxN".to_owned(),
+		));
+	}
+	return p
+		.multiplied_by_matrix(a)?
+		.multiplied_by_matrix(&p.transposed());
+}
+
+fn check_same_square(a: &Matrix, b: &Matrix, operation: &str) -> Result<(), MathMatrixError> {
+	if a.rows != a.cols || (a.rows, a.cols) != (b.rows, b.cols) {
+		return Err(MathMatrixError::new(
+			SizeMismatch,
+			format!(
+				"{} allowed only for NxN matrices of the same size",
+				operation
+			),
+		));
+	}
+	return Ok(());
 }
 
 #[cfg(test)]
@@ -415,6 +1206,381 @@ mod tests {
 		let inv_mat = mat.invert().unwrap();
 		let identity = inv_mat.multiplied_by_matrix(&mat).unwrap();
 		identity.print();
-		assert_eq!(identity, Matrix::identity(4, 4).unwrap());
+		assert!(identity.approx_eq(&Matrix::identity(4, 4).unwrap(), 1e-12));
+	}
+
+	#[test]
+	fn test_diag_and_trace_of_product() {
+		let mat1 = Matrix::new(2, 3, vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]).unwrap();
+		let mat2 = Matrix::new(3, 2, vec![7.0, 9.0, 11.0, 8.0, 10.0, 12.0]).unwrap();
+		let product = mat1.multiplied_by_matrix(&mat2).unwrap();
+		let diagonal = diag_of_product(&mat1, &mat2).unwrap();
+		assert_eq!(
+			diagonal,
+			vec![
+				product.get_value(0, 0).unwrap(),
+				product.get_value(1, 1).unwrap()
+			]
+		);
+		assert_eq!(trace_of_product(&mat1, &mat2).unwrap(), 58.0 + 154.0);
+		assert!(trace_of_product(&mat1, &mat1).is_err());
+	}
+
+	#[test]
+	fn test_map_rows() {
+		let mat = Matrix::new(2, 2, vec![1.0, 3.0, 2.0, 4.0]).unwrap();
+		let normalized = mat
+			.map_rows(|_, row| {
+				let sum: f64 = row.iter().sum();
+				row.iter().map(|x| x / sum).collect()
+			})
+			.unwrap();
+		assert_eq!(
+			normalized.get_data(),
+			vec![1.0 / 3.0, 3.0 / 7.0, 2.0 / 3.0, 4.0 / 7.0]
+		);
+		let indexed = mat.map_rows(|i, _| vec![i as f64; 3]).unwrap();
+		assert_eq!(indexed.get_size(), (2, 3));
+		assert!(mat.map_rows(|i, _| vec![0.0; i + 1]).is_err());
+	}
+
+	#[test]
+	fn test_map_cols() {
+		let mat = Matrix::new(2, 2, vec![1.0, 3.0, 2.0, 4.0]).unwrap();
+		let detrended = mat
+			.map_cols(|_, col| {
+				let mean = col.iter().sum::<f64>() / col.len() as f64;
+				col.iter().map(|x| x - mean).collect()
+			})
+			.unwrap();
+		assert_eq!(detrended.get_data(), vec![-1.0, 1.0, -1.0, 1.0]);
+		let sums = mat.map_cols(|_, col| vec![col.iter().sum()]).unwrap();
+		assert_eq!(sums.get_size(), (1, 2));
+		assert_eq!(sums.get_data(), vec![4.0, 6.0]);
+	}
+
+	#[test]
+	fn test_map_rows_and_cols_mut() {
+		let mut mat = Matrix::new(2, 2, vec![1.0, 3.0, 2.0, 4.0]).unwrap();
+		mat.map_rows_mut(|i, row| row.iter_mut().for_each(|x| *x += i as f64));
+		assert_eq!(mat.get_data(), vec![1.0, 4.0, 2.0, 5.0]);
+		mat.map_cols_mut(|j, col| col.iter_mut().for_each(|x| *x *= (j + 1) as f64));
+		assert_eq!(mat.get_data(), vec![1.0, 4.0, 4.0, 10.0]);
+	}
+
+	#[test]
+	fn test_solve() {
+		let mat = Matrix::new(3, 3, vec![2.0, 4.0, -2.0, 1.0, -6.0, 7.0, 1.0, 0.0, 2.0]).unwrap();
+		let b = Matrix::new(3, 2, vec![5.0, -2.0, 9.0, 4.0, -6.0, 7.0]).unwrap();
+		let x = mat.solve(&b).unwrap();
+		assert_eq!(x.get_size(), (3, 2));
+		assert_eq!(x.get_data()[..3], [1.0, 1.0, 2.0]);
+		let residual = (mat.multiplied_by_matrix(&x).unwrap() - b).unwrap();
+		assert!(residual.get_data().iter().all(|r| r.abs() < 1e-12));
+		assert!(mat.solve(&Matrix::zeros(2, 1).unwrap()).is_err());
+		let singular = Matrix::new(2, 2, vec![1.0, 2.0, 2.0, 4.0]).unwrap();
+		assert!(singular.solve(&Matrix::zeros(2, 1).unwrap()).is_err());
+	}
+
+	#[test]
+	fn test_vec_round_trip_without_copy() {
+		let data = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+		let ptr = data.as_ptr();
+		let mat = Matrix::from_vec_unchecked(3, 2, data);
+		assert_eq!(mat.get_value(2, 1).unwrap(), 6.0);
+		let data = mat.into_vec();
+		assert_eq!(data.as_ptr(), ptr);
+
+		let boxed: Box<[f64]> = data.into_boxed_slice();
+		let ptr = boxed.as_ptr();
+		let data = Matrix::from_boxed_slice(2, 3, boxed).unwrap().into_vec();
+		assert_eq!(data.as_ptr(), ptr);
+		assert!(Matrix::from_boxed_slice(4, 2, vec![0.0; 6].into_boxed_slice()).is_err());
+	}
+
+	#[test]
+	fn test_aligned_matrix() {
+		let mat = Matrix::new(3, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0]).unwrap();
+		let aligned = mat.to_aligned(64).unwrap();
+		assert!(aligned.is_aligned_to(64));
+		assert!(aligned.is_aligned_to(32));
+		assert_eq!(aligned, mat);
+		assert_eq!(aligned.transposed(), mat.transposed());
+		assert!(Matrix::zeros_aligned(2, 2, 24).is_err());
+		assert!(Matrix::zeros_aligned(2, 2, 32).unwrap().is_aligned_to(32));
+		assert!(!mat.is_aligned_to(0));
+	}
+
+	#[test]
+	fn test_plu_decompose() {
+		let mat = Matrix::new(3, 3, vec![0.0, 2.0, 4.0, 1.0, 1.0, 3.0, 2.0, 0.0, 1.0]).unwrap();
+		assert!(mat.decompose().is_err());
+		let (p, l, u) = mat.plu_decompose().unwrap();
+		assert!(p
+			.multiplied_by_matrix(&mat)
+			.unwrap()
+			.approx_eq(&l.multiplied_by_matrix(&u).unwrap(), 1e-12));
+		for i in 0..3 {
+			assert_eq!(l.get_value(i, i).unwrap(), 1.0);
+			for j in (i + 1)..3 {
+				assert_eq!(l.get_value(i, j).unwrap(), 0.0);
+				assert_eq!(u.get_value(j, i).unwrap(), 0.0);
+			}
+		}
+	}
+
+	#[test]
+	fn test_invert_requires_pivoting() {
+		let mat = Matrix::new(2, 2, vec![0.0, 1.0, 1.0, 0.0]).unwrap();
+		assert_eq!(mat.invert().unwrap(), mat);
+		let singular = Matrix::new(2, 2, vec![1.0, 2.0, 2.0, 4.0]).unwrap();
+		assert!(singular.invert().is_err());
+	}
+
+	#[test]
+	fn test_solve_with_workspace() {
+		let mut workspace = Workspace::new();
+		let mat = Matrix::new(2, 2, vec![0.0, 1.0, 1.0, 0.0]).unwrap();
+		let b = Matrix::new(2, 1, vec![3.0, 4.0]).unwrap();
+		for _ in 0..3 {
+			let x = mat.solve_with_workspace(&b, &mut workspace).unwrap();
+			assert_eq!(x.get_data(), vec![4.0, 3.0]);
+		}
+		assert!(workspace.capacity() >= 8);
+	}
+
+	#[test]
+	fn test_multiplied_by_matrix_with() {
+		let mat1 = Matrix::new(3, 3, vec![1.0, 0.0, 1.0, 2.0, 0.0, 1.0, 1.0, 0.0, -1.0]).unwrap();
+		let mat2 = Matrix::new(3, 2, vec![2.0, 1.0, 0.0, 1.0, 1.0, 1.0]).unwrap();
+		let expected = mat1.multiplied_by_matrix(&mat2).unwrap();
+		for algorithm in [
+			MultiplicationAlgorithm::Naive,
+			MultiplicationAlgorithm::Blocked,
+			MultiplicationAlgorithm::Strassen,
+		] {
+			assert_eq!(
+				mat1.multiplied_by_matrix_with(&mat2, algorithm).unwrap(),
+				expected
+			);
+		}
+	}
+
+	#[test]
+	fn test_determinant() {
+		let mat = Matrix::new(3, 3, vec![2.0, 4.0, -2.0, 1.0, -1.0, 3.0, 3.0, 0.0, 1.0]).unwrap();
+		assert!((mat.determinant().unwrap() - 24.0).abs() < 1e-12);
+		// A single row swap flips the sign
+		let swapped = Matrix::new(2, 2, vec![0.0, 1.0, 1.0, 0.0]).unwrap();
+		assert_eq!(swapped.determinant().unwrap(), -1.0);
+		let singular = Matrix::new(2, 2, vec![1.0, 2.0, 2.0, 4.0]).unwrap();
+		assert_eq!(singular.determinant().unwrap(), 0.0);
+		assert!(Matrix::zeros(2, 3).unwrap().determinant().is_err());
+	}
+
+	#[test]
+	fn test_small_matrices_are_inline() {
+		let rotation = Matrix::new(2, 2, vec![0.0, 1.0, -1.0, 0.0]).unwrap();
+		let scale = Matrix::identity(2, 2).unwrap().multiplied_by_scalar(3.0);
+		assert!(scale.data.is_inline());
+		let product = rotation.multiplied_by_matrix(&scale).unwrap();
+		assert!(product.data.is_inline());
+		assert_eq!(product.get_data(), vec![0.0, 3.0, -3.0, 0.0]);
+		let sum = (product.clone() + product.transposed()).unwrap();
+		assert!(sum.data.is_inline());
+		assert_eq!(sum.get_data(), vec![0.0; 4]);
+		assert!(!Matrix::zeros(5, 5).unwrap().data.is_inline());
+	}
+
+	#[test]
+	fn test_triangular_solves() {
+		let mat = Matrix::new(3, 3, vec![2.0, 4.0, -2.0, 1.0, -1.0, 3.0, 3.0, 0.0, 1.0]).unwrap();
+		let b = Matrix::new(3, 2, vec![1.0, 2.0, 3.0, -1.0, 0.0, 4.0]).unwrap();
+		// Reuse one factorization: A * X = B <=> L * U * X = P * B
+		let (p, l, u) = mat.plu_decompose().unwrap();
+		let y = l
+			.solve_lower_triangular(&p.multiplied_by_matrix(&b).unwrap())
+			.unwrap();
+		let x = u.solve_upper_triangular(&y).unwrap();
+		assert!(x.approx_eq(&mat.solve(&b).unwrap(), 1e-12));
+		assert!(mat.multiplied_by_matrix(&x).unwrap().approx_eq(&b, 1e-12));
+		// Only the relevant triangle is read
+		let lower = Matrix::new(2, 2, vec![2.0, 1.0, 99.0, 4.0]).unwrap();
+		let x = lower
+			.solve_lower_triangular(&Matrix::new(2, 1, vec![2.0, 9.0]).unwrap())
+			.unwrap();
+		assert_eq!(x.get_data(), vec![1.0, 2.0]);
+		let singular = Matrix::new(2, 2, vec![1.0, 0.0, 1.0, 0.0]).unwrap();
+		assert!(singular.solve_upper_triangular(&x).is_err());
+		assert!(lower
+			.solve_upper_triangular(&Matrix::zeros(3, 1).unwrap())
+			.is_err());
+	}
+
+	#[test]
+	fn test_commutators_and_transforms() {
+		let sigma_x = Matrix::new(2, 2, vec![0.0, 1.0, 1.0, 0.0]).unwrap();
+		let sigma_z = Matrix::new(2, 2, vec![1.0, 0.0, 0.0, -1.0]).unwrap();
+		// [sz, sx] = 2i * sy = [0, 2; -2, 0]
+		let expected = Matrix::new(2, 2, vec![0.0, -2.0, 2.0, 0.0]).unwrap();
+		assert_eq!(commutator(&sigma_z, &sigma_x).unwrap(), expected);
+		assert_eq!(
+			anticommutator(&sigma_z, &sigma_x).unwrap(),
+			Matrix::zeros(2, 2).unwrap()
+		);
+
+		let p = Matrix::new(2, 2, vec![1.0, 0.0, 1.0, 1.0]).unwrap();
+		let a = Matrix::new(2, 2, vec![1.0, 0.0, 0.0, 2.0]).unwrap();
+		let transformed = similarity_transform(&p, &a).unwrap();
+		let lhs = p.multiplied_by_matrix(&transformed).unwrap();
+		assert!(lhs.approx_eq(&a.multiplied_by_matrix(&p).unwrap(), 1e-12));
+		assert!(similarity_transform(&Matrix::zeros(2, 2).unwrap(), &a).is_err());
+
+		// [1, 1] * diag(1, 2) * [1, 1]^T = 3
+		let row = Matrix::new(1, 2, vec![1.0, 1.0]).unwrap();
+		assert_eq!(congruence(&row, &a).unwrap().get_data(), vec![3.0]);
+		assert!(congruence(&a, &row).is_err());
+		assert!(commutator(&a, &row).is_err());
+	}
+
+	#[test]
+	fn test_condition_number() {
+		let mat = Matrix::new(2, 2, vec![1.0, 0.0, 0.0, 1e-6]).unwrap();
+		assert!((mat.condition_number().unwrap() - 1e6).abs() < 1e-3);
+		// [4, 1; 2, 3]: ||A||_1 = 6, A^-1 = [3, -1; -2, 4] / 10, ||A^-1||_1 = 0.5
+		let mat = Matrix::new(2, 2, vec![4.0, 2.0, 1.0, 3.0]).unwrap();
+		assert!((mat.condition_number_1().unwrap() - 3.0).abs() < 1e-12);
+		// ||A||_inf = 5, ||A^-1||_inf = 0.6: an estimate from below, here within 10%
+		let estimate = mat.condition_number_inf().unwrap();
+		assert!(estimate <= 3.0 + 1e-12 && estimate > 2.6);
+		let singular = Matrix::new(2, 2, vec![1.0, 2.0, 2.0, 4.0]).unwrap();
+		assert_eq!(singular.condition_number().unwrap(), f64::INFINITY);
+		assert_eq!(singular.condition_number_1().unwrap(), f64::INFINITY);
+	}
+
+	#[test]
+	fn test_vec_and_structural_matrices() {
+		let mat = Matrix::new(2, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+		let vectorized = mat.vec();
+		assert_eq!(vectorized.get_size(), (6, 1));
+		assert_eq!(vectorized.unvec(2, 3).unwrap(), mat);
+		assert!(vectorized.unvec(4, 2).is_err());
+		assert!(mat.unvec(3, 2).is_err());
+
+		let commutation = Matrix::commutation(2, 3).unwrap();
+		assert_eq!(
+			commutation.multiplied_by_matrix(&vectorized).unwrap(),
+			mat.transposed().vec()
+		);
+
+		// [1, 2; 2, 3] has vech = (1, 2, 3)
+		let symmetric = Matrix::new(2, 2, vec![1.0, 2.0, 2.0, 3.0]).unwrap();
+		let vech = Matrix::new(3, 1, vec![1.0, 2.0, 3.0]).unwrap();
+		let duplication = Matrix::duplication(2).unwrap();
+		assert_eq!(duplication.get_size(), (4, 3));
+		assert_eq!(
+			duplication.multiplied_by_matrix(&vech).unwrap(),
+			symmetric.vec()
+		);
+	}
+
+	#[test]
+	fn test_structure_string() {
+		let mat = Matrix::new(2, 3, vec![1.0, 0.0, -2.0, 1e-14, f64::NAN, 3.0]).unwrap();
+		assert_eq!(mat.structure_string(), "+-?\n·++\n");
+		assert_eq!(mat.structure_string_with(1e-12), "+-?\n·0+\n");
+	}
+
+	#[test]
+	fn test_elementwise() {
+		let a = Matrix::new(2, 2, vec![1.0, 2.0, 3.0, 4.0]).unwrap();
+		let b = Matrix::new(2, 2, vec![2.0, 4.0, -1.0, 0.5]).unwrap();
+		let product = a.elementwise_mul(&b).unwrap();
+		assert_eq!(product.get_data(), vec![2.0, 8.0, -3.0, 2.0]);
+		assert_eq!(product.elementwise_div(&b).unwrap(), a);
+		assert!(a.elementwise_mul(&Matrix::zeros(2, 1).unwrap()).is_err());
+		let quotient = a.elementwise_div(&Matrix::zeros(2, 2).unwrap()).unwrap();
+		assert!(quotient.get_value(0, 0).unwrap().is_infinite());
+	}
+
+	#[test]
+	fn test_kronecker() {
+		let a = Matrix::new(2, 2, vec![1.0, 3.0, 2.0, 4.0]).unwrap();
+		let b = Matrix::new(1, 2, vec![0.0, 1.0]).unwrap();
+		let product = a.kronecker(&b);
+		assert_eq!(product.get_size(), (2, 4));
+		assert_eq!(
+			product.get_data(),
+			vec![0.0, 0.0, 1.0, 3.0, 0.0, 0.0, 2.0, 4.0]
+		);
+		// vec(A * X * C) = (C^T (x) A) * vec(X)
+		let x = Matrix::new(2, 3, vec![1.0, -1.0, 2.0, 0.5, 0.0, 3.0]).unwrap();
+		let c = Matrix::new(3, 2, vec![2.0, 1.0, 0.0, -1.0, 1.0, 4.0]).unwrap();
+		let left = a
+			.multiplied_by_matrix(&x)
+			.unwrap()
+			.multiplied_by_matrix(&c)
+			.unwrap()
+			.vec();
+		let right = c.transposed().kronecker(&a).multiplied_by_matrix(&x.vec());
+		assert!(left.approx_eq(&right.unwrap(), 1e-12));
+	}
+
+	#[test]
+	fn test_trace() {
+		let a = Matrix::new(2, 2, vec![1.0, 3.0, 2.0, 4.0]).unwrap();
+		assert_eq!(a.trace().unwrap(), 5.0);
+		let b = Matrix::new(2, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+		assert!(b.trace().is_err());
+		let product = b.multiplied_by_matrix(&b.transposed()).unwrap();
+		assert_eq!(
+			product.trace().unwrap(),
+			trace_of_product(&b, &b.transposed()).unwrap()
+		);
+	}
+
+	#[test]
+	fn test_pow() {
+		// Fibonacci: [[1, 1], [1, 0]]^n has F(n + 1), F(n) in its first column
+		let fib = Matrix::new(2, 2, vec![1.0, 1.0, 1.0, 0.0]).unwrap();
+		let power = fib.pow(10).unwrap();
+		assert_eq!(power.get_data(), vec![89.0, 55.0, 55.0, 34.0]);
+		assert_eq!(fib.pow(0).unwrap(), Matrix::identity(2, 2).unwrap());
+		let inverse_power = fib.pow_i(-10).unwrap();
+		assert!(inverse_power
+			.multiplied_by_matrix(&power)
+			.unwrap()
+			.approx_eq(&Matrix::identity(2, 2).unwrap(), 1e-9));
+		assert!(Matrix::zeros(2, 3).unwrap().pow(2).is_err());
+		assert!(Matrix::zeros(2, 2).unwrap().pow_i(-1).is_err());
+	}
+
+	#[test]
+	fn test_map() {
+		let a = Matrix::new(2, 2, vec![1.0, -4.0, 0.0, 9.0]).unwrap();
+		assert_eq!(
+			a.map(|x| 2.0 * x + 1.0).get_data(),
+			vec![3.0, -7.0, 1.0, 19.0]
+		);
+		assert_eq!(a.abs().get_data(), vec![1.0, 4.0, 0.0, 9.0]);
+		assert_eq!(a.abs().powf(0.5).get_data(), vec![1.0, 2.0, 0.0, 3.0]);
+		assert_eq!(a.signum().get_data(), vec![1.0, -1.0, 1.0, 1.0]);
+		assert!(a.exp().ln().approx_eq(&a, 1e-12));
+		assert!(a.ln().get_value(1, 0).unwrap().is_nan());
+	}
+
+	#[test]
+	fn test_norms() {
+		// [1, -2; 3, 4]
+		let a = Matrix::new(2, 2, vec![1.0, 3.0, -2.0, 4.0]).unwrap();
+		assert_eq!(a.norm(NormKind::Frobenius).unwrap(), 30f64.sqrt());
+		assert_eq!(a.norm(NormKind::One).unwrap(), 6.0);
+		assert_eq!(a.norm(NormKind::Infinity).unwrap(), 7.0);
+		assert_eq!(a.norm(NormKind::Max).unwrap(), 4.0);
+		// sigma_max^2 is the largest eigenvalue of A^T * A = [10, 10; 10, 20]
+		let expected = (15.0 + 125f64.sqrt()).sqrt();
+		assert!((a.norm(NormKind::Spectral).unwrap() - expected).abs() < 1e-12);
+		let row = Matrix::new(1, 2, vec![3.0, 4.0]).unwrap();
+		assert!((row.norm(NormKind::Spectral).unwrap() - 5.0).abs() < 1e-12);
 	}
 }
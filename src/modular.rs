@@ -0,0 +1,324 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::integer::IntegerMatrix;
+
+// Inverse of a modulo m by the extended Euclidean algorithm, if gcd(a, m) = 1
+pub fn mod_inverse(a: u64, modulus: u64) -> Option<u64> {
+	if modulus < 2 {
+		return None;
+	}
+	let (mut r0, mut r1) = (modulus as i128, (a % modulus) as i128);
+	let (mut t0, mut t1) = (0i128, 1i128);
+	while r1 != 0 {
+		let q = r0 / r1;
+		(r0, r1) = (r1, r0 - q * r1);
+		(t0, t1) = (t1, t0 - q * t1);
+	}
+	if r0 != 1 {
+		return None;
+	}
+	return Some(t0.rem_euclid(modulus as i128) as u64);
+}
+
+/* Matrix over the integers modulo a runtime modulus m >= 2, entries stored
+reduced in 0..m, column major like Matrix. Arithmetic is exact: products go
+through u128, so any u64 modulus works. The modulus need not be prime (Hill
+ciphers work modulo 26): elimination combines rows with Euclid's algorithm
+instead of dividing by pivots, and a matrix is invertible exactly when its
+determinant is a unit modulo m.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModMatrix {
+	rows: usize,
+	cols: usize,
+	modulus: u64,
+	data: Vec<u64>,
+}
+
+impl ModMatrix {
+	// Entries are reduced modulo `modulus`, negative ones included
+	pub fn new(
+		rows: usize,
+		cols: usize,
+		data: Vec<i64>,
+		modulus: u64,
+	) -> Result<Self, MathMatrixError> {
+		if modulus < 2 {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				format!("Modulus must be at least 2, got {}", modulus),
+			));
+		}
+		if rows * cols == 0 {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"Rows and columns must be lager than 0".to_owned(),
+			));
+		}
+		if rows * cols != data.len() {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				format!(
+					"Size of data != rows * cols: {} != {}",
+					data.len(),
+					rows * cols
+				),
+			));
+		}
+		let data = data
+			.iter()
+			.map(|x| (*x as i128).rem_euclid(modulus as i128) as u64)
+			.collect();
+		return Ok(Self {
+			rows,
+			cols,
+			modulus,
+			data,
+		});
+	}
+
+	pub fn from_integer(mat: &IntegerMatrix, modulus: u64) -> Result<Self, MathMatrixError> {
+		let (rows, cols) = mat.get_size();
+		return Self::new(rows, cols, mat.get_data().to_vec(), modulus);
+	}
+
+	pub fn identity(size: usize, modulus: u64) -> Result<Self, MathMatrixError> {
+		let mut identity = Self::new(size, size, vec![0; size * size], modulus)?;
+		for i in 0..size {
+			identity.data[i * size + i] = 1;
+		}
+		return Ok(identity);
+	}
+
+	pub fn get_size(&self) -> (usize, usize) {
+		return (self.rows, self.cols);
+	}
+
+	pub fn get_modulus(&self) -> u64 {
+		return self.modulus;
+	}
+
+	pub fn get_data(&self) -> &[u64] {
+		return &self.data;
+	}
+
+	pub fn get_value(&self, row: usize, col: usize) -> Result<u64, MathMatrixError> {
+		if row >= self.rows || col >= self.cols {
+			return Err(MathMatrixError::new(
+				OutOfBoundary,
+				format!(
+					"Entry ({}, {}) outside a {}x{} matrix",
+					row, col, self.rows, self.cols
+				),
+			));
+		}
+		return Ok(self.data[col * self.rows + row]);
+	}
+
+	pub fn added_to(&self, other: &ModMatrix) -> Result<ModMatrix, MathMatrixError> {
+		self.check_modulus(other)?;
+		if self.get_size() != other.get_size() {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Operation not allowed between matrices with different sizes".to_owned(),
+			));
+		}
+		let mut sum = self.clone();
+		for (a, b) in sum.data.iter_mut().zip(other.data.iter()) {
+			*a = ((*a as u128 + *b as u128) % self.modulus as u128) as u64;
+		}
+		return Ok(sum);
+	}
+
+	pub fn multiplied_by_matrix(&self, other: &ModMatrix) -> Result<ModMatrix, MathMatrixError> {
+		self.check_modulus(other)?;
+		if self.cols != other.rows {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Multiplication allowed for NxM * MxO".to_owned(),
+			));
+		}
+		let modulus = self.modulus as u128;
+		let mut data = vec![0u64; self.rows * other.cols];
+		for j in 0..other.cols {
+			for i in 0..self.rows {
+				let mut sum = 0u128;
+				for k in 0..self.cols {
+					let term = self.data[k * self.rows + i] as u128
+						* other.data[j * other.rows + k] as u128;
+					sum = (sum + term % modulus) % modulus;
+				}
+				data[j * self.rows + i] = sum as u64;
+			}
+		}
+		return Ok(Self {
+			rows: self.rows,
+			cols: other.cols,
+			modulus: self.modulus,
+			data,
+		});
+	}
+
+	pub fn determinant(&self) -> Result<u64, MathMatrixError> {
+		self.check_square("Determinant")?;
+		let mut reduced = self.clone();
+		let negated = reduced.triangularize(None);
+		let mut determinant = 1u64;
+		for i in 0..self.rows {
+			determinant = reduced.mul(determinant, reduced.data[i * self.rows + i]);
+		}
+		if negated && determinant != 0 {
+			determinant = self.modulus - determinant;
+		}
+		return Ok(determinant);
+	}
+
+	pub fn inverse(&self) -> Result<ModMatrix, MathMatrixError> {
+		self.check_square("Inverse")?;
+		let n = self.rows;
+		let mut reduced = self.clone();
+		let mut inverse = Self::identity(n, self.modulus)?;
+		reduced.triangularize(Some(&mut inverse));
+		// Unit pivots: scale each row to a one, then clear above it
+		for k in (0..n).rev() {
+			let pivot = reduced.data[k * n + k];
+			let scale = mod_inverse(pivot, self.modulus).ok_or_else(|| {
+				MathMatrixError::new(
+					FailedToDecompose,
+					format!("Matrix is not invertible modulo {}", self.modulus),
+				)
+			})?;
+			reduced.scale_row(k, scale);
+			inverse.scale_row(k, scale);
+			for i in 0..k {
+				let factor = reduced.data[k * n + i];
+				reduced.subtract_row(i, k, factor);
+				inverse.subtract_row(i, k, factor);
+			}
+		}
+		return Ok(inverse);
+	}
+
+	/* Upper triangular form by unimodular row operations, each repeated on
+	`companion`. Below each pivot, Euclid's algorithm on the column entries
+	(as integers in 0..m) ends with their gcd in the pivot row and zeros
+	below. Returns whether the row swaps flipped the sign of the determinant.
+	*/
+	fn triangularize(&mut self, mut companion: Option<&mut ModMatrix>) -> bool {
+		let mut negated = false;
+		for k in 0..self.cols.min(self.rows) {
+			for i in (k + 1)..self.rows {
+				while self.data[k * self.rows + i] != 0 {
+					let quotient = self.data[k * self.rows + k] / self.data[k * self.rows + i];
+					self.subtract_row(k, i, quotient);
+					self.swap_rows(k, i);
+					if let Some(other) = companion.as_mut() {
+						other.subtract_row(k, i, quotient);
+						other.swap_rows(k, i);
+					}
+					negated = !negated;
+				}
+			}
+		}
+		return negated;
+	}
+
+	fn mul(&self, a: u64, b: u64) -> u64 {
+		return (a as u128 * b as u128 % self.modulus as u128) as u64;
+	}
+
+	// Row `target` -= factor * row `source`
+	fn subtract_row(&mut self, target: usize, source: usize, factor: u64) {
+		for j in 0..self.cols {
+			let update = self.mul(factor % self.modulus, self.data[j * self.rows + source]);
+			let elem = &mut self.data[j * self.rows + target];
+			*elem =
+				((*elem as u128 + (self.modulus - update) as u128) % self.modulus as u128) as u64;
+		}
+	}
+
+	fn scale_row(&mut self, row: usize, factor: u64) {
+		for j in 0..self.cols {
+			self.data[j * self.rows + row] = self.mul(factor, self.data[j * self.rows + row]);
+		}
+	}
+
+	fn swap_rows(&mut self, row_1: usize, row_2: usize) {
+		for j in 0..self.cols {
+			self.data.swap(j * self.rows + row_1, j * self.rows + row_2);
+		}
+	}
+
+	fn check_square(&self, operation: &str) -> Result<(), MathMatrixError> {
+		if self.rows != self.cols {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				format!("{} allowed only for square matrices", operation),
+			));
+		}
+		return Ok(());
+	}
+
+	fn check_modulus(&self, other: &ModMatrix) -> Result<(), MathMatrixError> {
+		if self.modulus != other.modulus {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				format!(
+					"Operation between moduli {} and {}",
+					self.modulus, other.modulus
+				),
+			));
+		}
+		return Ok(());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_mod_inverse() {
+		assert_eq!(mod_inverse(9, 26), Some(3));
+		assert_eq!(mod_inverse(13, 26), None);
+		let p = (1u64 << 61) - 1;
+		let inverse = mod_inverse(123456789, p).unwrap();
+		assert_eq!((123456789u128 * inverse as u128 % p as u128), 1);
+	}
+
+	#[test]
+	fn test_hill_cipher() {
+		// Key [3, 3; 2, 5] modulo 26, with inverse [15, 17; 20, 9]
+		let key = ModMatrix::new(2, 2, vec![3, 2, 3, 5], 26).unwrap();
+		assert_eq!(key.determinant().unwrap(), 9);
+		let inverse = key.inverse().unwrap();
+		assert_eq!(inverse.get_data(), &[15, 20, 17, 9]);
+		// "HELP" as letter indices, two per column
+		let plain = ModMatrix::new(2, 2, vec![7, 4, 11, 15], 26).unwrap();
+		let cipher = key.multiplied_by_matrix(&plain).unwrap();
+		assert_eq!(inverse.multiplied_by_matrix(&cipher).unwrap(), plain);
+		// det = 4 shares a factor with 26
+		let weak = ModMatrix::new(2, 2, vec![2, 0, 0, 2], 26).unwrap();
+		assert!(weak.inverse().is_err());
+		let other = ModMatrix::new(2, 2, vec![1, 0, 0, 1], 7).unwrap();
+		assert!(key.added_to(&other).is_err());
+	}
+
+	#[test]
+	fn test_exact_linear_algebra_mod_p() {
+		let p = 1_000_000_007;
+		let integers = IntegerMatrix::new(3, 3, vec![2, -1, 0, -1, 2, -1, 0, -1, 2]).unwrap();
+		let mat = ModMatrix::from_integer(&integers, p).unwrap();
+		assert_eq!(mat.determinant().unwrap(), 4);
+		let inverse = mat.inverse().unwrap();
+		assert_eq!(
+			mat.multiplied_by_matrix(&inverse).unwrap(),
+			ModMatrix::identity(3, p).unwrap()
+		);
+		// Singular modulo 2 although not over the integers
+		let mat = ModMatrix::from_integer(&integers, 2).unwrap();
+		assert_eq!(mat.determinant().unwrap(), 0);
+		assert!(mat.inverse().is_err());
+		assert!(ModMatrix::new(1, 1, vec![1], 1).is_err());
+	}
+}
@@ -0,0 +1,266 @@
+use super::cancel::CancellationToken;
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+use super::reduction;
+use std::ops::ControlFlow;
+
+/* Geometric multigrid for the 1D Poisson problem -u'' = f on (0, 1) with
+homogeneous Dirichlet boundaries. A grid with n interior points is coarsened
+to (n - 1) / 2 points, so the hierarchy is complete when n = 2^k - 1.
+Vectors are stored as n x 1 matrices.
+*/
+
+// Finite-difference Laplacian tridiag(-1, 2, -1) / h^2 with h = 1 / (n + 1)
+pub fn laplacian_1d(n: usize) -> Result<Matrix, MathMatrixError> {
+	let mut mat = Matrix::zeros(n, n)?;
+	let inv_h2 = ((n + 1) * (n + 1)) as f64;
+	for i in 0..n {
+		mat.set_value(i, i, 2.0 * inv_h2)?;
+		if i > 0 {
+			mat.set_value(i, i - 1, -inv_h2)?;
+		}
+		if i + 1 < n {
+			mat.set_value(i, i + 1, -inv_h2)?;
+		}
+	}
+	return Ok(mat);
+}
+
+// Full-weighting restriction from n_fine = 2 * n_coarse + 1 points
+pub fn restriction(n_fine: usize) -> Result<Matrix, MathMatrixError> {
+	if n_fine < 3 || n_fine.is_multiple_of(2) {
+		return Err(MathMatrixError::new(
+			OperationNotPermitted,
+			format!("Cannot coarsen a grid with {} points", n_fine),
+		));
+	}
+	let n_coarse = (n_fine - 1) / 2;
+	let mut mat = Matrix::zeros(n_coarse, n_fine)?;
+	for i in 0..n_coarse {
+		mat.set_value(i, 2 * i, 0.25)?;
+		mat.set_value(i, 2 * i + 1, 0.5)?;
+		mat.set_value(i, 2 * i + 2, 0.25)?;
+	}
+	return Ok(mat);
+}
+
+// Linear interpolation from n_coarse to 2 * n_coarse + 1 points
+pub fn prolongation(n_coarse: usize) -> Result<Matrix, MathMatrixError> {
+	let restriction_mat = restriction(2 * n_coarse + 1)?;
+	return Ok(restriction_mat.transposed().multiplied_by_scalar(2.0));
+}
+
+// Weighted Jacobi: x <- x + omega * D^(-1) * (b - A * x)
+pub fn jacobi_smooth(
+	a: &Matrix,
+	x: &Matrix,
+	b: &Matrix,
+	omega: f64,
+	sweeps: usize,
+) -> Result<Matrix, MathMatrixError> {
+	let (rows, cols) = a.get_size();
+	if rows != cols || x.get_size() != (rows, 1) || b.get_size() != (rows, 1) {
+		return Err(MathMatrixError::new(
+			SizeMismatch,
+			"Jacobi smoothing requires A: NxN, x: Nx1, b: Nx1".to_owned(),
+		));
+	}
+	let mut x_mat = x.clone();
+	for _ in 0..sweeps {
+		let residual = residual(a, &x_mat, b)?;
+		for i in 0..rows {
+			let diagonal = a.get_value(i, i)?;
+			if diagonal == 0.0 {
+				return Err(MathMatrixError::new(
+					OperationNotPermitted,
+					format!("Zero on the diagonal at row {}", i),
+				));
+			}
+			let elem = x_mat.get_value(i, 0)? + omega * residual.get_value(i, 0)? / diagonal;
+			x_mat.set_value(i, 0, elem)?;
+		}
+	}
+	return Ok(x_mat);
+}
+
+// r = b - A * x
+pub fn residual(a: &Matrix, x: &Matrix, b: &Matrix) -> Result<Matrix, MathMatrixError> {
+	return b.clone() - a.multiplied_by_matrix(x)?;
+}
+
+// One V-cycle with Galerkin coarse operators R * A * P
+pub fn v_cycle(
+	a: &Matrix,
+	x: &Matrix,
+	b: &Matrix,
+	pre_sweeps: usize,
+	post_sweeps: usize,
+) -> Result<Matrix, MathMatrixError> {
+	let omega = 2.0 / 3.0;
+	let (n, _) = a.get_size();
+	if n < 3 || n.is_multiple_of(2) {
+		// Coarsest grid: solve directly
+		return a.solve(b);
+	}
+	let x_mat = jacobi_smooth(a, x, b, omega, pre_sweeps)?;
+	let restriction_mat = restriction(n)?;
+	let prolongation_mat = prolongation((n - 1) / 2)?;
+	let coarse_a = restriction_mat
+		.multiplied_by_matrix(a)?
+		.multiplied_by_matrix(&prolongation_mat)?;
+	let coarse_b = restriction_mat.multiplied_by_matrix(&residual(a, &x_mat, b)?)?;
+	let coarse_x = Matrix::zeros((n - 1) / 2, 1)?;
+	let coarse_e = v_cycle(&coarse_a, &coarse_x, &coarse_b, pre_sweeps, post_sweeps)?;
+	let x_mat = (x_mat + prolongation_mat.multiplied_by_matrix(&coarse_e)?)?;
+	return jacobi_smooth(a, &x_mat, b, omega, post_sweeps);
+}
+
+// Repeat V-cycles until ||b - A * x|| <= tolerance * ||b||
+pub fn solve(
+	a: &Matrix,
+	b: &Matrix,
+	tolerance: f64,
+	max_cycles: usize,
+) -> Result<(Matrix, usize), MathMatrixError> {
+	return solve_monitored(
+		a,
+		b,
+		tolerance,
+		max_cycles,
+		&CancellationToken::new(),
+		&mut |_, _| ControlFlow::Continue(()),
+	);
+}
+
+// Checks the token before every V-cycle
+pub fn solve_with_cancellation(
+	a: &Matrix,
+	b: &Matrix,
+	tolerance: f64,
+	max_cycles: usize,
+	token: &CancellationToken,
+) -> Result<(Matrix, usize), MathMatrixError> {
+	return solve_monitored(a, b, tolerance, max_cycles, token, &mut |_, _| {
+		ControlFlow::Continue(())
+	});
+}
+
+/* Calls on_iteration(cycle, relative residual) before every V-cycle. Returning
+ControlFlow::Break stops early with the current iterate, which allows custom
+stopping rules.
+*/
+pub fn solve_with_progress<F: FnMut(usize, f64) -> ControlFlow<()>>(
+	a: &Matrix,
+	b: &Matrix,
+	tolerance: f64,
+	max_cycles: usize,
+	mut on_iteration: F,
+) -> Result<(Matrix, usize), MathMatrixError> {
+	return solve_monitored(
+		a,
+		b,
+		tolerance,
+		max_cycles,
+		&CancellationToken::new(),
+		&mut on_iteration,
+	);
+}
+
+fn solve_monitored(
+	a: &Matrix,
+	b: &Matrix,
+	tolerance: f64,
+	max_cycles: usize,
+	token: &CancellationToken,
+	on_iteration: &mut dyn FnMut(usize, f64) -> ControlFlow<()>,
+) -> Result<(Matrix, usize), MathMatrixError> {
+	let (n, _) = a.get_size();
+	let b_norm = euclidean_norm(b);
+	let mut x_mat = Matrix::zeros(n, 1)?;
+	for cycle in 0..max_cycles {
+		let residual_norm = euclidean_norm(&residual(a, &x_mat, b)?);
+		if residual_norm <= tolerance * b_norm {
+			return Ok((x_mat, cycle));
+		}
+		if on_iteration(cycle, residual_norm / b_norm).is_break() {
+			return Ok((x_mat, cycle));
+		}
+		token.check("Multigrid solve")?;
+		x_mat = v_cycle(a, &x_mat, b, 2, 2)?;
+	}
+	if euclidean_norm(&residual(a, &x_mat, b)?) <= tolerance * b_norm {
+		return Ok((x_mat, max_cycles));
+	}
+	return Err(MathMatrixError::new(
+		FailedToConverge,
+		format!("Multigrid did not converge in {} cycles", max_cycles),
+	));
+}
+
+fn euclidean_norm(mat: &Matrix) -> f64 {
+	return reduction::norm(&mat.get_data());
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_laplacian_1d() {
+		let mat = laplacian_1d(3).unwrap();
+		assert_eq!(
+			mat.get_data(),
+			vec![32.0, -16.0, 0.0, -16.0, 32.0, -16.0, 0.0, -16.0, 32.0]
+		);
+	}
+
+	#[test]
+	fn test_restriction_and_prolongation() {
+		let restriction_mat = restriction(5).unwrap();
+		assert_eq!(restriction_mat.get_size(), (2, 5));
+		let prolongation_mat = prolongation(2).unwrap();
+		assert_eq!(prolongation_mat.get_size(), (5, 2));
+		assert_eq!(prolongation_mat.get_value(1, 0).unwrap(), 1.0);
+		assert_eq!(prolongation_mat.get_value(2, 0).unwrap(), 0.5);
+		assert!(restriction(4).is_err());
+	}
+
+	#[test]
+	fn test_solve() {
+		let n = 31;
+		let a = laplacian_1d(n).unwrap();
+		let b = Matrix::new(n, 1, vec![1.0; n]).unwrap();
+		let (x, cycles) = solve(&a, &b, 1e-10, 30).unwrap();
+		assert!(cycles < 15);
+		// Exact solution of -u'' = 1 sampled on the grid: u(t) = t (1 - t) / 2
+		for i in 0..n {
+			let t = (i + 1) as f64 / (n + 1) as f64;
+			assert!((x.get_value(i, 0).unwrap() - t * (1.0 - t) / 2.0).abs() < 1e-8);
+		}
+	}
+
+	#[test]
+	fn test_solve_with_progress() {
+		let n = 31;
+		let a = laplacian_1d(n).unwrap();
+		let b = Matrix::new(n, 1, vec![1.0; n]).unwrap();
+		let mut residuals = Vec::new();
+		let (_, cycles) = solve_with_progress(&a, &b, 1e-10, 50, |_, residual| {
+			residuals.push(residual);
+			return ControlFlow::Continue(());
+		})
+		.unwrap();
+		assert_eq!(residuals.len(), cycles);
+		assert!(residuals.windows(2).all(|pair| pair[1] < pair[0]));
+		// Custom stopping rule: two cycles are enough
+		let (_, cycles) = solve_with_progress(&a, &b, 1e-10, 50, |cycle, _| {
+			if cycle == 2 {
+				return ControlFlow::Break(());
+			}
+			return ControlFlow::Continue(());
+		})
+		.unwrap();
+		assert_eq!(cycles, 2);
+	}
+}
@@ -0,0 +1,186 @@
+/* Kernels for C = A * B with A: MxK, B: KxN and every buffer column major.
+They accumulate into a zeroed C provided by the caller, so small products can
+live in inline storage. Naive and Blocked add the products for each element
+in the same order, so they round identically.
+*/
+
+const BLOCK_SIZE: usize = 64;
+const STRASSEN_CUTOFF: usize = 64;
+// Products smaller than this (in multiply-adds) are not worth a thread
+const PARALLEL_THRESHOLD: usize = 64 * 64 * 64;
+
+type Kernel = fn(&[f64], &[f64], usize, usize, usize, &mut [f64]);
+
+pub(crate) fn naive(a: &[f64], b: &[f64], m: usize, k: usize, n: usize, c: &mut [f64]) {
+	for j in 0..n {
+		for p in 0..k {
+			let b_pj = b[j * k + p];
+			for i in 0..m {
+				c[j * m + i] += a[p * m + i] * b_pj;
+			}
+		}
+	}
+}
+
+pub(crate) fn blocked(a: &[f64], b: &[f64], m: usize, k: usize, n: usize, c: &mut [f64]) {
+	for jj in (0..n).step_by(BLOCK_SIZE) {
+		for pp in (0..k).step_by(BLOCK_SIZE) {
+			for ii in (0..m).step_by(BLOCK_SIZE) {
+				for j in jj..(jj + BLOCK_SIZE).min(n) {
+					for p in pp..(pp + BLOCK_SIZE).min(k) {
+						let b_pj = b[j * k + p];
+						for i in ii..(ii + BLOCK_SIZE).min(m) {
+							c[j * m + i] += a[p * m + i] * b_pj;
+						}
+					}
+				}
+			}
+		}
+	}
+}
+
+pub(crate) fn strassen(a: &[f64], b: &[f64], m: usize, k: usize, n: usize, c: &mut [f64]) {
+	let size = m.max(k).max(n).next_power_of_two();
+	if size <= STRASSEN_CUTOFF {
+		blocked(a, b, m, k, n, c);
+		return;
+	}
+	let padded = strassen_square(&pad(a, m, k, size), &pad(b, k, n, size), size);
+	for j in 0..n {
+		c[j * m..(j + 1) * m].copy_from_slice(&padded[j * size..j * size + m]);
+	}
+}
+
+/* Splits B and C into column ranges and runs the kernel on each range in its
+own thread. Every column of C is computed exactly as the single-threaded
+kernel would, so the result does not depend on the thread count.
+*/
+pub(crate) fn by_columns(
+	kernel: Kernel,
+	a: &[f64],
+	b: &[f64],
+	(m, k, n): (usize, usize, usize),
+	threads: usize,
+	c: &mut [f64],
+) {
+	let threads = threads.min(n).max(1);
+	if threads == 1 || m * k * n < PARALLEL_THRESHOLD {
+		kernel(a, b, m, k, n, c);
+		return;
+	}
+	let chunk = n.div_ceil(threads);
+	std::thread::scope(|scope| {
+		for (index, out) in c.chunks_mut(chunk * m).enumerate() {
+			let cols = out.len() / m;
+			let first = index * chunk;
+			let b_part = &b[first * k..(first + cols) * k];
+			scope.spawn(move || kernel(a, b_part, m, k, cols, out));
+		}
+	});
+}
+
+fn pad(x: &[f64], rows: usize, cols: usize, size: usize) -> Vec<f64> {
+	let mut padded = vec![0f64; size * size];
+	for j in 0..cols {
+		padded[j * size..j * size + rows].copy_from_slice(&x[j * rows..(j + 1) * rows]);
+	}
+	return padded;
+}
+
+fn strassen_square(a: &[f64], b: &[f64], size: usize) -> Vec<f64> {
+	if size <= STRASSEN_CUTOFF {
+		let mut c = vec![0f64; size * size];
+		blocked(a, b, size, size, size, &mut c);
+		return c;
+	}
+	let half = size / 2;
+	let (a11, a12, a21, a22) = split(a, size);
+	let (b11, b12, b21, b22) = split(b, size);
+	let m1 = strassen_square(&add(&a11, &a22), &add(&b11, &b22), half);
+	let m2 = strassen_square(&add(&a21, &a22), &b11, half);
+	let m3 = strassen_square(&a11, &sub(&b12, &b22), half);
+	let m4 = strassen_square(&a22, &sub(&b21, &b11), half);
+	let m5 = strassen_square(&add(&a11, &a12), &b22, half);
+	let m6 = strassen_square(&sub(&a21, &a11), &add(&b11, &b12), half);
+	let m7 = strassen_square(&sub(&a12, &a22), &add(&b21, &b22), half);
+	let c11 = add(&sub(&add(&m1, &m4), &m5), &m7);
+	let c12 = add(&m3, &m5);
+	let c21 = add(&m2, &m4);
+	let c22 = add(&add(&sub(&m1, &m2), &m3), &m6);
+	let mut c = vec![0f64; size * size];
+	for j in 0..half {
+		for i in 0..half {
+			c[j * size + i] = c11[j * half + i];
+			c[(j + half) * size + i] = c12[j * half + i];
+			c[j * size + i + half] = c21[j * half + i];
+			c[(j + half) * size + i + half] = c22[j * half + i];
+		}
+	}
+	return c;
+}
+
+// Quadrants (11, 12, 21, 22) of a square matrix with even size
+fn split(x: &[f64], size: usize) -> (Vec<f64>, Vec<f64>, Vec<f64>, Vec<f64>) {
+	let half = size / 2;
+	let mut quadrants = (
+		Vec::with_capacity(half * half),
+		Vec::with_capacity(half * half),
+		Vec::with_capacity(half * half),
+		Vec::with_capacity(half * half),
+	);
+	for j in 0..half {
+		quadrants.0.extend_from_slice(&x[j * size..j * size + half]);
+		quadrants
+			.2
+			.extend_from_slice(&x[j * size + half..(j + 1) * size]);
+	}
+	for j in half..size {
+		quadrants.1.extend_from_slice(&x[j * size..j * size + half]);
+		quadrants
+			.3
+			.extend_from_slice(&x[j * size + half..(j + 1) * size]);
+	}
+	return quadrants;
+}
+
+fn add(x: &[f64], y: &[f64]) -> Vec<f64> {
+	return x.iter().zip(y.iter()).map(|(a, b)| a + b).collect();
+}
+
+fn sub(x: &[f64], y: &[f64]) -> Vec<f64> {
+	return x.iter().zip(y.iter()).map(|(a, b)| a - b).collect();
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn sample(rows: usize, cols: usize, seed: usize) -> Vec<f64> {
+		return (0..rows * cols)
+			.map(|i| ((i * 7 + seed * 13) % 17) as f64 - 8.0)
+			.collect();
+	}
+
+	#[test]
+	fn test_kernels_agree() {
+		let (m, k, n) = (70, 130, 90);
+		let a = sample(m, k, 1);
+		let b = sample(k, n, 2);
+		let run = |kernel: Kernel| {
+			let mut c = vec![0f64; m * n];
+			kernel(&a, &b, m, k, n, &mut c);
+			return c;
+		};
+		let expected = run(naive);
+		assert_eq!(run(blocked), expected);
+		// Small integers keep Strassen exact
+		assert_eq!(run(strassen), expected);
+		for threads in 1..5 {
+			for kernel in [naive as Kernel, blocked] {
+				let mut c = vec![0f64; m * n];
+				by_columns(kernel, &a, &b, (m, k, n), threads, &mut c);
+				assert_eq!(c, expected);
+			}
+		}
+	}
+}
@@ -0,0 +1,76 @@
+use super::error::MathMatrixError;
+use super::matrix::Matrix;
+
+/* Linear map A: R^N -> R^M known only through its action, so that iterative
+methods can run on operators whose matrix is never formed: stencils,
+Jacobian-vector products, products of factors, ...
+*/
+pub trait LinearOperator {
+	// (M, N)
+	fn get_size(&self) -> (usize, usize);
+
+	// A * v for v: NxK
+	fn apply(&self, v: &Matrix) -> Result<Matrix, MathMatrixError>;
+}
+
+impl LinearOperator for Matrix {
+	fn get_size(&self) -> (usize, usize) {
+		return Matrix::get_size(self);
+	}
+
+	fn apply(&self, v: &Matrix) -> Result<Matrix, MathMatrixError> {
+		return self.multiplied_by_matrix(v);
+	}
+}
+
+// Operator defined by a closure computing A * v
+pub struct FnOperator<F> {
+	rows: usize,
+	cols: usize,
+	f: F,
+}
+
+impl<F: Fn(&Matrix) -> Result<Matrix, MathMatrixError>> FnOperator<F> {
+	pub fn new(rows: usize, cols: usize, f: F) -> Self {
+		return Self { rows, cols, f };
+	}
+}
+
+impl<F: Fn(&Matrix) -> Result<Matrix, MathMatrixError>> LinearOperator for FnOperator<F> {
+	fn get_size(&self) -> (usize, usize) {
+		return (self.rows, self.cols);
+	}
+
+	fn apply(&self, v: &Matrix) -> Result<Matrix, MathMatrixError> {
+		return (self.f)(v);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_operators() {
+		let mat = Matrix::new(2, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+		let v = Matrix::new(3, 1, vec![1.0, 0.0, -1.0]).unwrap();
+		// Same map as mat, never stored: row i of A * v is sum_j a_ij * v_j
+		let operator = FnOperator::new(2, 3, |v: &Matrix| {
+			let v = v.get_data();
+			return Matrix::new(
+				2,
+				1,
+				vec![
+					v[0] + 3.0 * v[1] + 5.0 * v[2],
+					2.0 * v[0] + 4.0 * v[1] + 6.0 * v[2],
+				],
+			);
+		});
+		let operators: [&dyn LinearOperator; 2] = [&mat, &operator];
+		for operator in operators.iter() {
+			assert_eq!(operator.get_size(), (2, 3));
+			assert_eq!(operator.apply(&v).unwrap().get_data(), vec![-4.0, -4.0]);
+		}
+		assert!(LinearOperator::apply(&mat, &mat).is_err());
+	}
+}
@@ -0,0 +1,260 @@
+use super::eigen::Eigen;
+use super::matrix::Matrix;
+
+/* Cross-checking of results, enabled by the `oracle` feature for stress
+testing. `solve` (and so `invert`) and `eigen` check every result they
+return:
+- Small systems with integer entries are compared against the exact
+  rational solution, from determinants computed with fraction-free
+  (Bareiss) elimination in 128-bit integers
+- Everything else, and eigenpairs, gets a residual check relative to the
+  norms involved
+Discrepancies beyond the tolerance are reported as numerical warnings (see
+the `log` feature) and collected per thread: `take_discrepancies` returns
+those of the calling thread since the previous call. Without the feature
+the checks compile to nothing.
+*/
+
+// Largest system compared against exact arithmetic
+#[cfg(feature = "oracle")]
+const EXACT_MAX_SIZE: usize = 8;
+// Largest integer entry accepted for exact arithmetic
+#[cfg(feature = "oracle")]
+const EXACT_MAX_ENTRY: f64 = 1048576.0;
+// Relative forward error against exact solutions
+#[cfg(feature = "oracle")]
+const FORWARD_TOLERANCE: f64 = 1e-8;
+// Relative residual for the other checks
+#[cfg(feature = "oracle")]
+const RESIDUAL_TOLERANCE: f64 = 1e-10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Discrepancy {
+	pub operation: &'static str,
+	// Relative error found, and the tolerance it exceeded
+	pub error: f64,
+	pub tolerance: f64,
+	// Whether the reference was exact arithmetic rather than a residual
+	pub exact: bool,
+}
+
+#[cfg(feature = "oracle")]
+thread_local! {
+	static DISCREPANCIES: std::cell::RefCell<Vec<Discrepancy>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+pub fn take_discrepancies() -> Vec<Discrepancy> {
+	#[cfg(feature = "oracle")]
+	return DISCREPANCIES.with(|discrepancies| std::mem::take(&mut *discrepancies.borrow_mut()));
+	#[cfg(not(feature = "oracle"))]
+	return Vec::new();
+}
+
+// Checks the solution x of A * x = B
+#[allow(unused_variables)]
+pub(crate) fn check_solve(a: &Matrix, b: &Matrix, x: &Matrix) {
+	#[cfg(feature = "oracle")]
+	{
+		if let Some(error) = exact_forward_error(a, b, x) {
+			record("solve", error, FORWARD_TOLERANCE, true);
+			return;
+		}
+		// ||A * x - B|| <= tolerance * (||A|| * ||x|| + ||B||), in max norms
+		let residual = match a.multiplied_by_matrix(x).and_then(|ax| ax - b.clone()) {
+			Ok(residual) => residual,
+			Err(_) => return,
+		};
+		let scale = max_abs(a) * max_abs(x) * a.get_size().1 as f64 + max_abs(b);
+		record(
+			"solve",
+			relative(max_abs(&residual), scale),
+			RESIDUAL_TOLERANCE,
+			false,
+		);
+	}
+}
+
+/* Checks A * v = lambda * v for every eigenpair; for a complex pair
+(vr + i * vi) that is A * vr = re * vr - im * vi and A * vi = im * vr + re * vi
+*/
+#[allow(unused_variables)]
+pub(crate) fn check_eigen(a: &Matrix, eigen: &Eigen) {
+	#[cfg(feature = "oracle")]
+	{
+		let n = a.get_size().0;
+		let vectors = &eigen.vectors;
+		let column = |j: usize| {
+			(0..n)
+				.map(|i| vectors.get_value(i, j).unwrap())
+				.collect::<Vec<f64>>()
+		};
+		let apply = |v: &[f64]| -> Vec<f64> {
+			return (0..n)
+				.map(|i| (0..n).map(|j| a.get_value(i, j).unwrap() * v[j]).sum())
+				.collect();
+		};
+		let mut error = 0f64;
+		let mut k = 0;
+		while k < n {
+			let (re, im) = (eigen.real[k], eigen.imaginary[k]);
+			let vr = column(k);
+			if im == 0.0 {
+				let av = apply(&vr);
+				for i in 0..n {
+					error = error.max((av[i] - re * vr[i]).abs());
+				}
+				k += 1;
+				continue;
+			}
+			let vi = column(k + 1);
+			let (avr, avi) = (apply(&vr), apply(&vi));
+			for i in 0..n {
+				error = error.max((avr[i] - re * vr[i] + im * vi[i]).abs());
+				error = error.max((avi[i] - im * vr[i] - re * vi[i]).abs());
+			}
+			k += 2;
+		}
+		let scale = max_abs(a) * max_abs(vectors) * n as f64;
+		record("eigen", relative(error, scale), RESIDUAL_TOLERANCE, false);
+	}
+}
+
+#[cfg(feature = "oracle")]
+fn record(operation: &'static str, error: f64, tolerance: f64, exact: bool) {
+	if error <= tolerance {
+		return;
+	}
+	numerical_warning!(
+		"oracle: {} relative error {:e} exceeds {:e} ({} reference)",
+		operation,
+		error,
+		tolerance,
+		if exact { "exact" } else { "residual" }
+	);
+	DISCREPANCIES.with(|discrepancies| {
+		discrepancies.borrow_mut().push(Discrepancy {
+			operation,
+			error,
+			tolerance,
+			exact,
+		})
+	});
+}
+
+#[cfg(feature = "oracle")]
+fn max_abs(mat: &Matrix) -> f64 {
+	return mat.get_data().iter().fold(0f64, |acc, x| acc.max(x.abs()));
+}
+
+#[cfg(feature = "oracle")]
+fn relative(error: f64, scale: f64) -> f64 {
+	if scale == 0.0 {
+		return error;
+	}
+	return error / scale;
+}
+
+/* max |x - x_exact| / max |x_exact| using Cramer's rule with exact
+determinants. None when A or B are not small integer matrices, A is
+singular, or the integers overflow.
+*/
+#[cfg(feature = "oracle")]
+fn exact_forward_error(a: &Matrix, b: &Matrix, x: &Matrix) -> Option<f64> {
+	let n = a.get_size().0;
+	if n > EXACT_MAX_SIZE {
+		return None;
+	}
+	let integers = |mat: &Matrix| -> Option<Vec<Vec<i128>>> {
+		let (rows, cols) = mat.get_size();
+		let mut out = vec![vec![0i128; cols]; rows];
+		for i in 0..rows {
+			for j in 0..cols {
+				let value = mat.get_value(i, j).ok()?;
+				if value.fract() != 0.0 || value.abs() > EXACT_MAX_ENTRY {
+					return None;
+				}
+				out[i][j] = value as i128;
+			}
+		}
+		return Some(out);
+	};
+	let (a_int, b_int) = (integers(a)?, integers(b)?);
+	let determinant = bareiss_determinant(a_int.clone())?;
+	if determinant == 0 {
+		return None;
+	}
+	let (mut error, mut largest) = (0f64, 0f64);
+	for col in 0..b.get_size().1 {
+		for i in 0..n {
+			let mut replaced = a_int.clone();
+			for row in 0..n {
+				replaced[row][i] = b_int[row][col];
+			}
+			let exact = bareiss_determinant(replaced)? as f64 / determinant as f64;
+			error = error.max((x.get_value(i, col).ok()? - exact).abs());
+			largest = largest.max(exact.abs());
+		}
+	}
+	return Some(relative(error, largest));
+}
+
+// Every intermediate of Bareiss elimination is a minor of A, so divisions are exact
+#[cfg(feature = "oracle")]
+fn bareiss_determinant(mut a: Vec<Vec<i128>>) -> Option<i128> {
+	let n = a.len();
+	let mut sign = 1;
+	let mut previous = 1i128;
+	for k in 0..n.saturating_sub(1) {
+		if a[k][k] == 0 {
+			let row = ((k + 1)..n).find(|row| a[*row][k] != 0)?;
+			a.swap(k, row);
+			sign = -sign;
+		}
+		for i in (k + 1)..n {
+			for j in (k + 1)..n {
+				let left = a[i][j].checked_mul(a[k][k])?;
+				let right = a[i][k].checked_mul(a[k][j])?;
+				a[i][j] = left.checked_sub(right)? / previous;
+			}
+		}
+		previous = a[k][k];
+	}
+	return Some(sign * a[n - 1][n - 1]);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	#[cfg(feature = "oracle")]
+	fn test_oracle() {
+		take_discrepancies();
+		let a = Matrix::new(3, 3, vec![2.0, 1.0, 0.0, 1.0, 3.0, 1.0, 0.0, 1.0, 4.0]).unwrap();
+		let b = Matrix::new(3, 1, vec![1.0, 2.0, 3.0]).unwrap();
+		let x = a.solve(&b).unwrap();
+		a.invert().unwrap();
+		a.eigen().unwrap();
+		assert!(take_discrepancies().is_empty());
+
+		// A wrong solution is caught against the exact one
+		let wrong = (x.clone() + Matrix::new(3, 1, vec![1e-6, 0.0, 0.0]).unwrap()).unwrap();
+		check_solve(&a, &b, &wrong);
+		// And by the residual for non-integer data
+		let scaled = a.multiplied_by_scalar(0.1);
+		check_solve(&scaled, &b, &wrong);
+		let discrepancies = take_discrepancies();
+		assert_eq!(discrepancies.len(), 2);
+		assert!(discrepancies[0].exact && !discrepancies[1].exact);
+		assert_eq!(discrepancies[0].operation, "solve");
+		assert_eq!(bareiss_determinant(vec![vec![2, 1], vec![4, 3]]), Some(2));
+	}
+
+	#[test]
+	#[cfg(not(feature = "oracle"))]
+	fn test_oracle_disabled() {
+		let a = Matrix::identity(2, 2).unwrap();
+		check_solve(&a, &a, &Matrix::zeros(2, 2).unwrap());
+		assert!(take_discrepancies().is_empty());
+	}
+}
@@ -0,0 +1,161 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+
+/* Permutation matrix P stored as an index vector, with the convention of
+`plu_permutation`: row i of P * A is row indices[i] of A. Applying it moves
+rows or columns in O(N * M) instead of multiplying by a dense N x N matrix.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Permutation {
+	indices: Vec<usize>,
+}
+
+impl Permutation {
+	pub fn new(indices: Vec<usize>) -> Result<Self, MathMatrixError> {
+		let mut seen = vec![false; indices.len()];
+		for index in indices.iter() {
+			if *index >= indices.len() || seen[*index] {
+				return Err(MathMatrixError::new(
+					FailedToInitialize,
+					format!("Indices are not a permutation of 0..{}", indices.len()),
+				));
+			}
+			seen[*index] = true;
+		}
+		return Ok(Self { indices });
+	}
+
+	pub fn identity(size: usize) -> Self {
+		return Self {
+			indices: (0..size).collect(),
+		};
+	}
+
+	pub fn get_indices(&self) -> &[usize] {
+		return &self.indices;
+	}
+
+	pub fn get_size(&self) -> (usize, usize) {
+		return (self.indices.len(), self.indices.len());
+	}
+
+	// P^-1 = P^T
+	pub fn inverse(&self) -> Permutation {
+		let mut indices = vec![0; self.indices.len()];
+		for (i, index) in self.indices.iter().enumerate() {
+			indices[*index] = i;
+		}
+		return Self { indices };
+	}
+
+	// P * Q: applying Q first, then P
+	pub fn compose(&self, other: &Permutation) -> Result<Permutation, MathMatrixError> {
+		if self.indices.len() != other.indices.len() {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Operation not allowed between matrices with different sizes".to_owned(),
+			));
+		}
+		return Ok(Self {
+			indices: self.indices.iter().map(|i| other.indices[*i]).collect(),
+		});
+	}
+
+	// det(P): -1 for an odd number of transpositions, 1 otherwise
+	pub fn sign(&self) -> f64 {
+		let mut sign = 1.0;
+		// Each cycle of length k is k - 1 transpositions
+		let mut visited = vec![false; self.indices.len()];
+		for start in 0..self.indices.len() {
+			let mut i = start;
+			let mut length = 0;
+			while !visited[i] {
+				visited[i] = true;
+				i = self.indices[i];
+				length += 1;
+			}
+			if length > 0 && length % 2 == 0 {
+				sign = -sign;
+			}
+		}
+		return sign;
+	}
+
+	pub fn to_matrix(&self) -> Result<Matrix, MathMatrixError> {
+		let size = self.indices.len();
+		let mut mat = Matrix::zeros(size, size)?;
+		for (row, index) in self.indices.iter().enumerate() {
+			mat.set_value(row, *index, 1.0)?;
+		}
+		return Ok(mat);
+	}
+
+	// P * A
+	pub fn permute_rows(&self, a: &Matrix) -> Result<Matrix, MathMatrixError> {
+		let (rows, cols) = a.get_size();
+		if rows != self.indices.len() {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Multiplication allowed for NxM * MxO".to_owned(),
+			));
+		}
+		let source = a.get_data();
+		let mut data = vec![0f64; rows * cols];
+		for j in 0..cols {
+			for (i, index) in self.indices.iter().enumerate() {
+				data[j * rows + i] = source[j * rows + index];
+			}
+		}
+		return Matrix::new(rows, cols, data);
+	}
+
+	// A * P^T: column j of the result is column indices[j] of A
+	pub fn permute_columns(&self, a: &Matrix) -> Result<Matrix, MathMatrixError> {
+		let (rows, cols) = a.get_size();
+		if cols != self.indices.len() {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Multiplication allowed for NxM * MxO".to_owned(),
+			));
+		}
+		let source = a.get_data();
+		let mut data = Vec::with_capacity(rows * cols);
+		for index in self.indices.iter() {
+			data.extend_from_slice(&source[index * rows..(index + 1) * rows]);
+		}
+		return Matrix::new(rows, cols, data);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_permutation() {
+		let p = Permutation::new(vec![2, 0, 1]).unwrap();
+		let q = Permutation::new(vec![1, 0, 2]).unwrap();
+		let a = Matrix::new(3, 2, (0..6).map(|k| k as f64).collect()).unwrap();
+		let p_mat = p.to_matrix().unwrap();
+		assert_eq!(
+			p.permute_rows(&a).unwrap(),
+			p_mat.multiplied_by_matrix(&a).unwrap()
+		);
+		let b = a.transposed();
+		assert_eq!(
+			p.permute_columns(&b).unwrap(),
+			b.multiplied_by_matrix(&p_mat.transposed()).unwrap()
+		);
+		assert_eq!(
+			p.compose(&q).unwrap().to_matrix().unwrap(),
+			p_mat.multiplied_by_matrix(&q.to_matrix().unwrap()).unwrap()
+		);
+		assert_eq!(p.compose(&p.inverse()).unwrap(), Permutation::identity(3));
+		// A 3-cycle is even, a transposition odd
+		assert_eq!(p.sign(), 1.0);
+		assert_eq!(q.sign(), -1.0);
+		assert!(Permutation::new(vec![0, 0, 1]).is_err());
+		assert!(p.permute_rows(&b).is_err());
+	}
+}
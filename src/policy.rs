@@ -0,0 +1,123 @@
+use std::sync::RwLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MultiplicationAlgorithm {
+	Naive,
+	// Cache-friendly tiles, same rounding as Naive
+	Blocked,
+	// O(n^2.81) recursion, pays off only for large square-ish products
+	Strassen,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QrAlgorithm {
+	// Modified Gram-Schmidt, cheaper but loses orthogonality on ill-conditioned input
+	GramSchmidt,
+	Householder,
+}
+
+// Accumulation used by dot products, norms and determinants
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+	// Plain f64 sums and products
+	Standard,
+	/* Double-double accumulators (about 106 significant bits) with
+	error-free transformations, a few times slower. Only the accumulation is
+	extended: inputs and results are f64. Needs the `extended-precision`
+	feature; without it, accumulation falls back to Standard.
+	*/
+	Extended,
+}
+
+/* Which algorithm to run where several exist. `None` selects one from the
+problem size. The global policy applies to plain calls such as
+`multiplied_by_matrix` and `qr`; the `*_with` variants take an explicit
+algorithm instead.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlgorithmPolicy {
+	pub multiplication: Option<MultiplicationAlgorithm>,
+	pub qr: Option<QrAlgorithm>,
+	pub precision: Precision,
+}
+
+impl AlgorithmPolicy {
+	pub const AUTOMATIC: AlgorithmPolicy = AlgorithmPolicy {
+		multiplication: None,
+		qr: None,
+		precision: Precision::Standard,
+	};
+
+	// Product of an MxK by a KxN matrix
+	pub fn multiplication_for(&self, m: usize, k: usize, n: usize) -> MultiplicationAlgorithm {
+		if let Some(algorithm) = self.multiplication {
+			return algorithm;
+		}
+		let smallest = m.min(k).min(n);
+		let largest = m.max(k).max(n);
+		if smallest >= 512 && largest <= 2 * smallest {
+			return MultiplicationAlgorithm::Strassen;
+		}
+		if largest >= 64 {
+			return MultiplicationAlgorithm::Blocked;
+		}
+		return MultiplicationAlgorithm::Naive;
+	}
+
+	pub fn qr_for(&self, _rows: usize, _cols: usize) -> QrAlgorithm {
+		return self.qr.unwrap_or(QrAlgorithm::Householder);
+	}
+}
+
+impl Default for AlgorithmPolicy {
+	fn default() -> Self {
+		return Self::AUTOMATIC;
+	}
+}
+
+static GLOBAL_POLICY: RwLock<AlgorithmPolicy> = RwLock::new(AlgorithmPolicy::AUTOMATIC);
+
+pub fn global_policy() -> AlgorithmPolicy {
+	return *GLOBAL_POLICY.read().unwrap_or_else(|err| err.into_inner());
+}
+
+pub fn set_global_policy(policy: AlgorithmPolicy) {
+	*GLOBAL_POLICY.write().unwrap_or_else(|err| err.into_inner()) = policy;
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_size_based_defaults() {
+		let policy = AlgorithmPolicy::AUTOMATIC;
+		assert_eq!(
+			policy.multiplication_for(4, 4, 4),
+			MultiplicationAlgorithm::Naive
+		);
+		assert_eq!(
+			policy.multiplication_for(100, 3, 100),
+			MultiplicationAlgorithm::Blocked
+		);
+		assert_eq!(
+			policy.multiplication_for(600, 700, 800),
+			MultiplicationAlgorithm::Strassen
+		);
+		assert_eq!(policy.qr_for(10, 3), QrAlgorithm::Householder);
+	}
+
+	#[test]
+	fn test_override() {
+		let policy = AlgorithmPolicy {
+			multiplication: Some(MultiplicationAlgorithm::Strassen),
+			qr: Some(QrAlgorithm::GramSchmidt),
+			precision: Precision::Standard,
+		};
+		assert_eq!(
+			policy.multiplication_for(2, 2, 2),
+			MultiplicationAlgorithm::Strassen
+		);
+		assert_eq!(policy.qr_for(2, 2), QrAlgorithm::GramSchmidt);
+	}
+}
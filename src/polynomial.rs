@@ -0,0 +1,278 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+
+/* Polynomial with real coefficients in ascending order: c0 + c1 x + c2 x^2 ...
+Trailing zero coefficients are dropped, so the zero polynomial has none.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polynomial {
+	coefficients: Vec<f64>,
+}
+
+impl Polynomial {
+	pub fn new(coefficients: Vec<f64>) -> Self {
+		let mut coefficients = coefficients;
+		while coefficients.last() == Some(&0.0) {
+			coefficients.pop();
+		}
+		return Self { coefficients };
+	}
+
+	pub fn constant(value: f64) -> Self {
+		return Self::new(vec![value]);
+	}
+
+	// The polynomial x
+	pub fn x() -> Self {
+		return Self::new(vec![0.0, 1.0]);
+	}
+
+	pub fn get_coefficients(&self) -> &[f64] {
+		return &self.coefficients;
+	}
+
+	// None for the zero polynomial
+	pub fn degree(&self) -> Option<usize> {
+		return self.coefficients.len().checked_sub(1);
+	}
+
+	// Horner's scheme
+	pub fn evaluate(&self, x: f64) -> f64 {
+		return self
+			.coefficients
+			.iter()
+			.rev()
+			.fold(0.0, |acc, c| acc * x + c);
+	}
+}
+
+impl std::ops::Add for Polynomial {
+	type Output = Polynomial;
+
+	fn add(self, other: Polynomial) -> Polynomial {
+		let (mut long, short) = if self.coefficients.len() >= other.coefficients.len() {
+			(self.coefficients, other.coefficients)
+		} else {
+			(other.coefficients, self.coefficients)
+		};
+		for (a, b) in long.iter_mut().zip(short.iter()) {
+			*a += b;
+		}
+		return Polynomial::new(long);
+	}
+}
+
+impl std::ops::Neg for Polynomial {
+	type Output = Polynomial;
+
+	fn neg(self) -> Polynomial {
+		return Polynomial::new(self.coefficients.iter().map(|c| -c).collect());
+	}
+}
+
+impl std::ops::Sub for Polynomial {
+	type Output = Polynomial;
+
+	fn sub(self, other: Polynomial) -> Polynomial {
+		return self + (-other);
+	}
+}
+
+impl std::ops::Mul for Polynomial {
+	type Output = Polynomial;
+
+	fn mul(self, other: Polynomial) -> Polynomial {
+		if self.coefficients.is_empty() || other.coefficients.is_empty() {
+			return Polynomial::new(vec![]);
+		}
+		let mut product = vec![0f64; self.coefficients.len() + other.coefficients.len() - 1];
+		for (i, a) in self.coefficients.iter().enumerate() {
+			for (j, b) in other.coefficients.iter().enumerate() {
+				product[i + j] += a * b;
+			}
+		}
+		return Polynomial::new(product);
+	}
+}
+
+// Largest PolyMatrix whose determinant is computed; the work grows as N^2 * 2^N
+const MAX_DETERMINANT_SIZE: usize = 16;
+
+/* Matrix of polynomials in one variable, column major like Matrix. Meant for
+small matrices such as x * I - A or the denominators of transfer functions:
+the determinant is computed symbolically, without dividing polynomials.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolyMatrix {
+	rows: usize,
+	cols: usize,
+	data: Vec<Polynomial>,
+}
+
+impl PolyMatrix {
+	pub fn new(rows: usize, cols: usize, data: Vec<Polynomial>) -> Result<Self, MathMatrixError> {
+		if rows * cols == 0 {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"Rows and columns must be lager than 0".to_owned(),
+			));
+		}
+		if rows * cols != data.len() {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				format!(
+					"Size of data != rows * cols: {} != {}",
+					data.len(),
+					rows * cols
+				),
+			));
+		}
+		return Ok(Self { rows, cols, data });
+	}
+
+	// Constant polynomials
+	pub fn from_matrix(mat: &Matrix) -> Self {
+		let (rows, cols) = mat.get_size();
+		let data = mat
+			.get_data()
+			.iter()
+			.map(|x| Polynomial::constant(*x))
+			.collect();
+		return Self { rows, cols, data };
+	}
+
+	// x * I - A, whose determinant is the characteristic polynomial of A
+	pub fn characteristic_matrix(a: &Matrix) -> Result<Self, MathMatrixError> {
+		let (rows, cols) = a.get_size();
+		if rows != cols {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Characteristic matrix requires a square NxN matrix".to_owned(),
+			));
+		}
+		let mut mat = Self::from_matrix(a);
+		for elem in mat.data.iter_mut() {
+			*elem = -elem.clone();
+		}
+		for i in 0..rows {
+			let index = i * rows + i;
+			mat.data[index] = Polynomial::x() + mat.data[index].clone();
+		}
+		return Ok(mat);
+	}
+
+	pub fn get_size(&self) -> (usize, usize) {
+		return (self.rows, self.cols);
+	}
+
+	pub fn get_value(&self, row: usize, col: usize) -> Result<&Polynomial, MathMatrixError> {
+		if row >= self.rows || col >= self.cols {
+			return Err(MathMatrixError::new(
+				OutOfBoundary,
+				format!(
+					"Entry ({}, {}) outside a {}x{} matrix",
+					row, col, self.rows, self.cols
+				),
+			));
+		}
+		return Ok(&self.data[col * self.rows + row]);
+	}
+
+	// Value of every entry at x
+	pub fn evaluate(&self, x: f64) -> Matrix {
+		return Matrix::from_vec_unchecked(
+			self.rows,
+			self.cols,
+			self.data.iter().map(|p| p.evaluate(x)).collect(),
+		);
+	}
+
+	/* Laplace expansion along the columns, sharing minors: minors[mask] is
+	the determinant of the rows in `mask` and the first popcount(mask)
+	columns. Only ring operations are used, so no division by a polynomial
+	is needed.
+	*/
+	pub fn determinant(&self) -> Result<Polynomial, MathMatrixError> {
+		if self.rows != self.cols {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				"Determinant allowed only for square matrices".to_owned(),
+			));
+		}
+		let n = self.rows;
+		if n > MAX_DETERMINANT_SIZE {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				format!(
+					"Symbolic determinant limited to {}x{} matrices",
+					MAX_DETERMINANT_SIZE, MAX_DETERMINANT_SIZE
+				),
+			));
+		}
+		let mut minors = vec![Polynomial::new(vec![]); 1 << n];
+		minors[0] = Polynomial::constant(1.0);
+		for mask in 1usize..(1 << n) {
+			// Expand along column k, the last of the minor
+			let k = mask.count_ones() as usize - 1;
+			let mut minor = Polynomial::new(vec![]);
+			for (position, i) in (0..n).filter(|i| mask & (1 << i) != 0).enumerate() {
+				let term = self.data[k * n + i].clone() * minors[mask & !(1 << i)].clone();
+				minor = if (position + k).is_multiple_of(2) {
+					minor + term
+				} else {
+					minor - term
+				};
+			}
+			minors[mask] = minor;
+		}
+		return Ok(minors[(1 << n) - 1].clone());
+	}
+}
+
+impl Matrix {
+	// det(x * I - A), monic of degree N
+	pub fn characteristic_polynomial(&self) -> Result<Polynomial, MathMatrixError> {
+		return PolyMatrix::characteristic_matrix(self)?.determinant();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_polynomial_arithmetic() {
+		let p = Polynomial::new(vec![1.0, 2.0, 0.0]);
+		assert_eq!(p.degree(), Some(1));
+		let q = Polynomial::x() * Polynomial::x() - Polynomial::constant(1.0);
+		assert_eq!(
+			(p.clone() * q.clone()).get_coefficients(),
+			&[-1.0, -2.0, 1.0, 2.0]
+		);
+		assert_eq!((q.clone() - q).degree(), None);
+		assert_eq!(p.evaluate(3.0), 7.0);
+	}
+
+	#[test]
+	fn test_characteristic_polynomial() {
+		// Eigenvalues 1 and 3: x^2 - 4x + 3
+		let a = Matrix::new(2, 2, vec![2.0, 1.0, 1.0, 2.0]).unwrap();
+		assert_eq!(
+			a.characteristic_polynomial().unwrap().get_coefficients(),
+			&[3.0, -4.0, 1.0]
+		);
+		// A companion matrix recovers its polynomial x^3 + 2x^2 - 5x + 7
+		let companion =
+			Matrix::new(3, 3, vec![0.0, 1.0, 0.0, 0.0, 0.0, 1.0, -7.0, 5.0, -2.0]).unwrap();
+		let p = companion.characteristic_polynomial().unwrap();
+		assert_eq!(p.get_coefficients(), &[7.0, -5.0, 2.0, 1.0]);
+		// Evaluating det(x * I - A) agrees with the numeric determinant
+		let char_mat = PolyMatrix::characteristic_matrix(&companion).unwrap();
+		let numeric = char_mat.evaluate(1.5).determinant().unwrap();
+		assert!((numeric - p.evaluate(1.5)).abs() < 1e-12);
+		assert!(PolyMatrix::from_matrix(&Matrix::zeros(2, 3).unwrap())
+			.determinant()
+			.is_err());
+	}
+}
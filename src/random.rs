@@ -0,0 +1,238 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+
+/* Seeded pseudo-random numbers (xoshiro256**, state expanded from the seed
+with SplitMix64). Not suitable for cryptography; the same seed always gives
+the same sequence, which keeps simulations reproducible.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rng {
+	state: [u64; 4],
+	// Second value of the last Box-Muller pair
+	spare_normal: Option<f64>,
+}
+
+impl Rng {
+	pub fn new(seed: u64) -> Self {
+		let mut seed = seed;
+		let mut state = [0u64; 4];
+		for word in state.iter_mut() {
+			seed = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+			let mut z = seed;
+			z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+			z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+			*word = z ^ (z >> 31);
+		}
+		return Self {
+			state,
+			spare_normal: None,
+		};
+	}
+
+	pub fn next_u64(&mut self) -> u64 {
+		let s = &mut self.state;
+		let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+		let t = s[1] << 17;
+		s[2] ^= s[0];
+		s[3] ^= s[1];
+		s[1] ^= s[2];
+		s[0] ^= s[3];
+		s[2] ^= t;
+		s[3] = s[3].rotate_left(45);
+		return result;
+	}
+
+	// Uniform in [0, 1), with 53 random bits
+	pub fn uniform(&mut self) -> f64 {
+		return (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+	}
+
+	// Standard normal N(0, 1) by the Box-Muller transform
+	pub fn normal(&mut self) -> f64 {
+		if let Some(value) = self.spare_normal.take() {
+			return value;
+		}
+		// 1 - u is in (0, 1], so the logarithm is finite
+		let radius = (-2.0 * (1.0 - self.uniform()).ln()).sqrt();
+		let angle = 2.0 * std::f64::consts::PI * self.uniform();
+		self.spare_normal = Some(radius * angle.sin());
+		return radius * angle.cos();
+	}
+
+	// rows x cols matrix of independent N(0, 1) entries
+	pub fn normal_matrix(&mut self, rows: usize, cols: usize) -> Result<Matrix, MathMatrixError> {
+		return Matrix::new(
+			rows,
+			cols,
+			(0..rows * cols).map(|_| self.normal()).collect(),
+		);
+	}
+}
+
+/* Multivariate normal distribution over matrices of a fixed shape: vec(X)
+has the given mean and covariance. Sampling uses a factor F with
+F * F^T = covariance from the symmetric eigendecomposition, so covariances
+that are only positive semidefinite work too.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultivariateNormal {
+	mean: Matrix,
+	factor: Matrix,
+}
+
+impl MultivariateNormal {
+	// mean: MxN, covariance: (M * N)x(M * N) symmetric positive semidefinite
+	pub fn new(mean: Matrix, covariance: &Matrix) -> Result<Self, MathMatrixError> {
+		let (rows, cols) = mean.get_size();
+		if covariance.get_size() != (rows * cols, rows * cols) {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Covariance must be (M * N)x(M * N) for an MxN mean".to_owned(),
+			));
+		}
+		let (values, vectors) = covariance.eigen_symmetric()?;
+		let largest = values.iter().fold(0f64, |acc, x| acc.max(x.abs()));
+		if values.iter().any(|value| *value < -1e-12 * largest) {
+			return Err(MathMatrixError::new(
+				NotPositiveDefinite,
+				"Covariance must be positive semidefinite".to_owned(),
+			));
+		}
+		let factor = vectors.map_cols(|j, column| {
+			let scale = values[j].max(0.0).sqrt();
+			return column.iter().map(|v| v * scale).collect();
+		})?;
+		return Ok(Self { mean, factor });
+	}
+
+	pub fn get_mean(&self) -> &Matrix {
+		return &self.mean;
+	}
+
+	pub fn sample(&self, rng: &mut Rng) -> Result<Matrix, MathMatrixError> {
+		let (rows, cols) = self.mean.get_size();
+		let noise = self
+			.factor
+			.multiplied_by_matrix(&rng.normal_matrix(rows * cols, 1)?)?;
+		return self.mean.clone() + noise.unvec(rows, cols)?;
+	}
+}
+
+// Entrywise mean of equally sized matrices
+pub fn sample_mean(samples: &[Matrix]) -> Result<Matrix, MathMatrixError> {
+	check_samples(samples, 1)?;
+	let (rows, cols) = samples[0].get_size();
+	let mut sum = Matrix::zeros(rows, cols)?;
+	for sample in samples {
+		sum = (sum + sample.clone())?;
+	}
+	return Ok(sum.multiplied_by_scalar(1.0 / samples.len() as f64));
+}
+
+/* Unbiased sample covariance of vec(X) over equally sized matrices X, with
+the 1 / (count - 1) normalization
+*/
+pub fn sample_covariance(samples: &[Matrix]) -> Result<Matrix, MathMatrixError> {
+	check_samples(samples, 2)?;
+	let mean = sample_mean(samples)?.vec();
+	let size = mean.get_size().0;
+	let mut sum = Matrix::zeros(size, size)?;
+	for sample in samples {
+		let deviation = (sample.vec() - mean.clone())?;
+		sum = (sum + deviation.multiplied_by_matrix(&deviation.transposed())?)?;
+	}
+	return Ok(sum.multiplied_by_scalar(1.0 / (samples.len() - 1) as f64));
+}
+
+// Output statistics of a Monte Carlo run
+#[derive(Debug, Clone, PartialEq)]
+pub struct Propagated {
+	// Sample mean of f(X), same shape as f(X)
+	pub mean: Matrix,
+	// Sample covariance of vec(f(X))
+	pub covariance: Matrix,
+}
+
+/* Monte Carlo uncertainty propagation: evaluates f on `samples` draws of X
+from `input` and returns the mean and covariance of the outputs. Unlike
+linearization, this captures the effect of nonlinearity in f, at a cost of
+one evaluation per sample; the statistical error decreases like
+1 / sqrt(samples). Fails on the first error returned by f.
+*/
+pub fn propagate_uncertainty<F: Fn(&Matrix) -> Result<Matrix, MathMatrixError>>(
+	f: F,
+	input: &MultivariateNormal,
+	samples: usize,
+	rng: &mut Rng,
+) -> Result<Propagated, MathMatrixError> {
+	let mut outputs = Vec::with_capacity(samples);
+	for _ in 0..samples {
+		outputs.push(f(&input.sample(rng)?)?);
+	}
+	return Ok(Propagated {
+		mean: sample_mean(&outputs)?,
+		covariance: sample_covariance(&outputs)?,
+	});
+}
+
+fn check_samples(samples: &[Matrix], minimum: usize) -> Result<(), MathMatrixError> {
+	if samples.len() < minimum {
+		return Err(MathMatrixError::new(
+			OperationNotPermitted,
+			format!("At least {} samples required", minimum),
+		));
+	}
+	let size = samples[0].get_size();
+	if samples.iter().any(|sample| sample.get_size() != size) {
+		return Err(MathMatrixError::new(
+			SizeMismatch,
+			"All samples must have the same size".to_owned(),
+		));
+	}
+	return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_rng() {
+		let mut rng = Rng::new(42);
+		let mut same_seed = Rng::new(42);
+		let values: Vec<f64> = (0..1000).map(|_| rng.uniform()).collect();
+		assert!(values.iter().all(|v| (0.0..1.0).contains(v)));
+		assert_eq!(same_seed.uniform(), values[0]);
+		let mean = values.iter().sum::<f64>() / 1000.0;
+		assert!((mean - 0.5).abs() < 0.05);
+		let normals: Vec<f64> = (0..10000).map(|_| rng.normal()).collect();
+		let mean = normals.iter().sum::<f64>() / 10000.0;
+		let variance = normals.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / 9999.0;
+		assert!(mean.abs() < 0.05 && (variance - 1.0).abs() < 0.05);
+	}
+
+	#[test]
+	fn test_propagate_uncertainty() {
+		// Linear map x = A^-1 * b: exact output mean A^-1 * mu, covariance A^-1 * S * A^-T
+		let a = Matrix::new(2, 2, vec![4.0, 1.0, 1.0, 3.0]).unwrap();
+		let mean = Matrix::new(2, 1, vec![1.0, 2.0]).unwrap();
+		let covariance = Matrix::new(2, 2, vec![0.04, 0.01, 0.01, 0.09]).unwrap();
+		let input = MultivariateNormal::new(mean.clone(), &covariance).unwrap();
+		let mut rng = Rng::new(7);
+		let result = propagate_uncertainty(|b| a.solve(b), &input, 20000, &mut rng).unwrap();
+		let inverse = a.invert().unwrap();
+		let expected_mean = inverse.multiplied_by_matrix(&mean).unwrap();
+		let expected_covariance = inverse
+			.multiplied_by_matrix(&covariance)
+			.unwrap()
+			.multiplied_by_matrix(&inverse.transposed())
+			.unwrap();
+		assert!(result.mean.approx_eq(&expected_mean, 5e-3));
+		assert!(result.covariance.approx_eq(&expected_covariance, 5e-4));
+
+		let not_psd = Matrix::new(2, 2, vec![1.0, 2.0, 2.0, 1.0]).unwrap();
+		assert!(MultivariateNormal::new(mean.clone(), &not_psd).is_err());
+		assert!(sample_covariance(&[mean]).is_err());
+	}
+}
@@ -0,0 +1,131 @@
+use super::policy::{self, Precision};
+
+/* Dot products, norms and products with the accumulation selected by
+`AlgorithmPolicy::precision`. The extended path keeps a double-double
+(hi, lo) accumulator, updated with error-free transformations: two_sum and
+two_product return the rounded result together with its exact rounding
+error.
+*/
+
+pub(crate) fn dot(x: &[f64], y: &[f64]) -> f64 {
+	return dot_with(policy::global_policy().precision, x, y);
+}
+
+pub(crate) fn norm(x: &[f64]) -> f64 {
+	return dot(x, x).sqrt();
+}
+
+// Product of all values, e.g. the diagonal of U for a determinant
+pub(crate) fn product<I: Iterator<Item = f64>>(values: I) -> f64 {
+	return product_with(policy::global_policy().precision, values);
+}
+
+pub(crate) fn dot_with(precision: Precision, x: &[f64], y: &[f64]) -> f64 {
+	match precision {
+		Precision::Extended if cfg!(feature = "extended-precision") => {
+			// Dot2 of Ogita, Rump and Oishi
+			let (mut sum, mut error) = (0f64, 0f64);
+			for (a, b) in x.iter().zip(y.iter()) {
+				let (product, product_error) = two_product(*a, *b);
+				let (next, sum_error) = two_sum(sum, product);
+				sum = next;
+				error += product_error + sum_error;
+			}
+			return sum + error;
+		}
+		_ => {
+			return x.iter().zip(y.iter()).map(|(a, b)| a * b).sum();
+		}
+	}
+}
+
+pub(crate) fn product_with<I: Iterator<Item = f64>>(precision: Precision, values: I) -> f64 {
+	match precision {
+		Precision::Extended if cfg!(feature = "extended-precision") => {
+			let (mut hi, mut lo) = (1f64, 0f64);
+			for value in values {
+				// (hi + lo) * value, renormalized
+				let (product, error) = two_product(hi, value);
+				let (next_hi, next_lo) = two_sum(product, error + lo * value);
+				hi = next_hi;
+				lo = next_lo;
+			}
+			return hi + lo;
+		}
+		_ => {
+			return values.product();
+		}
+	}
+}
+
+// a + b = sum + error exactly (Knuth)
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+	let sum = a + b;
+	let b_virtual = sum - a;
+	let error = (a - (sum - b_virtual)) + (b - b_virtual);
+	return (sum, error);
+}
+
+// a * b = product + error exactly, using a fused multiply-add
+fn two_product(a: f64, b: f64) -> (f64, f64) {
+	let product = a * b;
+	return (product, a.mul_add(b, -product));
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_standard_accumulation() {
+		assert_eq!(
+			dot_with(Precision::Standard, &[1.0, 2.0], &[3.0, 4.0]),
+			11.0
+		);
+		// 1 is lost next to 1e16
+		assert_eq!(
+			dot_with(Precision::Standard, &[1e16, 1.0, -1e16], &[1.0; 3]),
+			0.0
+		);
+		assert_eq!(
+			product_with(Precision::Standard, [2.0, 3.0].iter().cloned()),
+			6.0
+		);
+	}
+
+	#[test]
+	#[cfg(feature = "extended-precision")]
+	fn test_extended_accumulation() {
+		assert_eq!(
+			dot_with(Precision::Extended, &[1e16, 1.0, -1e16], &[1.0; 3]),
+			1.0
+		);
+		// (1 + 2^-30)^2 = 1 + 2^-29 + 2^-60: the last term needs the low word
+		let x = 1.0 + 2f64.powi(-30);
+		let exact_tail = dot_with(
+			Precision::Extended,
+			&[x, -1.0, -2f64.powi(-29)],
+			&[x, 1.0, 1.0],
+		);
+		assert_eq!(exact_tail, 2f64.powi(-60));
+		// 0.1 * 0.2 * 0.3 * 0.4 of the f64 inputs, correctly rounded
+		let values = || (1..=4).map(|k| 0.1 * k as f64);
+		assert_eq!(
+			product_with(Precision::Standard, values()),
+			0.002400000000000001
+		);
+		assert_eq!(
+			product_with(Precision::Extended, values()),
+			0.0024000000000000007
+		);
+	}
+
+	#[test]
+	#[cfg(not(feature = "extended-precision"))]
+	fn test_extended_falls_back_to_standard() {
+		assert_eq!(
+			dot_with(Precision::Extended, &[1e16, 1.0, -1e16], &[1.0; 3]),
+			0.0
+		);
+	}
+}
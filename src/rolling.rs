@@ -0,0 +1,98 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+
+/* Rolling windows along the row axis: window k covers rows k..k + window,
+so an N-row matrix has N - window + 1 windows. Rows are observations and
+columns are variables, as in `TimeSeries`.
+*/
+impl Matrix {
+	// f applied to each window, a window x M block, in order
+	pub fn rolling_apply<F: FnMut(&Matrix) -> Result<Matrix, MathMatrixError>>(
+		&self,
+		window: usize,
+		mut f: F,
+	) -> Result<Vec<Matrix>, MathMatrixError> {
+		let (rows, cols) = self.get_size();
+		check_window(window, 1, rows)?;
+		return (0..=(rows - window))
+			.map(|start| f(&self.get_block(start, 0, window, cols)?))
+			.collect();
+	}
+
+	/* Row k is the mean of window k, (N - window + 1) x M. Uses a running
+	sum per column, so the cost does not depend on the window length.
+	*/
+	pub fn rolling_mean(&self, window: usize) -> Result<Matrix, MathMatrixError> {
+		let (rows, cols) = self.get_size();
+		check_window(window, 1, rows)?;
+		let count = rows - window + 1;
+		let data = self.get_data();
+		let mut means = Vec::with_capacity(count * cols);
+		for column in data.chunks(rows) {
+			let mut sum: f64 = column[..window].iter().sum();
+			means.push(sum / window as f64);
+			for k in 1..count {
+				sum += column[k + window - 1] - column[k - 1];
+				means.push(sum / window as f64);
+			}
+		}
+		return Matrix::new(count, cols, means);
+	}
+
+	// Unbiased M x M sample covariance of the columns over each window
+	pub fn rolling_cov(&self, window: usize) -> Result<Vec<Matrix>, MathMatrixError> {
+		check_window(window, 2, self.get_size().0)?;
+		return self.rolling_apply(window, |block| {
+			let (rows, cols) = block.get_size();
+			let mut centered = block.get_data();
+			for column in centered.chunks_mut(rows) {
+				let mean = column.iter().sum::<f64>() / rows as f64;
+				column.iter_mut().for_each(|x| *x -= mean);
+			}
+			let centered = Matrix::new(rows, cols, centered)?;
+			return Ok(centered
+				.transposed()
+				.multiplied_by_matrix(&centered)?
+				.multiplied_by_scalar(1.0 / (rows - 1) as f64));
+		});
+	}
+}
+
+fn check_window(window: usize, min: usize, rows: usize) -> Result<(), MathMatrixError> {
+	if window < min || window > rows {
+		return Err(MathMatrixError::new(
+			OperationNotPermitted,
+			format!(
+				"Window of {} rows outside {}..={} for a matrix with {} rows",
+				window, min, rows, rows
+			),
+		));
+	}
+	return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_rolling_windows() {
+		let a = Matrix::new(4, 2, vec![1.0, 2.0, 4.0, 8.0, 1.0, 3.0, 2.0, 6.0]).unwrap();
+		let means = a.rolling_mean(2).unwrap();
+		assert_eq!(means.get_data(), vec![1.5, 3.0, 6.0, 2.0, 2.5, 4.0]);
+		let sums = a
+			.rolling_apply(3, |block| {
+				return Matrix::new(1, 3, vec![1.0; 3])?.multiplied_by_matrix(block);
+			})
+			.unwrap();
+		assert_eq!(sums.len(), 2);
+		assert_eq!(sums[1].get_data(), vec![14.0, 11.0]);
+		let covariances = a.rolling_cov(2).unwrap();
+		assert_eq!(covariances.len(), 3);
+		// Rows (1, 1) and (2, 3): deviations (-0.5, -1) and (0.5, 1)
+		assert_eq!(covariances[0].get_data(), vec![0.5, 1.0, 1.0, 2.0]);
+		assert!(a.rolling_mean(5).is_err());
+		assert!(a.rolling_cov(1).is_err());
+	}
+}
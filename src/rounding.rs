@@ -0,0 +1,166 @@
+use super::error::MathMatrixError;
+use super::matrix::Matrix;
+use super::random::Rng;
+
+// Storage formats narrower than f64, described by their significand and exponent ranges
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LowPrecision {
+	// IEEE binary32
+	F32,
+	// IEEE binary16
+	F16,
+}
+
+impl LowPrecision {
+	// Significand bits, including the implicit leading one
+	fn digits(&self) -> i32 {
+		match self {
+			LowPrecision::F32 => 24,
+			LowPrecision::F16 => 11,
+		}
+	}
+
+	fn min_exponent(&self) -> i32 {
+		match self {
+			LowPrecision::F32 => -126,
+			LowPrecision::F16 => -14,
+		}
+	}
+
+	pub fn max_value(&self) -> f64 {
+		match self {
+			LowPrecision::F32 => f32::MAX as f64,
+			LowPrecision::F16 => 65504.0,
+		}
+	}
+
+	// Unit roundoff of round-to-nearest, 2^-digits
+	pub fn unit_roundoff(&self) -> f64 {
+		return 2f64.powi(-self.digits());
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundingMode {
+	// Round to nearest, ties to even, as the hardware does
+	Nearest,
+	/* Round to one of the two neighbouring representable values with
+	probability proportional to proximity, so the rounding error is zero in
+	expectation and does not accumulate as stagnation in long sums.
+	*/
+	Stochastic,
+}
+
+/* How results are rounded when stored in a low-precision format. Rounded
+values are returned as f64 holding exactly a value of the target format, so
+every kernel of the crate can run on them unchanged and the precision effects
+can be studied in isolation. Stochastic rounding draws from its own seeded
+generator: the same seed and the same sequence of calls round identically.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementPolicy {
+	format: LowPrecision,
+	mode: RoundingMode,
+	rng: Rng,
+}
+
+impl ElementPolicy {
+	pub fn new(format: LowPrecision, mode: RoundingMode, seed: u64) -> Self {
+		return Self {
+			format,
+			mode,
+			rng: Rng::new(seed),
+		};
+	}
+
+	pub fn get_format(&self) -> LowPrecision {
+		return self.format;
+	}
+
+	pub fn get_mode(&self) -> RoundingMode {
+		return self.mode;
+	}
+
+	pub fn round(&mut self, x: f64) -> f64 {
+		if !x.is_finite() || x == 0.0 {
+			return x;
+		}
+		// Spacing of the format around x; fixed below the normal range (subnormals)
+		let exponent =
+			(((x.to_bits() >> 52) & 0x7ff) as i32 - 1023).max(self.format.min_exponent());
+		let ulp = 2f64.powi(exponent - self.format.digits() + 1);
+		// Exact: scaling by a power of two and flooring lose nothing in f64
+		let scaled = x / ulp;
+		let below = scaled.floor();
+		let steps = match self.mode {
+			RoundingMode::Nearest => scaled.round_ties_even(),
+			RoundingMode::Stochastic => {
+				if self.rng.uniform() < scaled - below {
+					below + 1.0
+				} else {
+					below
+				}
+			}
+		};
+		let rounded = steps * ulp;
+		if rounded.abs() > self.format.max_value() {
+			return f64::INFINITY.copysign(x);
+		}
+		return rounded;
+	}
+
+	// Narrows to f32 storage; exact once rounded, whatever the format
+	pub fn round_to_f32(&mut self, x: f64) -> f32 {
+		return self.round(x) as f32;
+	}
+
+	// Every entry rounded, in column-major order
+	pub fn round_matrix(&mut self, mat: &Matrix) -> Result<Matrix, MathMatrixError> {
+		let (rows, cols) = mat.get_size();
+		let data = mat.get_data().iter().map(|x| self.round(*x)).collect();
+		return Matrix::new(rows, cols, data);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_round_nearest() {
+		let mut policy = ElementPolicy::new(LowPrecision::F32, RoundingMode::Nearest, 0);
+		for x in [0.1, -1.0 / 3.0, 1e-40, 123456789.123, f64::MAX].iter() {
+			assert_eq!(policy.round_to_f32(*x), *x as f32);
+		}
+		let mut policy = ElementPolicy::new(LowPrecision::F16, RoundingMode::Nearest, 0);
+		// Halfway between 1 and the next half-precision value: ties to even
+		assert_eq!(policy.round(1.0 + 2f64.powi(-11)), 1.0);
+		assert_eq!(
+			policy.round(1.0 + 3.0 * 2f64.powi(-11)),
+			1.0 + 2f64.powi(-9)
+		);
+		assert_eq!(policy.round(65504.0), 65504.0);
+		assert_eq!(policy.round(-65520.0), f64::NEG_INFINITY);
+		// Smallest subnormal
+		assert_eq!(policy.round(2f64.powi(-24) * 0.9), 2f64.powi(-24));
+	}
+
+	#[test]
+	fn test_round_stochastic() {
+		let x = 1.0 + 2f64.powi(-12);
+		let (below, above) = (1.0, 1.0 + 2f64.powi(-10));
+		let mut policy = ElementPolicy::new(LowPrecision::F16, RoundingMode::Stochastic, 7);
+		let samples: Vec<f64> = (0..20000).map(|_| policy.round(x)).collect();
+		assert!(samples.iter().all(|y| *y == below || *y == above));
+		// Unbiased: rounds up a quarter of the time
+		let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+		assert!((mean - x).abs() < 2f64.powi(-10) * 0.02);
+		// Representable values are never perturbed
+		assert_eq!(policy.round(0.5), 0.5);
+		// Reproducible from the seed
+		let mut replay = ElementPolicy::new(LowPrecision::F16, RoundingMode::Stochastic, 7);
+		let mat = Matrix::new(2, 1, vec![x, x]).unwrap();
+		let rounded = replay.round_matrix(&mat).unwrap();
+		assert_eq!(rounded.get_data(), &samples[..2]);
+	}
+}
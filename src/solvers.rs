@@ -0,0 +1,1264 @@
+use super::cancel::CancellationToken;
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+use super::operator::{FnOperator, LinearOperator};
+use super::reduction::dot;
+use std::ops::ControlFlow;
+
+// Header of a serialized CgState: magic bytes and format version
+const CG_STATE_MAGIC: &[u8; 6] = b"MWRCG\x01";
+
+/* Conjugate gradient iteration for A * x = b with A symmetric positive
+definite. The state holds everything needed to continue the iteration, so a
+long job can stop, save it with `to_bytes`, and resume later with more
+iterations or a tighter tolerance.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct CgState {
+	x: Vec<f64>,
+	// Residual b - A * x
+	r: Vec<f64>,
+	// Search direction
+	p: Vec<f64>,
+	// r^T * r
+	rr: f64,
+	b_norm: f64,
+	iterations: usize,
+}
+
+impl CgState {
+	// Starts from x = 0
+	pub fn new(a: &Matrix, b: &Matrix) -> Result<Self, MathMatrixError> {
+		check_square_system(a, b, "Conjugate gradient")?;
+		let rows = a.get_size().0;
+		let r = b.get_data();
+		let rr = dot(&r, &r);
+		return Ok(Self {
+			x: vec![0f64; rows],
+			p: r.clone(),
+			r,
+			rr,
+			b_norm: rr.sqrt(),
+			iterations: 0,
+		});
+	}
+
+	/* Runs up to `max_iterations` more iterations, stopping once
+	||r|| <= tolerance * ||b||. Returns whether that tolerance was reached.
+	*/
+	pub fn iterate(
+		&mut self,
+		a: &Matrix,
+		tolerance: f64,
+		max_iterations: usize,
+	) -> Result<bool, MathMatrixError> {
+		return self.iterate_monitored(
+			a,
+			tolerance,
+			max_iterations,
+			&CancellationToken::new(),
+			&mut |_, _| ControlFlow::Continue(()),
+		);
+	}
+
+	/* Calls on_iteration(iterations so far, relative residual) and checks the
+	token before every iteration. ControlFlow::Break stops early, keeping the
+	state valid for a later resume.
+	*/
+	fn iterate_monitored(
+		&mut self,
+		a: &Matrix,
+		tolerance: f64,
+		max_iterations: usize,
+		token: &CancellationToken,
+		on_iteration: &mut dyn FnMut(usize, f64) -> ControlFlow<()>,
+	) -> Result<bool, MathMatrixError> {
+		if a.get_size() != (self.x.len(), self.x.len()) {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Matrix does not match the solver state".to_owned(),
+			));
+		}
+		for _ in 0..max_iterations {
+			if self.is_converged(tolerance) {
+				return Ok(true);
+			}
+			if on_iteration(self.iterations, self.get_relative_residual()).is_break() {
+				return Ok(false);
+			}
+			token.check("Conjugate gradient")?;
+			let p_mat = Matrix::from_vec_unchecked(self.p.len(), 1, self.p.clone());
+			let ap = a.multiplied_by_matrix(&p_mat)?.into_vec();
+			let curvature = dot(&self.p, &ap);
+			if curvature <= 0.0 {
+				return Err(MathMatrixError::new(
+					NotPositiveDefinite,
+					"Conjugate gradient requires a positive definite matrix".to_owned(),
+				));
+			}
+			let alpha = self.rr / curvature;
+			for i in 0..self.x.len() {
+				self.x[i] += alpha * self.p[i];
+				self.r[i] -= alpha * ap[i];
+			}
+			let rr_next = dot(&self.r, &self.r);
+			let beta = rr_next / self.rr;
+			for i in 0..self.p.len() {
+				self.p[i] = self.r[i] + beta * self.p[i];
+			}
+			self.rr = rr_next;
+			self.iterations += 1;
+		}
+		return Ok(self.is_converged(tolerance));
+	}
+
+	pub fn is_converged(&self, tolerance: f64) -> bool {
+		return self.get_residual_norm() <= tolerance * self.b_norm;
+	}
+
+	pub fn get_solution(&self) -> Matrix {
+		return Matrix::from_vec_unchecked(self.x.len(), 1, self.x.clone());
+	}
+
+	// Total iterations since the state was created, across resumes
+	pub fn get_iterations(&self) -> usize {
+		return self.iterations;
+	}
+
+	// ||b - A * x|| as tracked by the recurrence
+	pub fn get_residual_norm(&self) -> f64 {
+		return self.rr.sqrt();
+	}
+
+	// ||b - A * x|| / ||b||, or the plain residual norm when b = 0
+	pub fn get_relative_residual(&self) -> f64 {
+		if self.b_norm == 0.0 {
+			return self.get_residual_norm();
+		}
+		return self.get_residual_norm() / self.b_norm;
+	}
+
+	// Little-endian binary encoding that round-trips exactly
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let n = self.x.len();
+		let mut bytes = Vec::with_capacity(CG_STATE_MAGIC.len() + 8 * (4 + 3 * n));
+		bytes.extend_from_slice(CG_STATE_MAGIC);
+		bytes.extend_from_slice(&(n as u64).to_le_bytes());
+		bytes.extend_from_slice(&(self.iterations as u64).to_le_bytes());
+		bytes.extend_from_slice(&self.rr.to_le_bytes());
+		bytes.extend_from_slice(&self.b_norm.to_le_bytes());
+		for value in self.x.iter().chain(self.r.iter()).chain(self.p.iter()) {
+			bytes.extend_from_slice(&value.to_le_bytes());
+		}
+		return bytes;
+	}
+
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self, MathMatrixError> {
+		let invalid = || {
+			return MathMatrixError::new(
+				FailedToInitialize,
+				"Invalid serialized conjugate gradient state".to_owned(),
+			);
+		};
+		let header = CG_STATE_MAGIC.len();
+		if bytes.len() < header + 32 || &bytes[..header] != CG_STATE_MAGIC {
+			return Err(invalid());
+		}
+		let word = |index: usize| {
+			let start = header + 8 * index;
+			let mut word = [0u8; 8];
+			word.copy_from_slice(&bytes[start..start + 8]);
+			return word;
+		};
+		let n = u64::from_le_bytes(word(0)) as usize;
+		if Some((bytes.len() - header - 32) / 8) != n.checked_mul(3)
+			|| !(bytes.len() - header).is_multiple_of(8)
+		{
+			return Err(invalid());
+		}
+		let vector = |offset: usize| -> Vec<f64> {
+			return (0..n)
+				.map(|i| f64::from_le_bytes(word(4 + offset * n + i)))
+				.collect();
+		};
+		return Ok(Self {
+			x: vector(0),
+			r: vector(1),
+			p: vector(2),
+			rr: f64::from_le_bytes(word(2)),
+			b_norm: f64::from_le_bytes(word(3)),
+			iterations: u64::from_le_bytes(word(1)) as usize,
+		});
+	}
+}
+
+// Outcome of an iterative solve of A * x = b
+#[derive(Debug, Clone, PartialEq)]
+pub struct IterativeResult {
+	pub solution: Matrix,
+	pub iterations: usize,
+	// Final ||b - A * x|| / ||b||
+	pub relative_residual: f64,
+	// Whether relative_residual <= tolerance was reached
+	pub converged: bool,
+}
+
+/* Conjugate gradient from x = 0 for A * x = b with A: NxN symmetric positive
+definite and b: Nx1. Each iteration costs one product with A, and in exact
+arithmetic the method terminates within N iterations; in practice it
+converges much sooner when the eigenvalues of A are clustered. Running out of
+iterations is not an error: check `converged` in the result. To pause and
+resume a long solve, use `CgState` directly.
+*/
+pub fn conjugate_gradient(
+	a: &Matrix,
+	b: &Matrix,
+	tolerance: f64,
+	max_iterations: usize,
+) -> Result<IterativeResult, MathMatrixError> {
+	return conjugate_gradient_monitored(
+		a,
+		b,
+		tolerance,
+		max_iterations,
+		&CancellationToken::new(),
+		&mut |_, _| ControlFlow::Continue(()),
+	);
+}
+
+// Checks the token before every iteration
+pub fn conjugate_gradient_with_cancellation(
+	a: &Matrix,
+	b: &Matrix,
+	tolerance: f64,
+	max_iterations: usize,
+	token: &CancellationToken,
+) -> Result<IterativeResult, MathMatrixError> {
+	return conjugate_gradient_monitored(a, b, tolerance, max_iterations, token, &mut |_, _| {
+		ControlFlow::Continue(())
+	});
+}
+
+/* Calls on_iteration(iteration, relative residual) before every iteration.
+Returning ControlFlow::Break stops early with the current iterate.
+*/
+pub fn conjugate_gradient_with_progress<F: FnMut(usize, f64) -> ControlFlow<()>>(
+	a: &Matrix,
+	b: &Matrix,
+	tolerance: f64,
+	max_iterations: usize,
+	mut on_iteration: F,
+) -> Result<IterativeResult, MathMatrixError> {
+	return conjugate_gradient_monitored(
+		a,
+		b,
+		tolerance,
+		max_iterations,
+		&CancellationToken::new(),
+		&mut on_iteration,
+	);
+}
+
+fn conjugate_gradient_monitored(
+	a: &Matrix,
+	b: &Matrix,
+	tolerance: f64,
+	max_iterations: usize,
+	token: &CancellationToken,
+	on_iteration: &mut dyn FnMut(usize, f64) -> ControlFlow<()>,
+) -> Result<IterativeResult, MathMatrixError> {
+	let mut state = CgState::new(a, b)?;
+	let converged = state.iterate_monitored(a, tolerance, max_iterations, token, on_iteration)?;
+	return Ok(warn_unconverged(IterativeResult {
+		solution: state.get_solution(),
+		iterations: state.get_iterations(),
+		relative_residual: state.get_relative_residual(),
+		converged,
+	}));
+}
+
+/* Restarted GMRES(restart) from x = 0 for A * x = b with A: NxN
+nonsingular, not necessarily symmetric, and b: Nx1. Each cycle builds an
+orthonormal Krylov basis of up to `restart` vectors with Arnoldi and picks the
+x in it minimizing ||b - A * x||; the basis is then discarded and the next
+cycle starts from the new residual. Larger `restart` values converge in fewer
+iterations at O(N * restart) memory. `max_iterations` counts products with A.
+*/
+pub fn gmres(
+	a: &Matrix,
+	b: &Matrix,
+	restart: usize,
+	tolerance: f64,
+	max_iterations: usize,
+) -> Result<IterativeResult, MathMatrixError> {
+	return gmres_with_progress(a, b, restart, tolerance, max_iterations, |_, _| {
+		ControlFlow::Continue(())
+	});
+}
+
+// Checks the token before every iteration
+pub fn gmres_with_cancellation(
+	a: &Matrix,
+	b: &Matrix,
+	restart: usize,
+	tolerance: f64,
+	max_iterations: usize,
+	token: &CancellationToken,
+) -> Result<IterativeResult, MathMatrixError> {
+	return gmres_monitored(
+		a,
+		b,
+		restart,
+		tolerance,
+		max_iterations,
+		token,
+		&mut |_, _| ControlFlow::Continue(()),
+	);
+}
+
+/* Calls on_iteration(iteration, relative residual) before every iteration,
+where the residual is the one GMRES tracks without forming A * x. Returning
+ControlFlow::Break stops early with the current iterate.
+*/
+pub fn gmres_with_progress<F: FnMut(usize, f64) -> ControlFlow<()>>(
+	a: &Matrix,
+	b: &Matrix,
+	restart: usize,
+	tolerance: f64,
+	max_iterations: usize,
+	on_iteration: F,
+) -> Result<IterativeResult, MathMatrixError> {
+	return gmres_operator_with_progress(a, b, restart, tolerance, max_iterations, on_iteration);
+}
+
+// Matrix-free GMRES: A is only accessed through its products with vectors
+pub fn gmres_operator(
+	a: &dyn LinearOperator,
+	b: &Matrix,
+	restart: usize,
+	tolerance: f64,
+	max_iterations: usize,
+) -> Result<IterativeResult, MathMatrixError> {
+	return gmres_operator_with_progress(a, b, restart, tolerance, max_iterations, |_, _| {
+		ControlFlow::Continue(())
+	});
+}
+
+pub fn gmres_operator_with_progress<F: FnMut(usize, f64) -> ControlFlow<()>>(
+	a: &dyn LinearOperator,
+	b: &Matrix,
+	restart: usize,
+	tolerance: f64,
+	max_iterations: usize,
+	mut on_iteration: F,
+) -> Result<IterativeResult, MathMatrixError> {
+	return gmres_monitored(
+		a,
+		b,
+		restart,
+		tolerance,
+		max_iterations,
+		&CancellationToken::new(),
+		&mut on_iteration,
+	);
+}
+
+fn gmres_monitored(
+	a: &dyn LinearOperator,
+	b: &Matrix,
+	restart: usize,
+	tolerance: f64,
+	max_iterations: usize,
+	token: &CancellationToken,
+	on_iteration: &mut dyn FnMut(usize, f64) -> ControlFlow<()>,
+) -> Result<IterativeResult, MathMatrixError> {
+	check_square_system(a, b, "GMRES")?;
+	if restart == 0 {
+		return Err(MathMatrixError::new(
+			OperationNotPermitted,
+			"GMRES requires a positive restart length".to_owned(),
+		));
+	}
+	let n = b.get_size().0;
+	let b_data = b.get_data();
+	let b_norm = dot(&b_data, &b_data).sqrt();
+	let scale = if b_norm == 0.0 { 1.0 } else { b_norm };
+	let mut x = vec![0f64; n];
+	let mut iterations = 0;
+	let mut stopped = false;
+	let mut residual = b_data.clone();
+	let mut residual_norm = b_norm;
+	while residual_norm > tolerance * b_norm && iterations < max_iterations && !stopped {
+		// Arnoldi basis V, Hessenberg H reduced to triangular by Givens rotations (c, s)
+		let mut basis = vec![residual
+			.iter()
+			.map(|v| v / residual_norm)
+			.collect::<Vec<f64>>()];
+		let mut h: Vec<Vec<f64>> = Vec::with_capacity(restart);
+		let mut rotations: Vec<(f64, f64)> = Vec::with_capacity(restart);
+		let mut g = vec![residual_norm];
+		while h.len() < restart && iterations < max_iterations {
+			let estimate = g[h.len()].abs();
+			if estimate <= tolerance * b_norm {
+				break;
+			}
+			if on_iteration(iterations, estimate / scale).is_break() {
+				stopped = true;
+				break;
+			}
+			token.check("GMRES")?;
+			let j = h.len();
+			let v_mat = Matrix::from_vec_unchecked(n, 1, basis[j].clone());
+			let mut w = a.apply(&v_mat)?.into_vec();
+			let mut column = vec![0f64; j + 2];
+			for i in 0..=j {
+				column[i] = dot(&w, &basis[i]);
+				for k in 0..n {
+					w[k] -= column[i] * basis[i][k];
+				}
+			}
+			column[j + 1] = dot(&w, &w).sqrt();
+			for (i, (c, s)) in rotations.iter().enumerate() {
+				let (top, bottom) = (column[i], column[i + 1]);
+				column[i] = c * top + s * bottom;
+				column[i + 1] = -s * top + c * bottom;
+			}
+			let radius = column[j].hypot(column[j + 1]);
+			let (c, s) = if radius == 0.0 {
+				(1.0, 0.0)
+			} else {
+				(column[j] / radius, column[j + 1] / radius)
+			};
+			g.push(-s * g[j]);
+			g[j] *= c;
+			let next_norm = column[j + 1];
+			column[j] = radius;
+			column.truncate(j + 1);
+			rotations.push((c, s));
+			h.push(column);
+			iterations += 1;
+			if next_norm == 0.0 {
+				// Happy breakdown: the Krylov space is invariant and holds the solution
+				break;
+			}
+			basis.push(w.iter().map(|v| v / next_norm).collect());
+		}
+		// Back substitution for y in H * y = g, then x += V * y
+		let k = h.len();
+		let mut y = vec![0f64; k];
+		for i in (0..k).rev() {
+			let mut elem = g[i];
+			for j in (i + 1)..k {
+				elem -= h[j][i] * y[j];
+			}
+			if h[i][i] == 0.0 {
+				return Err(MathMatrixError::new(
+					FailedToDecompose,
+					"GMRES breakdown: the matrix is singular".to_owned(),
+				));
+			}
+			y[i] = elem / h[i][i];
+		}
+		for j in 0..k {
+			for i in 0..n {
+				x[i] += y[j] * basis[j][i];
+			}
+		}
+		let ax = mat_vec(a, &x)?;
+		residual = b_data.iter().zip(ax.iter()).map(|(b, ax)| b - ax).collect();
+		residual_norm = dot(&residual, &residual).sqrt();
+		if k == 0 {
+			break;
+		}
+	}
+	return Ok(warn_unconverged(IterativeResult {
+		solution: Matrix::from_vec_unchecked(n, 1, x),
+		iterations,
+		relative_residual: residual_norm / scale,
+		converged: residual_norm <= tolerance * b_norm,
+	}));
+}
+
+// Settings shared by the stationary methods `jacobi`, `gauss_seidel` and `sor`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StationaryConfig {
+	// Stop once ||b - A * x|| <= tolerance * ||b||
+	pub tolerance: f64,
+	// Maximum number of sweeps
+	pub max_iterations: usize,
+	/* omega: the new value of each entry is (1 - omega) * old + omega * update.
+	Damps Jacobi for omega < 1, over-relaxes SOR for 1 < omega < 2, and is
+	ignored by Gauss-Seidel.
+	*/
+	pub relaxation: f64,
+}
+
+impl Default for StationaryConfig {
+	fn default() -> Self {
+		return Self {
+			tolerance: 1e-10,
+			max_iterations: 1000,
+			relaxation: 1.0,
+		};
+	}
+}
+
+/* Stationary iterations from x = 0 for A * x = b with A: NxN and b: Nx1.
+Each sweep solves equation i for x_i:
+- Jacobi: from the previous sweep only, so all entries update independently
+- Gauss-Seidel: using the entries already updated in this sweep
+- SOR: Gauss-Seidel with relaxation
+They converge for strictly diagonally dominant A, and Gauss-Seidel and SOR
+with 0 < omega < 2 also for symmetric positive definite A.
+*/
+pub fn jacobi(
+	a: &Matrix,
+	b: &Matrix,
+	config: &StationaryConfig,
+) -> Result<IterativeResult, MathMatrixError> {
+	return jacobi_with_progress(a, b, config, |_, _| ControlFlow::Continue(()));
+}
+
+pub fn gauss_seidel(
+	a: &Matrix,
+	b: &Matrix,
+	config: &StationaryConfig,
+) -> Result<IterativeResult, MathMatrixError> {
+	return gauss_seidel_with_progress(a, b, config, |_, _| ControlFlow::Continue(()));
+}
+
+pub fn sor(
+	a: &Matrix,
+	b: &Matrix,
+	config: &StationaryConfig,
+) -> Result<IterativeResult, MathMatrixError> {
+	return sor_with_progress(a, b, config, |_, _| ControlFlow::Continue(()));
+}
+
+/* The `_with_progress` variants call on_iteration(sweep, relative residual)
+before every sweep. Returning ControlFlow::Break stops early with the
+current iterate.
+*/
+pub fn jacobi_with_progress<F: FnMut(usize, f64) -> ControlFlow<()>>(
+	a: &Matrix,
+	b: &Matrix,
+	config: &StationaryConfig,
+	mut on_iteration: F,
+) -> Result<IterativeResult, MathMatrixError> {
+	return stationary(a, b, config, config.relaxation, false, &mut on_iteration);
+}
+
+pub fn gauss_seidel_with_progress<F: FnMut(usize, f64) -> ControlFlow<()>>(
+	a: &Matrix,
+	b: &Matrix,
+	config: &StationaryConfig,
+	mut on_iteration: F,
+) -> Result<IterativeResult, MathMatrixError> {
+	return stationary(a, b, config, 1.0, true, &mut on_iteration);
+}
+
+pub fn sor_with_progress<F: FnMut(usize, f64) -> ControlFlow<()>>(
+	a: &Matrix,
+	b: &Matrix,
+	config: &StationaryConfig,
+	mut on_iteration: F,
+) -> Result<IterativeResult, MathMatrixError> {
+	if !(config.relaxation > 0.0 && config.relaxation < 2.0) {
+		return Err(MathMatrixError::new(
+			OperationNotPermitted,
+			format!("SOR relaxation {} not in (0, 2)", config.relaxation),
+		));
+	}
+	return stationary(a, b, config, config.relaxation, true, &mut on_iteration);
+}
+
+fn stationary(
+	a: &Matrix,
+	b: &Matrix,
+	config: &StationaryConfig,
+	relaxation: f64,
+	in_place: bool,
+	on_iteration: &mut dyn FnMut(usize, f64) -> ControlFlow<()>,
+) -> Result<IterativeResult, MathMatrixError> {
+	check_square_system(a, b, "Stationary iteration")?;
+	let n = b.get_size().0;
+	let (a_data, b_data) = (a.get_data(), b.get_data());
+	if (0..n).any(|i| a_data[i * n + i] == 0.0) {
+		return Err(MathMatrixError::new(
+			FailedToDecompose,
+			"Stationary iteration requires a nonzero diagonal".to_owned(),
+		));
+	}
+	let b_norm = dot(&b_data, &b_data).sqrt();
+	let scale = if b_norm == 0.0 { 1.0 } else { b_norm };
+	let residual_norm = |x: &[f64]| -> f64 {
+		let mut norm = 0.0;
+		for i in 0..n {
+			let ax: f64 = (0..n).map(|j| a_data[i + j * n] * x[j]).sum();
+			norm += (b_data[i] - ax).powi(2);
+		}
+		return norm.sqrt();
+	};
+	let mut x = vec![0f64; n];
+	let mut iterations = 0;
+	let mut residual = b_norm;
+	while residual > config.tolerance * b_norm && iterations < config.max_iterations {
+		if on_iteration(iterations, residual / scale).is_break() {
+			break;
+		}
+		let previous = x.clone();
+		for i in 0..n {
+			let source = if in_place { &x } else { &previous };
+			let mut sum = b_data[i];
+			for j in 0..n {
+				if j != i {
+					sum -= a_data[i + j * n] * source[j];
+				}
+			}
+			x[i] = (1.0 - relaxation) * x[i] + relaxation * sum / a_data[i * n + i];
+		}
+		iterations += 1;
+		residual = residual_norm(&x);
+	}
+	return Ok(warn_unconverged(IterativeResult {
+		solution: Matrix::from_vec_unchecked(n, 1, x),
+		iterations,
+		relative_residual: residual / scale,
+		converged: residual <= config.tolerance * b_norm,
+	}));
+}
+
+/* Approximation M of A that is cheap to invert. Iterative solvers accepting
+a preconditioner work on M^-1 * A, which converges in fewer iterations the
+closer M is to A.
+*/
+pub trait Preconditioner {
+	// M^-1 * r for an Nx1 r
+	fn apply(&self, r: &Matrix) -> Result<Matrix, MathMatrixError>;
+}
+
+// M = I: no preconditioning
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct IdentityPreconditioner;
+
+impl Preconditioner for IdentityPreconditioner {
+	fn apply(&self, r: &Matrix) -> Result<Matrix, MathMatrixError> {
+		return Ok(r.clone());
+	}
+}
+
+// M = diag(A)
+#[derive(Debug, Clone, PartialEq)]
+pub struct JacobiPreconditioner {
+	inverse_diagonal: Vec<f64>,
+}
+
+impl JacobiPreconditioner {
+	pub fn new(a: &Matrix) -> Result<Self, MathMatrixError> {
+		let (rows, cols) = a.get_size();
+		if rows != cols {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Jacobi preconditioner requires a square NxN matrix".to_owned(),
+			));
+		}
+		let mut inverse_diagonal = Vec::with_capacity(rows);
+		for i in 0..rows {
+			let diagonal = a.get_value(i, i)?;
+			if diagonal == 0.0 {
+				return Err(MathMatrixError::new(
+					FailedToDecompose,
+					format!("Zero diagonal entry at row {}", i),
+				));
+			}
+			inverse_diagonal.push(1.0 / diagonal);
+		}
+		return Ok(Self { inverse_diagonal });
+	}
+}
+
+impl Preconditioner for JacobiPreconditioner {
+	fn apply(&self, r: &Matrix) -> Result<Matrix, MathMatrixError> {
+		let n = self.inverse_diagonal.len();
+		if r.get_size() != (n, 1) {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				format!("Preconditioner expects a {}x1 vector", n),
+			));
+		}
+		let data = r.get_data();
+		return Ok(Matrix::from_vec_unchecked(
+			n,
+			1,
+			data.iter()
+				.zip(self.inverse_diagonal.iter())
+				.map(|(v, d)| v * d)
+				.collect(),
+		));
+	}
+}
+
+/* Incomplete LU factorization ILU(0): M = L * U with L unit lower and U upper
+triangular, computed by Gaussian elimination without pivoting that drops
+every fill-in outside the nonzero pattern of A. On a dense A nothing is
+dropped and M = A.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ilu0 {
+	// L below the diagonal and U on and above it, row-major
+	factors: Vec<f64>,
+	size: usize,
+}
+
+impl Ilu0 {
+	pub fn new(a: &Matrix) -> Result<Self, MathMatrixError> {
+		let (n, cols) = a.get_size();
+		if n != cols {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"ILU(0) requires a square NxN matrix".to_owned(),
+			));
+		}
+		let mut lu = vec![0f64; n * n];
+		for i in 0..n {
+			for j in 0..n {
+				lu[i * n + j] = a.get_value(i, j)?;
+			}
+		}
+		for i in 1..n {
+			for k in 0..i {
+				if lu[i * n + k] == 0.0 {
+					continue;
+				}
+				if lu[k * n + k] == 0.0 {
+					return Err(MathMatrixError::new(
+						FailedToDecompose,
+						format!("Zero pivot at row {}", k),
+					));
+				}
+				lu[i * n + k] /= lu[k * n + k];
+				for j in (k + 1)..n {
+					// Only entries in the pattern of A are updated
+					if lu[i * n + j] != 0.0 {
+						lu[i * n + j] -= lu[i * n + k] * lu[k * n + j];
+					}
+				}
+			}
+		}
+		if n > 0 && lu[n * n - 1] == 0.0 {
+			return Err(MathMatrixError::new(
+				FailedToDecompose,
+				format!("Zero pivot at row {}", n - 1),
+			));
+		}
+		return Ok(Self {
+			factors: lu,
+			size: n,
+		});
+	}
+}
+
+impl Preconditioner for Ilu0 {
+	fn apply(&self, r: &Matrix) -> Result<Matrix, MathMatrixError> {
+		let n = self.size;
+		if r.get_size() != (n, 1) {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				format!("Preconditioner expects a {}x1 vector", n),
+			));
+		}
+		let lu = &self.factors;
+		let mut x = r.get_data();
+		for i in 0..n {
+			for j in 0..i {
+				x[i] -= lu[i * n + j] * x[j];
+			}
+		}
+		for i in (0..n).rev() {
+			for j in (i + 1)..n {
+				x[i] -= lu[i * n + j] * x[j];
+			}
+			x[i] /= lu[i * n + i];
+		}
+		return Ok(Matrix::from_vec_unchecked(n, 1, x));
+	}
+}
+
+/* Preconditioned conjugate gradient: as `conjugate_gradient`, for M
+symmetric positive definite too
+*/
+pub fn conjugate_gradient_preconditioned(
+	a: &Matrix,
+	b: &Matrix,
+	preconditioner: &dyn Preconditioner,
+	tolerance: f64,
+	max_iterations: usize,
+) -> Result<IterativeResult, MathMatrixError> {
+	return conjugate_gradient_operator(a, b, preconditioner, tolerance, max_iterations);
+}
+
+// Matrix-free preconditioned conjugate gradient
+pub fn conjugate_gradient_operator(
+	a: &dyn LinearOperator,
+	b: &Matrix,
+	preconditioner: &dyn Preconditioner,
+	tolerance: f64,
+	max_iterations: usize,
+) -> Result<IterativeResult, MathMatrixError> {
+	check_square_system(a, b, "Conjugate gradient")?;
+	let n = b.get_size().0;
+	let mut r = b.get_data();
+	let b_norm = dot(&r, &r).sqrt();
+	let scale = if b_norm == 0.0 { 1.0 } else { b_norm };
+	let mut x = vec![0f64; n];
+	let mut z = precondition(preconditioner, &r)?;
+	let mut p = z.clone();
+	let mut rz = dot(&r, &z);
+	let mut iterations = 0;
+	while dot(&r, &r).sqrt() > tolerance * b_norm && iterations < max_iterations {
+		let ap = mat_vec(a, &p)?;
+		let curvature = dot(&p, &ap);
+		if curvature <= 0.0 {
+			return Err(MathMatrixError::new(
+				NotPositiveDefinite,
+				"Conjugate gradient requires a positive definite matrix".to_owned(),
+			));
+		}
+		let alpha = rz / curvature;
+		for i in 0..n {
+			x[i] += alpha * p[i];
+			r[i] -= alpha * ap[i];
+		}
+		z = precondition(preconditioner, &r)?;
+		let rz_next = dot(&r, &z);
+		for i in 0..n {
+			p[i] = z[i] + rz_next / rz * p[i];
+		}
+		rz = rz_next;
+		iterations += 1;
+	}
+	let residual_norm = dot(&r, &r).sqrt();
+	return Ok(warn_unconverged(IterativeResult {
+		solution: Matrix::from_vec_unchecked(n, 1, x),
+		iterations,
+		relative_residual: residual_norm / scale,
+		converged: residual_norm <= tolerance * b_norm,
+	}));
+}
+
+/* Right-preconditioned GMRES: solves A * M^-1 * u = b, then x = M^-1 * u.
+The residual it minimizes is still the one of the original system.
+*/
+pub fn gmres_preconditioned(
+	a: &Matrix,
+	b: &Matrix,
+	preconditioner: &dyn Preconditioner,
+	restart: usize,
+	tolerance: f64,
+	max_iterations: usize,
+) -> Result<IterativeResult, MathMatrixError> {
+	let (rows, cols) = a.get_size();
+	let preconditioned = FnOperator::new(rows, cols, |v: &Matrix| {
+		return a.multiplied_by_matrix(&preconditioner.apply(v)?);
+	});
+	let mut result = gmres_monitored(
+		&preconditioned,
+		b,
+		restart,
+		tolerance,
+		max_iterations,
+		&CancellationToken::new(),
+		&mut |_, _| ControlFlow::Continue(()),
+	)?;
+	result.solution = preconditioner.apply(&result.solution)?;
+	return Ok(result);
+}
+
+/* BiCGSTAB (van der Vorst) from x = 0 for A * x = b with A: NxN nonsingular
+and b: Nx1. Like GMRES it handles nonsymmetric A, but with constant memory:
+two products with A and two preconditioner applications per iteration.
+Convergence is not monotone, and the method can break down (r_hat^T * r = 0
+or omega = 0), which is reported as an error.
+*/
+pub fn bicgstab(
+	a: &Matrix,
+	b: &Matrix,
+	tolerance: f64,
+	max_iterations: usize,
+) -> Result<IterativeResult, MathMatrixError> {
+	return bicgstab_preconditioned(a, b, &IdentityPreconditioner, tolerance, max_iterations);
+}
+
+pub fn bicgstab_preconditioned(
+	a: &Matrix,
+	b: &Matrix,
+	preconditioner: &dyn Preconditioner,
+	tolerance: f64,
+	max_iterations: usize,
+) -> Result<IterativeResult, MathMatrixError> {
+	return bicgstab_with_progress(a, b, preconditioner, tolerance, max_iterations, |_, _| {
+		ControlFlow::Continue(())
+	});
+}
+
+/* Calls on_iteration(iteration, relative residual) before every iteration.
+Returning ControlFlow::Break stops early with the current iterate.
+*/
+pub fn bicgstab_with_progress<F: FnMut(usize, f64) -> ControlFlow<()>>(
+	a: &Matrix,
+	b: &Matrix,
+	preconditioner: &dyn Preconditioner,
+	tolerance: f64,
+	max_iterations: usize,
+	mut on_iteration: F,
+) -> Result<IterativeResult, MathMatrixError> {
+	return bicgstab_monitored(
+		a,
+		b,
+		preconditioner,
+		tolerance,
+		max_iterations,
+		&mut on_iteration,
+	);
+}
+
+// Matrix-free preconditioned BiCGSTAB
+pub fn bicgstab_operator(
+	a: &dyn LinearOperator,
+	b: &Matrix,
+	preconditioner: &dyn Preconditioner,
+	tolerance: f64,
+	max_iterations: usize,
+) -> Result<IterativeResult, MathMatrixError> {
+	return bicgstab_monitored(
+		a,
+		b,
+		preconditioner,
+		tolerance,
+		max_iterations,
+		&mut |_, _| ControlFlow::Continue(()),
+	);
+}
+
+fn bicgstab_monitored(
+	a: &dyn LinearOperator,
+	b: &Matrix,
+	preconditioner: &dyn Preconditioner,
+	tolerance: f64,
+	max_iterations: usize,
+	on_iteration: &mut dyn FnMut(usize, f64) -> ControlFlow<()>,
+) -> Result<IterativeResult, MathMatrixError> {
+	check_square_system(a, b, "BiCGSTAB")?;
+	let n = b.get_size().0;
+	let mut r = b.get_data();
+	let b_norm = dot(&r, &r).sqrt();
+	let scale = if b_norm == 0.0 { 1.0 } else { b_norm };
+	let r_hat = r.clone();
+	let mut x = vec![0f64; n];
+	let (mut p, mut v) = (vec![0f64; n], vec![0f64; n]);
+	let (mut rho, mut alpha, mut omega) = (1.0, 1.0, 1.0);
+	let mut iterations = 0;
+	let breakdown = || {
+		return MathMatrixError::new(FailedToConverge, "BiCGSTAB breakdown".to_owned());
+	};
+	let mut residual_norm = b_norm;
+	while residual_norm > tolerance * b_norm && iterations < max_iterations {
+		if on_iteration(iterations, residual_norm / scale).is_break() {
+			break;
+		}
+		let rho_next = dot(&r_hat, &r);
+		if rho_next == 0.0 {
+			return Err(breakdown());
+		}
+		let beta = (rho_next / rho) * (alpha / omega);
+		for i in 0..n {
+			p[i] = r[i] + beta * (p[i] - omega * v[i]);
+		}
+		let y = precondition(preconditioner, &p)?;
+		v = mat_vec(a, &y)?;
+		alpha = rho_next / dot(&r_hat, &v);
+		let s: Vec<f64> = (0..n).map(|i| r[i] - alpha * v[i]).collect();
+		iterations += 1;
+		if dot(&s, &s).sqrt() <= tolerance * b_norm {
+			for i in 0..n {
+				x[i] += alpha * y[i];
+			}
+			r = s;
+			residual_norm = dot(&r, &r).sqrt();
+			break;
+		}
+		let z = precondition(preconditioner, &s)?;
+		let t = mat_vec(a, &z)?;
+		omega = dot(&t, &s) / dot(&t, &t);
+		if omega == 0.0 || omega.is_nan() {
+			return Err(breakdown());
+		}
+		for i in 0..n {
+			x[i] += alpha * y[i] + omega * z[i];
+			r[i] = s[i] - omega * t[i];
+		}
+		rho = rho_next;
+		residual_norm = dot(&r, &r).sqrt();
+	}
+	return Ok(warn_unconverged(IterativeResult {
+		solution: Matrix::from_vec_unchecked(n, 1, x),
+		iterations,
+		relative_residual: residual_norm / scale,
+		converged: residual_norm <= tolerance * b_norm,
+	}));
+}
+
+fn warn_unconverged(result: IterativeResult) -> IterativeResult {
+	if !result.converged {
+		numerical_warning!(
+			"Iterative solver did not converge in {} iterations: relative residual {:e}",
+			result.iterations,
+			result.relative_residual
+		);
+	}
+	return result;
+}
+
+fn precondition(
+	preconditioner: &dyn Preconditioner,
+	r: &[f64],
+) -> Result<Vec<f64>, MathMatrixError> {
+	let r_mat = Matrix::from_vec_unchecked(r.len(), 1, r.to_vec());
+	return Ok(preconditioner.apply(&r_mat)?.into_vec());
+}
+
+fn mat_vec(a: &dyn LinearOperator, x: &[f64]) -> Result<Vec<f64>, MathMatrixError> {
+	let x_mat = Matrix::from_vec_unchecked(x.len(), 1, x.to_vec());
+	return Ok(a.apply(&x_mat)?.into_vec());
+}
+
+fn check_square_system(
+	a: &dyn LinearOperator,
+	b: &Matrix,
+	solver: &str,
+) -> Result<(), MathMatrixError> {
+	let (rows, cols) = a.get_size();
+	if rows != cols || b.get_size() != (rows, 1) {
+		return Err(MathMatrixError::new(
+			SizeMismatch,
+			format!(
+				"{} requires a square NxN matrix and an Nx1 right-hand side",
+				solver
+			),
+		));
+	}
+	return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::multigrid::laplacian_1d;
+
+	#[test]
+	fn test_cg_converges() {
+		let n = 15;
+		let a = laplacian_1d(n).unwrap();
+		let b = Matrix::new(n, 1, (0..n).map(|i| i as f64).collect()).unwrap();
+		let mut state = CgState::new(&a, &b).unwrap();
+		assert!(state.iterate(&a, 1e-12, 100).unwrap());
+		// Exact arithmetic would need at most n iterations
+		assert!(state.get_iterations() <= n + 2);
+		let expected = a.solve(&b).unwrap();
+		assert!(state.get_solution().approx_eq(&expected, 1e-9));
+	}
+
+	#[test]
+	fn test_cg_resume_from_bytes() {
+		let n = 15;
+		let a = laplacian_1d(n).unwrap();
+		let b = Matrix::new(n, 1, vec![1.0; n]).unwrap();
+		let mut uninterrupted = CgState::new(&a, &b).unwrap();
+		uninterrupted.iterate(&a, 1e-12, 100).unwrap();
+
+		let mut state = CgState::new(&a, &b).unwrap();
+		assert!(!state.iterate(&a, 1e-12, 3).unwrap());
+		let bytes = state.to_bytes();
+		let mut restored = CgState::from_bytes(&bytes).unwrap();
+		assert_eq!(restored, state);
+		assert!(restored.iterate(&a, 1e-12, 100).unwrap());
+		assert_eq!(restored, uninterrupted);
+
+		assert!(CgState::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+		assert!(CgState::from_bytes(b"not a state").is_err());
+	}
+
+	#[test]
+	fn test_conjugate_gradient() {
+		let n = 31;
+		let a = laplacian_1d(n).unwrap();
+		let b = Matrix::new(n, 1, vec![1.0; n]).unwrap();
+		let result = conjugate_gradient(&a, &b, 1e-10, 100).unwrap();
+		assert!(result.converged);
+		assert!(result.relative_residual <= 1e-10);
+		assert!(result.solution.approx_eq(&a.solve(&b).unwrap(), 1e-8));
+
+		let result = conjugate_gradient(&a, &b, 1e-10, 3).unwrap();
+		assert!(!result.converged);
+		assert_eq!(result.iterations, 3);
+
+		let mut residuals = Vec::new();
+		let result = conjugate_gradient_with_progress(&a, &b, 1e-10, 100, |iteration, residual| {
+			residuals.push(residual);
+			if iteration == 5 {
+				return ControlFlow::Break(());
+			}
+			return ControlFlow::Continue(());
+		})
+		.unwrap();
+		assert!(!result.converged);
+		assert_eq!(result.iterations, 5);
+		assert_eq!(residuals.len(), 6);
+		assert_eq!(residuals[0], 1.0);
+
+		let token = CancellationToken::new();
+		token.cancel();
+		assert!(conjugate_gradient_with_cancellation(&a, &b, 1e-10, 100, &token).is_err());
+	}
+
+	#[test]
+	fn test_gmres() {
+		// Nonsymmetric convection-diffusion-like tridiagonal matrix
+		let n = 20;
+		let mut a = Matrix::zeros(n, n).unwrap();
+		for i in 0..n {
+			a.set_value(i, i, 4.0).unwrap();
+			if i > 0 {
+				a.set_value(i, i - 1, -2.0).unwrap();
+			}
+			if i + 1 < n {
+				a.set_value(i, i + 1, -1.0).unwrap();
+			}
+		}
+		let b = Matrix::new(n, 1, (0..n).map(|i| (i % 3) as f64).collect()).unwrap();
+		let expected = a.solve(&b).unwrap();
+		let result = gmres(&a, &b, 5, 1e-10, 200).unwrap();
+		assert!(result.converged);
+		assert!(result.relative_residual <= 1e-10);
+		assert!(result.solution.approx_eq(&expected, 1e-8));
+
+		// Without restarts GMRES finishes within N iterations
+		let full = gmres(&a, &b, n, 1e-10, n).unwrap();
+		assert!(full.converged);
+
+		// Matrix-free: the same tridiagonal product without storing A
+		let apply = |x: &Matrix| {
+			let x = x.get_data();
+			let ax = (0..n).map(|i| {
+				let left = if i > 0 { -2.0 * x[i - 1] } else { 0.0 };
+				let right = if i + 1 < n { -x[i + 1] } else { 0.0 };
+				return 4.0 * x[i] + left + right;
+			});
+			return Matrix::new(n, 1, ax.collect());
+		};
+		let mut residuals = Vec::new();
+		let operator = FnOperator::new(n, n, apply);
+		let result = gmres_operator_with_progress(&operator, &b, 5, 1e-10, 200, |_, residual| {
+			residuals.push(residual);
+			return ControlFlow::Continue(());
+		})
+		.unwrap();
+		assert!(result.solution.approx_eq(&expected, 1e-8));
+		assert_eq!(residuals.len(), result.iterations);
+		assert!(residuals.windows(2).all(|pair| pair[1] <= pair[0] + 1e-12));
+
+		let token = CancellationToken::new();
+		token.cancel();
+		assert!(gmres_with_cancellation(&a, &b, 5, 1e-10, 200, &token).is_err());
+		assert!(gmres(&a, &a, 5, 1e-10, 200).is_err());
+	}
+
+	#[test]
+	fn test_stationary_methods() {
+		// Strictly diagonally dominant
+		let a = Matrix::new(3, 3, vec![10.0, 2.0, 1.0, -1.0, 8.0, 2.0, 2.0, -1.0, 5.0]).unwrap();
+		let b = Matrix::new(3, 1, vec![7.0, -4.0, 6.0]).unwrap();
+		let expected = a.solve(&b).unwrap();
+		let config = StationaryConfig::default();
+		let jacobi_result = jacobi(&a, &b, &config).unwrap();
+		let gauss_seidel_result = gauss_seidel(&a, &b, &config).unwrap();
+		for result in [&jacobi_result, &gauss_seidel_result].iter() {
+			assert!(result.converged);
+			assert!(result.solution.approx_eq(&expected, 1e-9));
+		}
+		assert!(gauss_seidel_result.iterations < jacobi_result.iterations);
+
+		let over_relaxed = StationaryConfig {
+			relaxation: 1.1,
+			..config
+		};
+		assert!(sor(&a, &b, &over_relaxed)
+			.unwrap()
+			.solution
+			.approx_eq(&expected, 1e-9));
+		let invalid = StationaryConfig {
+			relaxation: 2.0,
+			..config
+		};
+		assert!(sor(&a, &b, &invalid).is_err());
+
+		let mut sweeps = 0;
+		let result = jacobi_with_progress(&a, &b, &config, |sweep, _| {
+			sweeps = sweep + 1;
+			return if sweep == 2 {
+				ControlFlow::Break(())
+			} else {
+				ControlFlow::Continue(())
+			};
+		})
+		.unwrap();
+		assert_eq!((sweeps, result.iterations, result.converged), (3, 2, false));
+		let zero_diagonal = Matrix::new(2, 2, vec![0.0, 1.0, 1.0, 0.0]).unwrap();
+		let b = Matrix::new(2, 1, vec![1.0, 1.0]).unwrap();
+		assert!(jacobi(&zero_diagonal, &b, &config).is_err());
+	}
+
+	#[test]
+	fn test_bicgstab_and_preconditioners() {
+		// Nonsymmetric with a widely varying diagonal
+		let n = 20;
+		let mut a = Matrix::zeros(n, n).unwrap();
+		for i in 0..n {
+			a.set_value(i, i, 2.0 + (i * i) as f64).unwrap();
+			if i > 0 {
+				a.set_value(i, i - 1, -1.5).unwrap();
+			}
+			if i + 1 < n {
+				a.set_value(i, i + 1, -0.5).unwrap();
+			}
+		}
+		let b = Matrix::new(n, 1, (0..n).map(|i| 1.0 + i as f64).collect()).unwrap();
+		let expected = a.solve(&b).unwrap();
+
+		let plain = bicgstab(&a, &b, 1e-10, 200).unwrap();
+		assert!(plain.converged);
+		assert!(plain.solution.approx_eq(&expected, 1e-8));
+		let jacobi = JacobiPreconditioner::new(&a).unwrap();
+		let preconditioned = bicgstab_preconditioned(&a, &b, &jacobi, 1e-10, 200).unwrap();
+		assert!(preconditioned.solution.approx_eq(&expected, 1e-8));
+		assert!(preconditioned.iterations < plain.iterations);
+
+		// On a tridiagonal matrix ILU(0) keeps all of the fill-in: M = A
+		let ilu = Ilu0::new(&a).unwrap();
+		assert!(ilu.apply(&b).unwrap().approx_eq(&expected, 1e-10));
+		let result = bicgstab_preconditioned(&a, &b, &ilu, 1e-10, 200).unwrap();
+		assert!(result.converged && result.iterations == 1);
+		let result = gmres_preconditioned(&a, &b, &jacobi, 5, 1e-10, 200).unwrap();
+		assert!(result.converged);
+		assert!(result.solution.approx_eq(&expected, 1e-8));
+
+		let spd = laplacian_1d(n).unwrap();
+		let plain = conjugate_gradient(&spd, &b, 1e-10, 200).unwrap();
+		let jacobi = JacobiPreconditioner::new(&spd).unwrap();
+		let result = conjugate_gradient_preconditioned(&spd, &b, &jacobi, 1e-10, 200).unwrap();
+		assert!(result.converged);
+		assert!(result.solution.approx_eq(&plain.solution, 1e-6));
+		assert!(JacobiPreconditioner::new(&Matrix::zeros(2, 2).unwrap()).is_err());
+	}
+}
@@ -0,0 +1,423 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+use super::operator::LinearOperator;
+use super::stats::{Operation, Probe};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SparseFormat {
+	// Compressed sparse rows: entries grouped by row
+	Csr,
+	// Compressed sparse columns: entries grouped by column
+	Csc,
+}
+
+/* Sparse matrix storing only its nonzero entries, in compressed rows or
+columns. Along the compressed dimension ("major": rows for CSR, columns for
+CSC), the entries of major index k are at offsets[k]..offsets[k + 1] of
+`indices` (the other, "minor" index) and `values`. Minor indices increase
+within each row/column and appear at most once. Memory is
+O(nonzeros + major dimension).
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseMatrix {
+	rows: usize,
+	cols: usize,
+	format: SparseFormat,
+	offsets: Vec<usize>,
+	indices: Vec<usize>,
+	values: Vec<f64>,
+}
+
+impl SparseMatrix {
+	/* Builds a rows x cols matrix from (row, col, value) triplets in any order.
+	Duplicate positions are summed; entries that sum to zero stay stored.
+	*/
+	pub fn from_triplets(
+		rows: usize,
+		cols: usize,
+		triplets: &[(usize, usize, f64)],
+		format: SparseFormat,
+	) -> Result<Self, MathMatrixError> {
+		if rows == 0 || cols == 0 {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"Rows and columns must be lager than 0".to_owned(),
+			));
+		}
+		if let Some((row, col, _)) = triplets
+			.iter()
+			.find(|(row, col, _)| *row >= rows || *col >= cols)
+		{
+			return Err(MathMatrixError::new(
+				OutOfBoundary,
+				format!(
+					"Entry ({}, {}) outside a {}x{} matrix",
+					row, col, rows, cols
+				),
+			));
+		}
+		let major_size = match format {
+			SparseFormat::Csr => rows,
+			SparseFormat::Csc => cols,
+		};
+		let split = |(row, col, value): &(usize, usize, f64)| match format {
+			SparseFormat::Csr => (*row, *col, *value),
+			SparseFormat::Csc => (*col, *row, *value),
+		};
+		// Bucket by major index, then sort and merge each bucket by minor index
+		let mut counts = vec![0usize; major_size + 1];
+		for triplet in triplets {
+			counts[split(triplet).0 + 1] += 1;
+		}
+		for k in 0..major_size {
+			counts[k + 1] += counts[k];
+		}
+		let mut next = counts.clone();
+		let mut entries = vec![(0usize, 0f64); triplets.len()];
+		for triplet in triplets {
+			let (major, minor, value) = split(triplet);
+			entries[next[major]] = (minor, value);
+			next[major] += 1;
+		}
+		let mut offsets = Vec::with_capacity(major_size + 1);
+		let mut indices = Vec::with_capacity(triplets.len());
+		let mut values = Vec::with_capacity(triplets.len());
+		offsets.push(0);
+		for k in 0..major_size {
+			let bucket = &mut entries[counts[k]..counts[k + 1]];
+			bucket.sort_by_key(|(minor, _)| *minor);
+			for (minor, value) in bucket.iter() {
+				if indices.len() > offsets[k] && indices.last() == Some(minor) {
+					*values.last_mut().unwrap() += value;
+				} else {
+					indices.push(*minor);
+					values.push(*value);
+				}
+			}
+			offsets.push(indices.len());
+		}
+		return Ok(Self {
+			rows,
+			cols,
+			format,
+			offsets,
+			indices,
+			values,
+		});
+	}
+
+	// Stores the nonzero entries of a dense matrix
+	pub fn from_dense(mat: &Matrix, format: SparseFormat) -> Result<Self, MathMatrixError> {
+		let (rows, cols) = mat.get_size();
+		let data = mat.get_data();
+		let mut triplets = Vec::new();
+		for j in 0..cols {
+			for i in 0..rows {
+				if data[i + j * rows] != 0.0 {
+					triplets.push((i, j, data[i + j * rows]));
+				}
+			}
+		}
+		return Self::from_triplets(rows, cols, &triplets, format);
+	}
+
+	pub fn to_dense(&self) -> Result<Matrix, MathMatrixError> {
+		let mut data = vec![0f64; self.rows * self.cols];
+		for (row, col, value) in self.triplets() {
+			data[row + col * self.rows] = value;
+		}
+		return Matrix::new(self.rows, self.cols, data);
+	}
+
+	// Same matrix in the given format
+	pub fn to_format(&self, format: SparseFormat) -> Self {
+		if format == self.format {
+			return self.clone();
+		}
+		return Self::from_triplets(self.rows, self.cols, &self.triplets(), format).unwrap();
+	}
+
+	// Stored entries as (row, col, value), ordered by major then minor index
+	pub fn triplets(&self) -> Vec<(usize, usize, f64)> {
+		let mut triplets = Vec::with_capacity(self.values.len());
+		for major in 0..self.offsets.len() - 1 {
+			for k in self.offsets[major]..self.offsets[major + 1] {
+				triplets.push(match self.format {
+					SparseFormat::Csr => (major, self.indices[k], self.values[k]),
+					SparseFormat::Csc => (self.indices[k], major, self.values[k]),
+				});
+			}
+		}
+		return triplets;
+	}
+
+	pub fn get_value(&self, row: usize, col: usize) -> Result<f64, MathMatrixError> {
+		if row >= self.rows || col >= self.cols {
+			return Err(MathMatrixError::new(
+				OutOfBoundary,
+				format!(
+					"Entry ({}, {}) outside a {}x{} matrix",
+					row, col, self.rows, self.cols
+				),
+			));
+		}
+		let (major, minor) = match self.format {
+			SparseFormat::Csr => (row, col),
+			SparseFormat::Csc => (col, row),
+		};
+		let range = self.offsets[major]..self.offsets[major + 1];
+		return Ok(match self.indices[range.clone()].binary_search(&minor) {
+			Ok(k) => self.values[range.start + k],
+			Err(_) => 0.0,
+		});
+	}
+
+	// A * x for x: Nx1, in O(nonzeros)
+	pub fn spmv(&self, x: &Matrix) -> Result<Matrix, MathMatrixError> {
+		if x.get_size() != (self.cols, 1) {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Sparse matrix-vector product allowed for NxM * Mx1".to_owned(),
+			));
+		}
+		return self.multiplied_by_matrix(x);
+	}
+
+	// Sparse times dense: A * B for B: MxK, giving a dense NxK matrix
+	pub fn multiplied_by_matrix(&self, other: &Matrix) -> Result<Matrix, MathMatrixError> {
+		let (other_rows, other_cols) = other.get_size();
+		if self.cols != other_rows {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Multiplication allowed for NxM * MxO".to_owned(),
+			));
+		}
+		let probe = Probe::start(Operation::SparseMultiplication);
+		let b = other.get_data();
+		let mut c = vec![0f64; self.rows * other_cols];
+		for k in 0..other_cols {
+			let (b_col, c_col) = (
+				&b[k * other_rows..(k + 1) * other_rows],
+				&mut c[k * self.rows..(k + 1) * self.rows],
+			);
+			for major in 0..self.offsets.len() - 1 {
+				let range = self.offsets[major]..self.offsets[major + 1];
+				match self.format {
+					// Row `major` of A dotted with the column of B
+					SparseFormat::Csr => {
+						c_col[major] = range.map(|p| self.values[p] * b_col[self.indices[p]]).sum();
+					}
+					// Column `major` of A scaled by one entry of B
+					SparseFormat::Csc => {
+						for p in range {
+							c_col[self.indices[p]] += self.values[p] * b_col[major];
+						}
+					}
+				}
+			}
+		}
+		probe.finish((2 * self.values.len() * other_cols) as u64, 1);
+		return Matrix::new(self.rows, other_cols, c);
+	}
+
+	pub fn get_size(&self) -> (usize, usize) {
+		return (self.rows, self.cols);
+	}
+
+	pub fn get_format(&self) -> SparseFormat {
+		return self.format;
+	}
+
+	// Number of stored entries
+	pub fn get_nnz(&self) -> usize {
+		return self.values.len();
+	}
+
+	pub fn get_offsets(&self) -> &[usize] {
+		return &self.offsets;
+	}
+
+	pub fn get_indices(&self) -> &[usize] {
+		return &self.indices;
+	}
+
+	pub fn get_values(&self) -> &[f64] {
+		return &self.values;
+	}
+}
+
+impl LinearOperator for SparseMatrix {
+	fn get_size(&self) -> (usize, usize) {
+		return SparseMatrix::get_size(self);
+	}
+
+	fn apply(&self, v: &Matrix) -> Result<Matrix, MathMatrixError> {
+		return self.multiplied_by_matrix(v);
+	}
+}
+
+/* Incremental assembly of a sparse matrix: entries are pushed one at a time
+in any order, e.g. element by element in finite elements, and entries pushed
+to the same position are summed when the matrix is built.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct CooBuilder {
+	rows: usize,
+	cols: usize,
+	entries: Vec<(usize, usize, f64)>,
+}
+
+impl CooBuilder {
+	pub fn new(rows: usize, cols: usize) -> Result<Self, MathMatrixError> {
+		return Self::with_capacity(rows, cols, 0);
+	}
+
+	// Reserves room for `capacity` pushed entries
+	pub fn with_capacity(
+		rows: usize,
+		cols: usize,
+		capacity: usize,
+	) -> Result<Self, MathMatrixError> {
+		if rows == 0 || cols == 0 {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"Rows and columns must be lager than 0".to_owned(),
+			));
+		}
+		return Ok(Self {
+			rows,
+			cols,
+			entries: Vec::with_capacity(capacity),
+		});
+	}
+
+	pub fn push(&mut self, row: usize, col: usize, value: f64) -> Result<(), MathMatrixError> {
+		if row >= self.rows || col >= self.cols {
+			return Err(MathMatrixError::new(
+				OutOfBoundary,
+				format!(
+					"Entry ({}, {}) outside a {}x{} matrix",
+					row, col, self.rows, self.cols
+				),
+			));
+		}
+		self.entries.push((row, col, value));
+		return Ok(());
+	}
+
+	// Number of pushed entries, counting duplicates
+	pub fn get_len(&self) -> usize {
+		return self.entries.len();
+	}
+
+	// CSR matrix with duplicates summed
+	pub fn build(&self) -> SparseMatrix {
+		return SparseMatrix::from_triplets(self.rows, self.cols, &self.entries, SparseFormat::Csr)
+			.unwrap();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_from_triplets() {
+		// [1, 0, 2; 0, 0, 3], with (0, 2) given as 1.5 + 0.5
+		let triplets = [(1, 2, 3.0), (0, 2, 1.5), (0, 0, 1.0), (0, 2, 0.5)];
+		let csr = SparseMatrix::from_triplets(2, 3, &triplets, SparseFormat::Csr).unwrap();
+		assert_eq!(csr.get_nnz(), 3);
+		assert_eq!(csr.get_offsets(), &[0, 2, 3]);
+		assert_eq!(csr.get_indices(), &[0, 2, 2]);
+		assert_eq!(csr.get_values(), &[1.0, 2.0, 3.0]);
+		assert_eq!(csr.get_value(0, 2).unwrap(), 2.0);
+		assert_eq!(csr.get_value(1, 0).unwrap(), 0.0);
+		assert!(csr.get_value(2, 0).is_err());
+
+		let csc = csr.to_format(SparseFormat::Csc);
+		assert_eq!(csc.get_offsets(), &[0, 1, 1, 3]);
+		assert_eq!(csc.get_indices(), &[0, 0, 1]);
+		assert_eq!(csc.to_dense().unwrap(), csr.to_dense().unwrap());
+		assert_eq!(csc.to_format(SparseFormat::Csr), csr);
+
+		assert!(SparseMatrix::from_triplets(2, 3, &[(2, 0, 1.0)], SparseFormat::Csr).is_err());
+		assert!(SparseMatrix::from_triplets(0, 3, &[], SparseFormat::Csr).is_err());
+		// rows * cols would overflow, but only the row pointers are allocated
+		let wide = SparseMatrix::from_triplets(2, usize::MAX, &[(1, 7, 1.0)], SparseFormat::Csr);
+		assert_eq!(wide.unwrap().get_value(1, 7).unwrap(), 1.0);
+	}
+
+	#[test]
+	fn test_dense_round_trip() {
+		let dense = Matrix::new(3, 2, vec![0.0, 4.0, 0.0, 5.0, 0.0, 6.0]).unwrap();
+		for format in [SparseFormat::Csr, SparseFormat::Csc].iter() {
+			let sparse = SparseMatrix::from_dense(&dense, *format).unwrap();
+			assert_eq!(sparse.get_nnz(), 3);
+			assert_eq!(sparse.to_dense().unwrap(), dense);
+		}
+	}
+
+	#[test]
+	fn test_coo_builder() {
+		// 1D finite elements: each element adds [1, -1; -1, 1] to its two nodes
+		let nodes = 5;
+		let mut builder = CooBuilder::new(nodes, nodes).unwrap();
+		for element in (0..nodes - 1).rev() {
+			for (i, j, value) in [(0, 0, 1.0), (0, 1, -1.0), (1, 0, -1.0), (1, 1, 1.0)].iter() {
+				builder.push(element + i, element + j, *value).unwrap();
+			}
+		}
+		assert_eq!(builder.get_len(), 16);
+		let stiffness = builder.build();
+		assert_eq!(stiffness.get_format(), SparseFormat::Csr);
+		assert_eq!(stiffness.get_nnz(), 3 * nodes - 2);
+		assert_eq!(stiffness.get_value(2, 2).unwrap(), 2.0);
+		assert_eq!(stiffness.get_value(0, 0).unwrap(), 1.0);
+		assert_eq!(stiffness.get_value(3, 2).unwrap(), -1.0);
+		assert!(builder.push(nodes, 0, 1.0).is_err());
+		assert!(CooBuilder::new(0, 1).is_err());
+		assert!(CooBuilder::new(usize::MAX, usize::MAX).is_ok());
+	}
+
+	#[test]
+	fn test_sparse_products() {
+		let dense =
+			Matrix::new(3, 4, (0..12).map(|k| ((k * 7) % 5) as f64 - 2.0).collect()).unwrap();
+		let b = Matrix::new(4, 2, vec![1.0, -2.0, 0.5, 3.0, 2.0, 0.0, -1.0, 1.0]).unwrap();
+		let expected = dense.multiplied_by_matrix(&b).unwrap();
+		let x = Matrix::new(4, 1, vec![1.0, 2.0, 3.0, 4.0]).unwrap();
+		for format in [SparseFormat::Csr, SparseFormat::Csc].iter() {
+			let sparse = SparseMatrix::from_dense(&dense, *format).unwrap();
+			assert_eq!(sparse.multiplied_by_matrix(&b).unwrap(), expected);
+			assert_eq!(
+				sparse.spmv(&x).unwrap(),
+				dense.multiplied_by_matrix(&x).unwrap()
+			);
+			assert!(sparse.spmv(&b).is_err());
+			assert!(sparse.multiplied_by_matrix(&dense).is_err());
+		}
+	}
+
+	#[test]
+	fn test_solve_sparse_operator() {
+		let n = 50;
+		let mut builder = CooBuilder::new(n, n).unwrap();
+		for i in 0..n {
+			builder.push(i, i, 2.0).unwrap();
+			if i > 0 {
+				builder.push(i, i - 1, -1.0).unwrap();
+				builder.push(i - 1, i, -1.0).unwrap();
+			}
+		}
+		let a = builder.build();
+		let b = Matrix::new(n, 1, vec![1.0; n]).unwrap();
+		let preconditioner = crate::solvers::IdentityPreconditioner;
+		let result =
+			crate::solvers::conjugate_gradient_operator(&a, &b, &preconditioner, 1e-10, 200)
+				.unwrap();
+		assert!(result.converged);
+		let residual = (a.spmv(&result.solution).unwrap() - b).unwrap();
+		assert!(residual.get_data().iter().all(|r| r.abs() < 1e-8));
+	}
+}
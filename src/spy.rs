@@ -0,0 +1,100 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+use super::sparse::SparseMatrix;
+use std::path::Path;
+
+/* Spy plots: the nonzero pattern as a black-and-white image, one pixel per
+entry, black where the entry is nonzero. The image is a binary PBM (P4),
+which most image viewers and converters open directly. Enabled by the
+`spy` feature.
+*/
+impl Matrix {
+	pub fn spy_pbm(&self) -> Vec<u8> {
+		let (rows, cols) = self.get_size();
+		let data = self.get_data();
+		let nonzeros = (0..cols).flat_map(|j| (0..rows).map(move |i| (i, j)));
+		return encode_pbm(
+			rows,
+			cols,
+			nonzeros.filter(|(i, j)| data[j * rows + i] != 0.0),
+		);
+	}
+
+	pub fn spy<P: AsRef<Path>>(&self, path: P) -> Result<(), MathMatrixError> {
+		return write(path.as_ref(), &self.spy_pbm());
+	}
+}
+
+impl SparseMatrix {
+	// Stored entries that are exactly zero are left blank
+	pub fn spy_pbm(&self) -> Vec<u8> {
+		let (rows, cols) = self.get_size();
+		let triplets = self.triplets();
+		return encode_pbm(
+			rows,
+			cols,
+			triplets
+				.iter()
+				.filter(|(_, _, value)| *value != 0.0)
+				.map(|(i, j, _)| (*i, *j)),
+		);
+	}
+
+	pub fn spy<P: AsRef<Path>>(&self, path: P) -> Result<(), MathMatrixError> {
+		return write(path.as_ref(), &self.spy_pbm());
+	}
+}
+
+// Rows of pixels packed eight to a byte, most significant bit first, 1 = black
+fn encode_pbm<I: Iterator<Item = (usize, usize)>>(
+	rows: usize,
+	cols: usize,
+	nonzeros: I,
+) -> Vec<u8> {
+	let header = format!("P4\n{} {}\n", cols, rows);
+	let bytes_per_row = cols.div_ceil(8);
+	let mut image = header.into_bytes();
+	let start = image.len();
+	image.resize(start + rows * bytes_per_row, 0);
+	for (i, j) in nonzeros {
+		image[start + i * bytes_per_row + j / 8] |= 0x80 >> (j % 8);
+	}
+	return image;
+}
+
+fn write(path: &Path, image: &[u8]) -> Result<(), MathMatrixError> {
+	return std::fs::write(path, image).map_err(|error| {
+		MathMatrixError::new(
+			FailedToWrite,
+			format!("Cannot write {}: {}", path.display(), error),
+		)
+	});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::sparse::SparseFormat;
+
+	#[test]
+	fn test_spy() {
+		// Ten columns span two bytes per row
+		let mut mat = Matrix::zeros(2, 10).unwrap();
+		mat.set_value(0, 0, 1.0).unwrap();
+		mat.set_value(0, 9, -2.0).unwrap();
+		mat.set_value(1, 3, 0.5).unwrap();
+		let image = mat.spy_pbm();
+		let mut expected = b"P4\n10 2\n".to_vec();
+		expected.extend_from_slice(&[0b1000_0000, 0b0100_0000, 0b0001_0000, 0]);
+		assert_eq!(image, expected);
+		let sparse = SparseMatrix::from_dense(&mat, SparseFormat::Csc).unwrap();
+		assert_eq!(sparse.spy_pbm(), expected);
+		let path = std::env::temp_dir().join(format!("spy_{}.pbm", std::process::id()));
+		sparse.spy(&path).unwrap();
+		assert_eq!(std::fs::read(&path).unwrap(), expected);
+		std::fs::remove_file(&path).unwrap();
+		let error = mat.spy(std::env::temp_dir().join("missing").join("spy.pbm"));
+		assert_eq!(error.unwrap_err().get_kind(), "FailedToWrite");
+	}
+}
@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/* Operation counters, enabled by the `instrumentation` feature. Each thread
+keeps its own counters: `take_report` returns what the calling thread ran
+since the previous call and resets them. Without the feature the probes
+compile to nothing and reports are always empty.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Operation {
+	MatrixMultiplication,
+	LuDecomposition,
+	QrDecomposition,
+	CholeskyDecomposition,
+	Solve,
+	SparseMultiplication,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OperationStats {
+	pub calls: u64,
+	pub flops: u64,
+	pub allocations: u64,
+	pub elapsed: Duration,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+	operations: BTreeMap<Operation, OperationStats>,
+}
+
+impl Report {
+	pub fn get(&self, operation: Operation) -> Option<&OperationStats> {
+		return self.operations.get(&operation);
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (&Operation, &OperationStats)> {
+		return self.operations.iter();
+	}
+
+	pub fn is_empty(&self) -> bool {
+		return self.operations.is_empty();
+	}
+
+	pub fn total_flops(&self) -> u64 {
+		return self.operations.values().map(|stats| stats.flops).sum();
+	}
+}
+
+impl ToString for Report {
+	fn to_string(&self) -> String {
+		let mut out = String::new();
+		for (operation, stats) in &self.operations {
+			out += &format!(
+				"{:?}: {} calls, {} flops, {} allocations, {:?}\n",
+				operation, stats.calls, stats.flops, stats.allocations, stats.elapsed
+			);
+		}
+		return out;
+	}
+}
+
+#[cfg(feature = "instrumentation")]
+thread_local! {
+	static REPORT: std::cell::RefCell<Report> = std::cell::RefCell::new(Report::default());
+}
+
+pub fn take_report() -> Report {
+	#[cfg(feature = "instrumentation")]
+	return REPORT.with(|report| std::mem::take(&mut *report.borrow_mut()));
+	#[cfg(not(feature = "instrumentation"))]
+	return Report::default();
+}
+
+// Measures one call of an operation from `start` to `finish`
+pub(crate) struct Probe {
+	#[cfg(feature = "instrumentation")]
+	operation: Operation,
+	#[cfg(feature = "instrumentation")]
+	start: std::time::Instant,
+}
+
+impl Probe {
+	#[allow(unused_variables)]
+	pub(crate) fn start(operation: Operation) -> Self {
+		return Self {
+			#[cfg(feature = "instrumentation")]
+			operation,
+			#[cfg(feature = "instrumentation")]
+			start: std::time::Instant::now(),
+		};
+	}
+
+	#[allow(unused_variables)]
+	pub(crate) fn finish(self, flops: u64, allocations: u64) {
+		#[cfg(feature = "instrumentation")]
+		REPORT.with(|report| {
+			let mut report = report.borrow_mut();
+			let stats = report.operations.entry(self.operation).or_default();
+			stats.calls += 1;
+			stats.flops += flops;
+			stats.allocations += allocations;
+			stats.elapsed += self.start.elapsed();
+		});
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::matrix::Matrix;
+
+	#[test]
+	#[cfg(feature = "instrumentation")]
+	fn test_take_report() {
+		take_report();
+		let mat = Matrix::identity(3, 3).unwrap();
+		mat.multiplied_by_matrix(&mat).unwrap();
+		mat.multiplied_by_matrix(&mat).unwrap();
+		let report = take_report();
+		let stats = report.get(Operation::MatrixMultiplication).unwrap();
+		assert_eq!(stats.calls, 2);
+		assert_eq!(stats.flops, 2 * 2 * 27);
+		assert!(report.get(Operation::Solve).is_none());
+		assert!(take_report().is_empty());
+	}
+
+	#[test]
+	#[cfg(not(feature = "instrumentation"))]
+	fn test_take_report_disabled() {
+		let mat = Matrix::identity(3, 3).unwrap();
+		mat.multiplied_by_matrix(&mat).unwrap();
+		assert!(take_report().is_empty());
+	}
+}
@@ -0,0 +1,221 @@
+use super::arena::ArenaBuffer;
+use std::alloc::{alloc_zeroed, dealloc, handle_alloc_error, Layout};
+use std::ops::{Deref, DerefMut};
+
+/* Backing buffer of a Matrix. Heap storage is a plain Vec<f64> and can be
+handed back to the caller without copying. Aligned storage is allocated with
+a caller-chosen power-of-two alignment for SIMD kernels and BLAS interop.
+Inline storage keeps small matrices (up to 4x4) off the heap entirely. Arena
+storage is a slice of an Arena chunk, released in bulk by `Arena::reset`.
+*/
+pub(crate) enum Storage {
+	Heap(Vec<f64>),
+	Aligned(AlignedBuffer),
+	Inline(InlineBuffer),
+	Arena(ArenaBuffer),
+}
+
+pub(crate) const INLINE_CAPACITY: usize = 16;
+
+impl Storage {
+	// Inline when it fits, heap otherwise
+	pub(crate) fn zeros(len: usize) -> Self {
+		if len <= INLINE_CAPACITY {
+			return Storage::Inline(InlineBuffer {
+				data: [0f64; INLINE_CAPACITY],
+				len,
+			});
+		}
+		return Storage::Heap(vec![0f64; len]);
+	}
+
+	pub(crate) fn is_inline(&self) -> bool {
+		return matches!(self, Storage::Inline(_));
+	}
+
+	pub(crate) fn aligned_zeros(len: usize, alignment: usize) -> Option<Self> {
+		return AlignedBuffer::zeros(len, alignment).map(Storage::Aligned);
+	}
+
+	// Zero-copy for heap storage, copies the other kinds
+	pub(crate) fn into_vec(self) -> Vec<f64> {
+		match self {
+			Storage::Heap(data) => data,
+			Storage::Arena(buffer) => buffer.into_vec(),
+			Storage::Aligned(buffer) => buffer.to_vec(),
+			Storage::Inline(buffer) => buffer.to_vec(),
+		}
+	}
+}
+
+impl From<Vec<f64>> for Storage {
+	fn from(data: Vec<f64>) -> Self {
+		return Storage::Heap(data);
+	}
+}
+
+impl Deref for Storage {
+	type Target = [f64];
+
+	fn deref(&self) -> &[f64] {
+		match self {
+			Storage::Heap(data) => data,
+			Storage::Aligned(buffer) => buffer,
+			Storage::Inline(buffer) => buffer,
+			Storage::Arena(buffer) => buffer,
+		}
+	}
+}
+
+impl DerefMut for Storage {
+	fn deref_mut(&mut self) -> &mut [f64] {
+		match self {
+			Storage::Heap(data) => data,
+			Storage::Aligned(buffer) => buffer,
+			Storage::Inline(buffer) => buffer,
+			Storage::Arena(buffer) => buffer,
+		}
+	}
+}
+
+impl Clone for Storage {
+	// Heap clones stay on the heap, so `into_vec` on them is still zero-copy
+	fn clone(&self) -> Self {
+		match self {
+			Storage::Heap(data) => Storage::Heap(data.clone()),
+			Storage::Aligned(buffer) => Storage::Aligned(buffer.clone()),
+			Storage::Inline(buffer) => Storage::Inline(*buffer),
+			Storage::Arena(buffer) => Storage::Arena(buffer.clone()),
+		}
+	}
+}
+
+impl std::fmt::Debug for Storage {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		return self.deref().fmt(f);
+	}
+}
+
+impl PartialEq for Storage {
+	fn eq(&self, other: &Storage) -> bool {
+		return self.deref() == other.deref();
+	}
+}
+
+impl PartialEq<Vec<f64>> for Storage {
+	fn eq(&self, other: &Vec<f64>) -> bool {
+		return self.deref() == other.as_slice();
+	}
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct InlineBuffer {
+	data: [f64; INLINE_CAPACITY],
+	len: usize,
+}
+
+impl Deref for InlineBuffer {
+	type Target = [f64];
+
+	fn deref(&self) -> &[f64] {
+		return &self.data[..self.len];
+	}
+}
+
+impl DerefMut for InlineBuffer {
+	fn deref_mut(&mut self) -> &mut [f64] {
+		return &mut self.data[..self.len];
+	}
+}
+
+pub(crate) struct AlignedBuffer {
+	ptr: *mut f64,
+	len: usize,
+	layout: Layout,
+}
+
+// The buffer is uniquely owned, exactly like a Vec<f64>
+unsafe impl Send for AlignedBuffer {}
+unsafe impl Sync for AlignedBuffer {}
+
+impl AlignedBuffer {
+	fn zeros(len: usize, alignment: usize) -> Option<Self> {
+		if len == 0 || alignment < std::mem::align_of::<f64>() {
+			return None;
+		}
+		let size = len.checked_mul(std::mem::size_of::<f64>())?;
+		let layout = Layout::from_size_align(size, alignment).ok()?;
+		// SAFETY: layout has a non-zero size
+		let ptr = unsafe { alloc_zeroed(layout) } as *mut f64;
+		if ptr.is_null() {
+			handle_alloc_error(layout);
+		}
+		return Some(Self { ptr, len, layout });
+	}
+}
+
+impl Deref for AlignedBuffer {
+	type Target = [f64];
+
+	fn deref(&self) -> &[f64] {
+		// SAFETY: ptr points to len initialized f64 owned by self
+		return unsafe { std::slice::from_raw_parts(self.ptr, self.len) };
+	}
+}
+
+impl DerefMut for AlignedBuffer {
+	fn deref_mut(&mut self) -> &mut [f64] {
+		// SAFETY: ptr points to len initialized f64 uniquely owned by self
+		return unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) };
+	}
+}
+
+impl Clone for AlignedBuffer {
+	fn clone(&self) -> Self {
+		let mut buffer = Self::zeros(self.len, self.layout.align()).unwrap();
+		buffer.copy_from_slice(self);
+		return buffer;
+	}
+}
+
+impl Drop for AlignedBuffer {
+	fn drop(&mut self) {
+		// SAFETY: ptr was allocated in `zeros` with exactly this layout
+		unsafe { dealloc(self.ptr as *mut u8, self.layout) };
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_aligned_storage() {
+		let mut storage = Storage::aligned_zeros(5, 64).unwrap();
+		assert_eq!(storage.as_ptr() as usize % 64, 0);
+		storage[4] = 1.0;
+		let copy = storage.clone();
+		assert_eq!(copy.as_ptr() as usize % 64, 0);
+		assert_eq!(copy, vec![0.0, 0.0, 0.0, 0.0, 1.0]);
+		assert_eq!(storage.into_vec(), vec![0.0, 0.0, 0.0, 0.0, 1.0]);
+		assert!(Storage::aligned_zeros(5, 48).is_none());
+		assert!(Storage::aligned_zeros(5, 4).is_none());
+	}
+
+	#[test]
+	fn test_inline_storage() {
+		let mut storage = Storage::zeros(16);
+		assert!(storage.is_inline());
+		storage[15] = 2.0;
+		assert_eq!(storage.len(), 16);
+		assert!(storage.clone().is_inline());
+		assert!(!Storage::zeros(17).is_inline());
+		// Heap buffers stay on the heap when cloned, however small
+		let heap = Storage::from(vec![1.0; 4]).clone();
+		assert!(!heap.is_inline());
+		let ptr = heap.as_ptr();
+		let data = heap.into_vec();
+		assert_eq!(data.as_ptr(), ptr);
+		assert_eq!(storage.into_vec()[15], 2.0);
+	}
+}
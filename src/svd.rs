@@ -0,0 +1,897 @@
+use super::cancel::CancellationToken;
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+use std::ops::ControlFlow;
+
+/* Golub-Kahan-Reinsch SVD, following the LINPACK/JAMA formulation:
+1. Householder bidiagonalization A = U * B * V^T (MxN with M >= N)
+2. Implicitly shifted QR sweeps on the bidiagonal B, rotating U and V along
+Wide matrices are handled through their transpose. Both stages are public
+(`Matrix::bidiagonalize` and `Bidiagonal::diagonalize`) for custom pipelines.
+*/
+
+/* A = U * B * V^T with B upper bidiagonal (NxN), U: MxN with orthonormal
+columns and V: NxN orthogonal, stored as rows. B has diagonal d and
+superdiagonal e (e[n - 1] is unused).
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bidiagonal {
+	u: Vec<Vec<f64>>,
+	d: Vec<f64>,
+	e: Vec<f64>,
+	v: Vec<Vec<f64>>,
+}
+
+impl Bidiagonal {
+	pub fn get_diagonal(&self) -> &[f64] {
+		return &self.d;
+	}
+
+	pub fn get_superdiagonal(&self) -> &[f64] {
+		return &self.e[..self.e.len() - 1];
+	}
+
+	pub fn get_b(&self) -> Result<Matrix, MathMatrixError> {
+		let n = self.d.len();
+		let mut b = Matrix::zeros(n, n)?;
+		for i in 0..n {
+			b.set_value(i, i, self.d[i])?;
+			if i + 1 < n {
+				b.set_value(i, i + 1, self.e[i])?;
+			}
+		}
+		return Ok(b);
+	}
+
+	pub fn get_u(&self) -> Result<Matrix, MathMatrixError> {
+		return from_rows(&self.u, self.u.len(), self.d.len());
+	}
+
+	pub fn get_v(&self) -> Result<Matrix, MathMatrixError> {
+		return from_rows(&self.v, self.d.len(), self.d.len());
+	}
+
+	/* Shifted QR sweeps driving the superdiagonal to zero while keeping
+	A = U * B * V^T. Afterwards the diagonal holds the singular values in
+	decreasing order.
+	*/
+	pub fn diagonalize(&mut self) -> Result<(), MathMatrixError> {
+		return bidiagonal_qr(self, &mut |_, _| ControlFlow::Continue(()));
+	}
+
+	/* Calls on_iteration(sweep, |e| of the trailing block) before every QR sweep.
+	Returning ControlFlow::Break aborts with a Cancelled error.
+	*/
+	pub fn diagonalize_with_progress<F: FnMut(usize, f64) -> ControlFlow<()>>(
+		&mut self,
+		mut on_iteration: F,
+	) -> Result<(), MathMatrixError> {
+		return bidiagonal_qr(self, &mut on_iteration);
+	}
+
+	// (U, S, V^T) as returned by `Matrix::svd`, once diagonalized
+	pub fn into_svd(self) -> Result<(Matrix, Matrix, Matrix), MathMatrixError> {
+		let n = self.d.len();
+		let mut s = Matrix::zeros(n, n)?;
+		for (i, value) in self.d.iter().enumerate() {
+			s.set_value(i, i, *value)?;
+		}
+		return Ok((self.get_u()?, s, self.get_v()?.transposed()));
+	}
+}
+
+// Pseudo-inverse with a record of how the tolerance was applied
+#[derive(Debug, Clone, PartialEq)]
+pub struct PseudoInverse {
+	pub matrix: Matrix,
+	// Number of singular values kept
+	pub rank: usize,
+	// Singular values at or below this were treated as zero
+	pub tolerance: f64,
+	// The singular values that were dropped, in decreasing order
+	pub truncated: Vec<f64>,
+}
+
+impl Matrix {
+	/* Thin singular value decomposition A = U * S * V^T with k = min(M, N):
+	- U: Mxk with orthonormal columns
+	- S: kxk diagonal, singular values in decreasing order
+	- V^T: kxN with orthonormal rows
+	*/
+	pub fn svd(&self) -> Result<(Matrix, Matrix, Matrix), MathMatrixError> {
+		return self.svd_with_cancellation(&CancellationToken::new());
+	}
+
+	// Checks the token between QR sweeps
+	pub fn svd_with_cancellation(
+		&self,
+		token: &CancellationToken,
+	) -> Result<(Matrix, Matrix, Matrix), MathMatrixError> {
+		let (rows, cols) = self.get_size();
+		if rows < cols {
+			let (u, s, v_t) = self.transposed().svd_with_cancellation(token)?;
+			return Ok((v_t.transposed(), s, u.transposed()));
+		}
+		token.check("SVD")?;
+		let mut bidiagonal = bidiagonalize(self);
+		bidiagonal_qr(&mut bidiagonal, &mut |_, _| token.to_control_flow())?;
+		return bidiagonal.into_svd();
+	}
+
+	/* Polar decomposition A = U * P for an MxN matrix with M >= N, from the
+	SVD A = W * S * V^T:
+	- U = W * V^T: MxN with orthonormal columns, the closest such matrix to A
+	- P = V * S * V^T: NxN symmetric positive semidefinite
+	For a square A, U may be a reflection (det(U) = -1) rather than a rotation.
+	*/
+	pub fn polar(&self) -> Result<(Matrix, Matrix), MathMatrixError> {
+		let (rows, cols) = self.get_size();
+		if rows < cols {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				"Polar decomposition requires at least as many rows as columns".to_owned(),
+			));
+		}
+		let (w, s, v_t) = self.svd()?;
+		let v = v_t.transposed();
+		let u = w.multiplied_by_matrix(&v_t)?;
+		let p = v.multiplied_by_matrix(&s)?.multiplied_by_matrix(&v_t)?;
+		return Ok((u, p));
+	}
+
+	// Golub-Kahan bidiagonalization, the first stage of `svd`. Requires M >= N.
+	pub fn bidiagonalize(&self) -> Result<Bidiagonal, MathMatrixError> {
+		let (rows, cols) = self.get_size();
+		if rows < cols {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				"Bidiagonalization requires at least as many rows as columns".to_owned(),
+			));
+		}
+		return Ok(bidiagonalize(self));
+	}
+
+	/* Number of singular values above the tolerance. The default tolerance is
+	max(M, N) * eps * largest singular value, as in LAPACK and MATLAB.
+	*/
+	pub fn rank(&self, tolerance: Option<f64>) -> Result<usize, MathMatrixError> {
+		let (_, s, _) = self.svd()?;
+		let tolerance = tolerance.unwrap_or(default_tolerance(self, &s)?);
+		return count_above(&s, tolerance);
+	}
+
+	/* Moore-Penrose pseudo-inverse V * S^+ * U^T, where S^+ inverts the singular
+	values above the tolerance (the default one of `rank` if None) and zeroes
+	the rest. The result reports the rank and the dropped singular values.
+	*/
+	pub fn pinv(&self, tolerance: Option<f64>) -> Result<PseudoInverse, MathMatrixError> {
+		let (u, s, v_t) = self.svd()?;
+		let k = s.get_size().0;
+		let tolerance = tolerance.unwrap_or(default_tolerance(self, &s)?);
+		let mut s_inverse = Matrix::zeros(k, k)?;
+		let mut rank = 0;
+		let mut truncated = Vec::new();
+		for i in 0..k {
+			let value = s.get_value(i, i)?;
+			if value > tolerance {
+				s_inverse.set_value(i, i, 1.0 / value)?;
+				rank += 1;
+			} else {
+				truncated.push(value);
+			}
+		}
+		let matrix = v_t
+			.transposed()
+			.multiplied_by_matrix(&s_inverse)?
+			.multiplied_by_matrix(&u.transposed())?;
+		return Ok(PseudoInverse {
+			matrix,
+			rank,
+			tolerance,
+			truncated,
+		});
+	}
+
+	/* Orthonormal basis of the range of A, as the columns of an MxR matrix
+	where R is the rank (same default tolerance as `rank`). None when A is
+	numerically zero.
+	*/
+	pub fn column_space(&self) -> Result<Option<Matrix>, MathMatrixError> {
+		let (u, s, _) = self.svd()?;
+		let rank = count_above(&s, default_tolerance(self, &s)?)?;
+		if rank == 0 {
+			return Ok(None);
+		}
+		return Ok(Some(leading_columns(&u, rank)));
+	}
+
+	/* Orthonormal basis of {x : A * x = 0}, as the columns of an Nx(N - R)
+	matrix. None when A has full column rank.
+	The thin SVD only provides min(M, N) right singular vectors, so the basis
+	is taken as the orthogonal complement of the row space: the last N - R
+	columns of the full Q in the QR decomposition of its R spanning vectors.
+	*/
+	pub fn null_space(&self) -> Result<Option<Matrix>, MathMatrixError> {
+		let cols = self.get_size().1;
+		let (_, s, v_t) = self.svd()?;
+		let rank = count_above(&s, default_tolerance(self, &s)?)?;
+		if rank == cols {
+			return Ok(None);
+		}
+		if rank == 0 {
+			return Ok(Some(Matrix::identity(cols, cols)?));
+		}
+		let (q, _) = leading_columns(&v_t.transposed(), rank).qr()?;
+		let data = q.into_vec();
+		return Ok(Some(Matrix::new(
+			cols,
+			cols - rank,
+			data[rank * cols..].to_vec(),
+		)?));
+	}
+
+	/* Best rank-k approximation in both the 2-norm and the Frobenius norm
+	(Eckart-Young): the SVD truncated to the k largest singular values
+	*/
+	pub fn low_rank_approx(&self, k: usize) -> Result<Matrix, MathMatrixError> {
+		let (u_k, s_k, v_t_k) = self.low_rank_factors(k)?;
+		return u_k.multiplied_by_matrix(&s_k)?.multiplied_by_matrix(&v_t_k);
+	}
+
+	/* Factors of `low_rank_approx` as (U_k: Mxk, S_k: kxk, V_k^T: kxN), which
+	store (M + N + 1) * k numbers instead of M * N
+	*/
+	pub fn low_rank_factors(&self, k: usize) -> Result<(Matrix, Matrix, Matrix), MathMatrixError> {
+		let (rows, cols) = self.get_size();
+		if k == 0 || k > rows.min(cols) {
+			return Err(MathMatrixError::new(
+				OutOfBoundary,
+				format!("Rank {} not in 1..={}", k, rows.min(cols)),
+			));
+		}
+		let (u, s, v_t) = self.svd()?;
+		let mut s_k = Matrix::zeros(k, k)?;
+		for i in 0..k {
+			s_k.set_value(i, i, s.get_value(i, i)?)?;
+		}
+		let v_k = leading_columns(&v_t.transposed(), k);
+		return Ok((leading_columns(&u, k), s_k, v_k.transposed()));
+	}
+}
+
+// Number of singular values above tolerance
+fn count_above(s: &Matrix, tolerance: f64) -> Result<usize, MathMatrixError> {
+	let mut rank = 0;
+	for i in 0..s.get_size().0 {
+		if s.get_value(i, i)? > tolerance {
+			rank += 1;
+		}
+	}
+	return Ok(rank);
+}
+
+// First `count` columns of mat
+fn leading_columns(mat: &Matrix, count: usize) -> Matrix {
+	let rows = mat.get_size().0;
+	let mut data = mat.get_data();
+	data.truncate(rows * count);
+	return Matrix::from_vec_unchecked(rows, count, data);
+}
+
+fn default_tolerance(mat: &Matrix, s: &Matrix) -> Result<f64, MathMatrixError> {
+	let (rows, cols) = mat.get_size();
+	return Ok(rows.max(cols) as f64 * f64::EPSILON * s.get_value(0, 0)?);
+}
+
+fn from_rows(data: &[Vec<f64>], rows: usize, cols: usize) -> Result<Matrix, MathMatrixError> {
+	let mut mat = Matrix::zeros(rows, cols)?;
+	for i in 0..rows {
+		for j in 0..cols {
+			mat.set_value(i, j, data[i][j])?;
+		}
+	}
+	return Ok(mat);
+}
+
+fn bidiagonalize(mat: &Matrix) -> Bidiagonal {
+	let (m, n) = mat.get_size();
+	let mut a: Vec<Vec<f64>> = (0..m)
+		.map(|i| (0..n).map(|j| mat.get_value(i, j).unwrap()).collect())
+		.collect();
+	let mut d = vec![0f64; n];
+	let mut e = vec![0f64; n];
+	let mut u = vec![vec![0f64; n]; m];
+	let mut v = vec![vec![0f64; n]; n];
+	let mut work = vec![0f64; m];
+
+	// Reduce A to bidiagonal form, storing the diagonal in d and the superdiagonal in e
+	let nct = (m - 1).min(n);
+	let nrt = n.saturating_sub(2).min(m);
+	for k in 0..nct.max(nrt) {
+		if k < nct {
+			// Householder reflection zeroing A[k+1.., k]
+			d[k] = 0.0;
+			for i in k..m {
+				d[k] = d[k].hypot(a[i][k]);
+			}
+			if d[k] != 0.0 {
+				if a[k][k] < 0.0 {
+					d[k] = -d[k];
+				}
+				for i in k..m {
+					a[i][k] /= d[k];
+				}
+				a[k][k] += 1.0;
+			}
+			d[k] = -d[k];
+		}
+		for j in (k + 1)..n {
+			if k < nct && d[k] != 0.0 {
+				let mut t = 0.0;
+				for i in k..m {
+					t += a[i][k] * a[i][j];
+				}
+				t = -t / a[k][k];
+				for i in k..m {
+					a[i][j] += t * a[i][k];
+				}
+			}
+			e[j] = a[k][j];
+		}
+		if k < nct {
+			for i in k..m {
+				u[i][k] = a[i][k];
+			}
+		}
+		if k < nrt {
+			// Householder reflection zeroing A[k, k+2..]
+			e[k] = 0.0;
+			for i in (k + 1)..n {
+				e[k] = e[k].hypot(e[i]);
+			}
+			if e[k] != 0.0 {
+				if e[k + 1] < 0.0 {
+					e[k] = -e[k];
+				}
+				for i in (k + 1)..n {
+					e[i] /= e[k];
+				}
+				e[k + 1] += 1.0;
+			}
+			e[k] = -e[k];
+			if k + 1 < m && e[k] != 0.0 {
+				for i in (k + 1)..m {
+					work[i] = 0.0;
+				}
+				for j in (k + 1)..n {
+					for i in (k + 1)..m {
+						work[i] += e[j] * a[i][j];
+					}
+				}
+				for j in (k + 1)..n {
+					let t = -e[j] / e[k + 1];
+					for i in (k + 1)..m {
+						a[i][j] += t * work[i];
+					}
+				}
+			}
+			for i in (k + 1)..n {
+				v[i][k] = e[i];
+			}
+		}
+	}
+	if nct < n {
+		d[nct] = a[nct][nct];
+	}
+	if nrt + 1 < n {
+		e[nrt] = a[nrt][n - 1];
+	}
+	e[n - 1] = 0.0;
+
+	// Accumulate U from the stored reflections
+	for j in nct..n {
+		for row in u.iter_mut() {
+			row[j] = 0.0;
+		}
+		u[j][j] = 1.0;
+	}
+	for k in (0..nct).rev() {
+		if d[k] != 0.0 {
+			for j in (k + 1)..n {
+				let mut t = 0.0;
+				for i in k..m {
+					t += u[i][k] * u[i][j];
+				}
+				t = -t / u[k][k];
+				for i in k..m {
+					u[i][j] += t * u[i][k];
+				}
+			}
+			for i in k..m {
+				u[i][k] = -u[i][k];
+			}
+			u[k][k] += 1.0;
+			for i in 0..k {
+				u[i][k] = 0.0;
+			}
+		} else {
+			for row in u.iter_mut() {
+				row[k] = 0.0;
+			}
+			u[k][k] = 1.0;
+		}
+	}
+
+	// Accumulate V from the stored reflections
+	for k in (0..n).rev() {
+		if k < nrt && e[k] != 0.0 {
+			for j in (k + 1)..n {
+				let mut t = 0.0;
+				for i in (k + 1)..n {
+					t += v[i][k] * v[i][j];
+				}
+				t = -t / v[k + 1][k];
+				for i in (k + 1)..n {
+					v[i][j] += t * v[i][k];
+				}
+			}
+		}
+		for row in v.iter_mut() {
+			row[k] = 0.0;
+		}
+		v[k][k] = 1.0;
+	}
+	return Bidiagonal { u, d, e, v };
+}
+
+// Drive the superdiagonal of B to zero with shifted QR sweeps (Givens rotations)
+fn bidiagonal_qr(
+	bidiagonal: &mut Bidiagonal,
+	on_iteration: &mut dyn FnMut(usize, f64) -> ControlFlow<()>,
+) -> Result<(), MathMatrixError> {
+	let Bidiagonal { u, d, e, v } = bidiagonal;
+	let m = u.len();
+	let n = d.len();
+	let eps = f64::EPSILON;
+	let tiny = 2f64.powi(-966);
+	let max_sweeps = 75 * n.max(1);
+	let mut sweeps = 0;
+	let mut p = n;
+	while p > 0 {
+		// Look for a negligible superdiagonal element e[k], k < p - 1
+		let mut k: isize = p as isize - 2;
+		while k >= 0 {
+			let ku = k as usize;
+			if e[ku].abs() <= tiny + eps * (d[ku].abs() + d[ku + 1].abs()) {
+				e[ku] = 0.0;
+				break;
+			}
+			k -= 1;
+		}
+		let kase;
+		if k == p as isize - 2 {
+			// e[p - 2] is negligible: d[p - 1] converged
+			kase = 4;
+		} else {
+			// Look for a negligible diagonal element d[ks], k < ks < p
+			let mut ks: isize = p as isize - 1;
+			while ks > k {
+				let ksu = ks as usize;
+				let t = e[ksu].abs() + if ks != k + 1 { e[ksu - 1].abs() } else { 0.0 };
+				if d[ksu].abs() <= tiny + eps * t {
+					d[ksu] = 0.0;
+					break;
+				}
+				ks -= 1;
+			}
+			if ks == k {
+				kase = 3;
+			} else if ks == p as isize - 1 {
+				kase = 1;
+			} else {
+				kase = 2;
+				k = ks;
+			}
+		}
+		let k = (k + 1) as usize;
+
+		match kase {
+			// Deflate negligible d[p - 1]
+			1 => {
+				let mut f = e[p - 2];
+				e[p - 2] = 0.0;
+				for j in (k..=(p - 2)).rev() {
+					let t = d[j].hypot(f);
+					let (cs, sn) = (d[j] / t, f / t);
+					d[j] = t;
+					if j != k {
+						f = -sn * e[j - 1];
+						e[j - 1] *= cs;
+					}
+					rotate_columns(v, j, p - 1, cs, sn);
+				}
+			}
+			// Split at negligible d[k - 1]
+			2 => {
+				let mut f = e[k - 1];
+				e[k - 1] = 0.0;
+				for j in k..p {
+					let t = d[j].hypot(f);
+					let (cs, sn) = (d[j] / t, f / t);
+					d[j] = t;
+					f = -sn * e[j];
+					e[j] *= cs;
+					rotate_columns(u, j, k - 1, cs, sn);
+				}
+			}
+			// One QR sweep with a Wilkinson-like shift from the trailing 2x2 block
+			3 => {
+				if on_iteration(sweeps, e[p - 2].abs()).is_break() {
+					return Err(MathMatrixError::new(
+						Cancelled,
+						"SVD was stopped".to_owned(),
+					));
+				}
+				sweeps += 1;
+				if sweeps > max_sweeps {
+					return Err(MathMatrixError::new(
+						FailedToConverge,
+						format!("SVD did not converge in {} sweeps", max_sweeps),
+					));
+				}
+				let scale = d[p - 1]
+					.abs()
+					.max(d[p - 2].abs())
+					.max(e[p - 2].abs())
+					.max(d[k].abs())
+					.max(e[k].abs());
+				let sp = d[p - 1] / scale;
+				let spm1 = d[p - 2] / scale;
+				let epm1 = e[p - 2] / scale;
+				let sk = d[k] / scale;
+				let ek = e[k] / scale;
+				let b = ((spm1 + sp) * (spm1 - sp) + epm1 * epm1) / 2.0;
+				let c = (sp * epm1) * (sp * epm1);
+				let mut shift = 0.0;
+				if b != 0.0 || c != 0.0 {
+					shift = (b * b + c).sqrt();
+					if b < 0.0 {
+						shift = -shift;
+					}
+					shift = c / (b + shift);
+				}
+				let mut f = (sk + sp) * (sk - sp) + shift;
+				let mut g = sk * ek;
+				// Chase the bulge down the bidiagonal
+				for j in k..(p - 1) {
+					let t = f.hypot(g);
+					let (cs, sn) = (f / t, g / t);
+					if j != k {
+						e[j - 1] = t;
+					}
+					f = cs * d[j] + sn * e[j];
+					e[j] = cs * e[j] - sn * d[j];
+					g = sn * d[j + 1];
+					d[j + 1] *= cs;
+					rotate_columns(v, j, j + 1, cs, sn);
+					let t = f.hypot(g);
+					let (cs, sn) = (f / t, g / t);
+					d[j] = t;
+					f = cs * e[j] + sn * d[j + 1];
+					d[j + 1] = -sn * e[j] + cs * d[j + 1];
+					g = sn * e[j + 1];
+					e[j + 1] *= cs;
+					if j < m - 1 {
+						rotate_columns(u, j, j + 1, cs, sn);
+					}
+				}
+				e[p - 2] = f;
+			}
+			// Converged: make d[k] non-negative and move it into decreasing order
+			_ => {
+				if d[k] <= 0.0 {
+					d[k] = if d[k] < 0.0 { -d[k] } else { 0.0 };
+					for row in v.iter_mut() {
+						row[k] = -row[k];
+					}
+				}
+				let mut k = k;
+				while k + 1 < n && d[k] < d[k + 1] {
+					d.swap(k, k + 1);
+					for row in v.iter_mut() {
+						row.swap(k, k + 1);
+					}
+					for row in u.iter_mut() {
+						row.swap(k, k + 1);
+					}
+					k += 1;
+				}
+				p -= 1;
+			}
+		}
+	}
+	return Ok(());
+}
+
+// [x_i, y_i] <- [cs * x_i + sn * y_i, -sn * x_i + cs * y_i] for columns x = j, y = l
+fn rotate_columns(mat: &mut [Vec<f64>], j: usize, l: usize, cs: f64, sn: f64) {
+	for row in mat.iter_mut() {
+		let t = cs * row[j] + sn * row[l];
+		row[l] = -sn * row[j] + cs * row[l];
+		row[j] = t;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn assert_svd(mat: &Matrix) {
+		let (rows, cols) = mat.get_size();
+		let k = rows.min(cols);
+		let (u, s, v_t) = mat.svd().unwrap();
+		assert_eq!(u.get_size(), (rows, k));
+		assert_eq!(s.get_size(), (k, k));
+		assert_eq!(v_t.get_size(), (k, cols));
+		let product = u
+			.multiplied_by_matrix(&s)
+			.unwrap()
+			.multiplied_by_matrix(&v_t)
+			.unwrap();
+		assert!(product.approx_eq(mat, 1e-10));
+		let identity = Matrix::identity(k, k).unwrap();
+		assert!(u
+			.transposed()
+			.multiplied_by_matrix(&u)
+			.unwrap()
+			.approx_eq(&identity, 1e-10));
+		assert!(v_t
+			.multiplied_by_matrix(&v_t.transposed())
+			.unwrap()
+			.approx_eq(&identity, 1e-10));
+		for i in 0..k {
+			assert!(s.get_value(i, i).unwrap() >= 0.0);
+			if i + 1 < k {
+				assert!(s.get_value(i, i).unwrap() >= s.get_value(i + 1, i + 1).unwrap());
+			}
+		}
+	}
+
+	#[test]
+	fn test_svd_known_values() {
+		let mat = Matrix::new(2, 2, vec![3.0, 0.0, 0.0, -2.0]).unwrap();
+		let (_, s, _) = mat.svd().unwrap();
+		assert!((s.get_value(0, 0).unwrap() - 3.0).abs() < 1e-12);
+		assert!((s.get_value(1, 1).unwrap() - 2.0).abs() < 1e-12);
+		assert_svd(&mat);
+	}
+
+	#[test]
+	fn test_svd_rectangular() {
+		let tall = Matrix::new(
+			4,
+			3,
+			vec![
+				1.0, 4.0, 7.0, 10.0, 2.0, 5.0, 8.0, 11.0, 3.0, 6.0, 9.0, 12.0,
+			],
+		)
+		.unwrap();
+		assert_svd(&tall);
+		assert_svd(&tall.transposed());
+		let data = (0..35)
+			.map(|i| ((i * 37 % 11) as f64 - 5.0) / 3.0)
+			.collect();
+		let mat = Matrix::new(7, 5, data).unwrap();
+		assert_svd(&mat);
+		assert_svd(&mat.transposed());
+	}
+
+	#[test]
+	fn test_svd_rank_deficient() {
+		// Rank one: every column is a multiple of [1 2 3]
+		let mat = Matrix::new(3, 3, vec![1.0, 2.0, 3.0, 2.0, 4.0, 6.0, -1.0, -2.0, -3.0]).unwrap();
+		let (_, s, _) = mat.svd().unwrap();
+		assert!(s.get_value(1, 1).unwrap() < 1e-12);
+		assert_svd(&mat);
+		assert_svd(&Matrix::new(1, 3, vec![1.0, 2.0, 2.0]).unwrap());
+	}
+
+	#[test]
+	fn test_rank() {
+		// Third column is the sum of the first two
+		let mat = Matrix::new(
+			4,
+			3,
+			vec![1.0, 2.0, 3.0, 4.0, 0.0, 1.0, 0.0, 1.0, 1.0, 3.0, 3.0, 5.0],
+		)
+		.unwrap();
+		assert_eq!(mat.rank(None).unwrap(), 2);
+		assert_eq!(mat.transposed().rank(None).unwrap(), 2);
+		assert_eq!(Matrix::identity(3, 3).unwrap().rank(None).unwrap(), 3);
+		assert_eq!(Matrix::zeros(2, 2).unwrap().rank(None).unwrap(), 0);
+		let nearly_singular = Matrix::new(2, 2, vec![1.0, 0.0, 0.0, 1e-8]).unwrap();
+		assert_eq!(nearly_singular.rank(None).unwrap(), 2);
+		assert_eq!(nearly_singular.rank(Some(1e-6)).unwrap(), 1);
+	}
+
+	#[test]
+	fn test_pinv() {
+		// Penrose conditions on a rank deficient rectangular matrix
+		let mat = Matrix::new(
+			4,
+			3,
+			vec![1.0, 2.0, 3.0, 4.0, 0.0, 1.0, 0.0, 1.0, 1.0, 3.0, 3.0, 5.0],
+		)
+		.unwrap();
+		let result = mat.pinv(None).unwrap();
+		assert_eq!(result.rank, 2);
+		assert_eq!(result.truncated.len(), 1);
+		assert!(result.truncated[0] <= result.tolerance);
+		let pinv = result.matrix;
+		assert_eq!(pinv.get_size(), (3, 4));
+		let a_x = mat.multiplied_by_matrix(&pinv).unwrap();
+		let x_a = pinv.multiplied_by_matrix(&mat).unwrap();
+		assert!(a_x
+			.multiplied_by_matrix(&mat)
+			.unwrap()
+			.approx_eq(&mat, 1e-12));
+		assert!(x_a
+			.multiplied_by_matrix(&pinv)
+			.unwrap()
+			.approx_eq(&pinv, 1e-12));
+		assert!(a_x.approx_eq(&a_x.transposed(), 1e-12));
+		assert!(x_a.approx_eq(&x_a.transposed(), 1e-12));
+		// Matches the inverse for a non-singular matrix
+		let square = Matrix::new(2, 2, vec![4.0, 2.0, 7.0, 6.0]).unwrap();
+		assert!(square
+			.pinv(None)
+			.unwrap()
+			.matrix
+			.approx_eq(&square.invert().unwrap(), 1e-12));
+	}
+
+	#[test]
+	fn test_pinv_tolerance_report() {
+		let mat = Matrix::new(3, 3, vec![3.0, 0.0, 0.0, 0.0, 1e-3, 0.0, 0.0, 0.0, 1e-9]).unwrap();
+		let default = mat.pinv(None).unwrap();
+		assert_eq!(default.rank, 3);
+		assert!(default.truncated.is_empty());
+		let aggressive = mat.pinv(Some(1e-6)).unwrap();
+		assert_eq!(aggressive.rank, 2);
+		assert_eq!(aggressive.tolerance, 1e-6);
+		assert_eq!(aggressive.truncated.len(), 1);
+		assert!((aggressive.truncated[0] - 1e-9).abs() < 1e-20);
+		assert_eq!(aggressive.matrix.get_value(2, 2).unwrap(), 0.0);
+		assert!((aggressive.matrix.get_value(1, 1).unwrap() - 1e3).abs() < 1e-9);
+	}
+
+	#[test]
+	fn test_svd_stages() {
+		let mat = Matrix::new(
+			4,
+			3,
+			vec![
+				2.0, -1.0, 0.0, 3.0, 1.0, 4.0, -2.0, 0.5, 0.0, 1.0, 1.0, -3.0,
+			],
+		)
+		.unwrap();
+		let mut bidiagonal = mat.bidiagonalize().unwrap();
+		assert_eq!(bidiagonal.get_superdiagonal().len(), 2);
+		let u = bidiagonal.get_u().unwrap();
+		let v = bidiagonal.get_v().unwrap();
+		let b = bidiagonal.get_b().unwrap();
+		let reconstructed = u
+			.multiplied_by_matrix(&b)
+			.unwrap()
+			.multiplied_by_matrix(&v.transposed())
+			.unwrap();
+		assert!(reconstructed.approx_eq(&mat, 1e-12));
+		// Custom convergence monitoring on the second stage
+		let mut sweeps = 0;
+		bidiagonal
+			.diagonalize_with_progress(|_, _| {
+				sweeps += 1;
+				return ControlFlow::Continue(());
+			})
+			.unwrap();
+		assert!(sweeps > 0);
+		assert!(bidiagonal.get_superdiagonal().iter().all(|e| *e == 0.0));
+		let (_, s, _) = mat.svd().unwrap();
+		let (_, s_stages, _) = bidiagonal.into_svd().unwrap();
+		assert_eq!(s_stages, s);
+		assert!(mat.transposed().bidiagonalize().is_err());
+	}
+
+	#[test]
+	fn test_polar() {
+		// Rotation by 30 degrees times a stretch
+		let (c, s) = (30f64.to_radians().cos(), 30f64.to_radians().sin());
+		let rotation = Matrix::new(2, 2, vec![c, s, -s, c]).unwrap();
+		let stretch = Matrix::new(2, 2, vec![2.0, 0.5, 0.5, 1.0]).unwrap();
+		let mat = rotation.multiplied_by_matrix(&stretch).unwrap();
+		let (u, p) = mat.polar().unwrap();
+		assert!(u.approx_eq(&rotation, 1e-12));
+		assert!(p.approx_eq(&stretch, 1e-12));
+		let noisy =
+			(mat.clone() + Matrix::new(2, 2, vec![0.01, -0.02, 0.0, 0.015]).unwrap()).unwrap();
+		let (u, p) = noisy.polar().unwrap();
+		let identity = Matrix::identity(2, 2).unwrap();
+		assert!(u
+			.transposed()
+			.multiplied_by_matrix(&u)
+			.unwrap()
+			.approx_eq(&identity, 1e-12));
+		assert!(p.is_symmetric(1e-12));
+		assert!(p
+			.eigen_symmetric()
+			.unwrap()
+			.0
+			.iter()
+			.all(|value| *value >= 0.0));
+		assert!(u.multiplied_by_matrix(&p).unwrap().approx_eq(&noisy, 1e-12));
+		assert!(Matrix::zeros(2, 3).unwrap().polar().is_err());
+	}
+
+	#[test]
+	fn test_null_and_column_space() {
+		// Rank 2: the third row is the sum of the first two
+		let mat = Matrix::new(
+			3,
+			4,
+			vec![1.0, 0.0, 1.0, 2.0, 1.0, 3.0, 0.0, 1.0, 1.0, 1.0, 1.0, 2.0],
+		)
+		.unwrap();
+		let null = mat.null_space().unwrap().unwrap();
+		assert_eq!(null.get_size(), (4, 2));
+		assert!(mat
+			.multiplied_by_matrix(&null)
+			.unwrap()
+			.approx_eq(&Matrix::zeros(3, 2).unwrap(), 1e-12));
+		let gram = null.transposed().multiplied_by_matrix(&null).unwrap();
+		assert!(gram.approx_eq(&Matrix::identity(2, 2).unwrap(), 1e-12));
+
+		let range = mat.column_space().unwrap().unwrap();
+		assert_eq!(range.get_size(), (3, 2));
+		// Projecting the columns of A onto the basis leaves them unchanged
+		let projector = range.multiplied_by_matrix(&range.transposed()).unwrap();
+		assert!(projector
+			.multiplied_by_matrix(&mat)
+			.unwrap()
+			.approx_eq(&mat, 1e-12));
+
+		assert!(Matrix::identity(3, 3)
+			.unwrap()
+			.null_space()
+			.unwrap()
+			.is_none());
+		assert!(Matrix::zeros(2, 3)
+			.unwrap()
+			.column_space()
+			.unwrap()
+			.is_none());
+		assert_eq!(
+			Matrix::zeros(2, 3).unwrap().null_space().unwrap(),
+			Some(Matrix::identity(3, 3).unwrap())
+		);
+	}
+
+	#[test]
+	fn test_low_rank_approx() {
+		let mat = Matrix::new(3, 3, vec![4.0, 1.0, 0.5, 1.0, 3.0, 0.2, 0.5, 0.2, 0.01]).unwrap();
+		let (_, s, _) = mat.svd().unwrap();
+		let approx = mat.low_rank_approx(2).unwrap();
+		assert_eq!(approx.rank(Some(1e-10)).unwrap(), 2);
+		// The 2-norm error is the first discarded singular value
+		let (_, error_s, _) = (mat.clone() - approx).unwrap().svd().unwrap();
+		assert!((error_s.get_value(0, 0).unwrap() - s.get_value(2, 2).unwrap()).abs() < 1e-12);
+		let (u_k, s_k, v_t_k) = mat.low_rank_factors(1).unwrap();
+		assert_eq!(
+			(u_k.get_size(), s_k.get_size(), v_t_k.get_size()),
+			((3, 1), (1, 1), (1, 3))
+		);
+		assert!(mat.low_rank_approx(3).unwrap().approx_eq(&mat, 1e-12));
+		assert!(mat.low_rank_approx(0).is_err());
+		assert!(mat.low_rank_approx(4).is_err());
+	}
+}
@@ -0,0 +1,260 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+use super::operator::LinearOperator;
+use super::triangular::LowerTriangular;
+
+/* Symmetric matrix storing only its lower triangle, packed column by column:
+n * (n + 1) / 2 entries. Covariance and Gram matrices are symmetric by
+construction, so keeping both halves wastes memory, and every operation here
+touches each stored entry once.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymmetricMatrix {
+	size: usize,
+	data: Vec<f64>,
+}
+
+impl SymmetricMatrix {
+	pub fn zeros(size: usize) -> Result<Self, MathMatrixError> {
+		if size == 0 {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"Rows and columns must be lager than 0".to_owned(),
+			));
+		}
+		return Ok(Self {
+			size,
+			data: vec![0f64; size * (size + 1) / 2],
+		});
+	}
+
+	// Reads only the lower triangle of `mat`, like `cholesky`
+	pub fn from_matrix(mat: &Matrix) -> Result<Self, MathMatrixError> {
+		let (rows, cols) = mat.get_size();
+		if rows != cols {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Symmetric matrices must be square".to_owned(),
+			));
+		}
+		let mut symmetric = Self::zeros(rows)?;
+		for j in 0..rows {
+			for i in j..rows {
+				let index = symmetric.index(i, j);
+				symmetric.data[index] = mat.get_value(i, j)?;
+			}
+		}
+		return Ok(symmetric);
+	}
+
+	// A^T * A, computing only its lower triangle
+	pub fn gram(a: &Matrix) -> Result<Self, MathMatrixError> {
+		let (rows, cols) = a.get_size();
+		let data = a.get_data();
+		let mut gram = Self::zeros(cols)?;
+		for j in 0..cols {
+			let col_j = &data[j * rows..(j + 1) * rows];
+			for i in j..cols {
+				let col_i = &data[i * rows..(i + 1) * rows];
+				let index = gram.index(i, j);
+				gram.data[index] = col_i.iter().zip(col_j.iter()).map(|(x, y)| x * y).sum();
+			}
+		}
+		return Ok(gram);
+	}
+
+	pub fn to_matrix(&self) -> Result<Matrix, MathMatrixError> {
+		let mut mat = Matrix::zeros(self.size, self.size)?;
+		for j in 0..self.size {
+			for i in j..self.size {
+				let value = self.data[self.index(i, j)];
+				mat.set_value(i, j, value)?;
+				mat.set_value(j, i, value)?;
+			}
+		}
+		return Ok(mat);
+	}
+
+	pub fn get_size(&self) -> (usize, usize) {
+		return (self.size, self.size);
+	}
+
+	pub fn get_value(&self, row: usize, col: usize) -> Result<f64, MathMatrixError> {
+		self.check_position(row, col)?;
+		return Ok(self.data[self.index(row.max(col), row.min(col))]);
+	}
+
+	// Sets both (row, col) and (col, row)
+	pub fn set_value(&mut self, row: usize, col: usize, value: f64) -> Result<(), MathMatrixError> {
+		self.check_position(row, col)?;
+		let index = self.index(row.max(col), row.min(col));
+		self.data[index] = value;
+		return Ok(());
+	}
+
+	pub fn added_to(&self, other: &SymmetricMatrix) -> Result<SymmetricMatrix, MathMatrixError> {
+		if self.size != other.size {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Operation not allowed between matrices with different sizes".to_owned(),
+			));
+		}
+		let mut sum = self.clone();
+		for (a, b) in sum.data.iter_mut().zip(other.data.iter()) {
+			*a += b;
+		}
+		return Ok(sum);
+	}
+
+	pub fn multiplied_by_scalar(&self, scalar: f64) -> SymmetricMatrix {
+		let mut scaled = self.clone();
+		scaled.data.iter_mut().for_each(|x| *x *= scalar);
+		return scaled;
+	}
+
+	// Each stored off-diagonal entry contributes to two rows of the product
+	pub fn multiplied_by_matrix(&self, other: &Matrix) -> Result<Matrix, MathMatrixError> {
+		let (other_rows, other_cols) = other.get_size();
+		if self.size != other_rows {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Multiplication allowed for NxM * MxO".to_owned(),
+			));
+		}
+		let n = self.size;
+		let b = other.get_data();
+		let mut c = vec![0f64; n * other_cols];
+		for k in 0..other_cols {
+			let (b_col, c_col) = (&b[k * n..(k + 1) * n], &mut c[k * n..(k + 1) * n]);
+			for j in 0..n {
+				let start = self.index(j, j);
+				c_col[j] += self.data[start] * b_col[j];
+				for i in (j + 1)..n {
+					let a_ij = self.data[start + i - j];
+					c_col[i] += a_ij * b_col[j];
+					c_col[j] += a_ij * b_col[i];
+				}
+			}
+		}
+		return Matrix::new(n, other_cols, c);
+	}
+
+	/* Cholesky factor L with A = L * L^T, packed. Reads the packed columns
+	directly: about n^3 / 6 multiply-adds, half of the dense loop.
+	*/
+	pub fn cholesky(&self) -> Result<LowerTriangular, MathMatrixError> {
+		let n = self.size;
+		let mut l = self.data.clone();
+		// Right-looking: column j is final once the columns before it are applied
+		for j in 0..n {
+			let start = self.index(j, j);
+			let diagonal = l[start];
+			if diagonal <= 0.0 || diagonal.is_nan() {
+				return Err(MathMatrixError::new(
+					NotPositiveDefinite,
+					format!("Non-positive pivot {} at row {}", diagonal, j),
+				));
+			}
+			let l_jj = diagonal.sqrt();
+			l[start] = l_jj;
+			for p in (start + 1)..(start + n - j) {
+				l[p] /= l_jj;
+			}
+			for k in (j + 1)..n {
+				let l_kj = l[start + k - j];
+				let column = self.index(k, k);
+				for i in k..n {
+					l[column + i - k] -= l[start + i - j] * l_kj;
+				}
+			}
+		}
+		let mut factor = LowerTriangular::zeros(n)?;
+		for j in 0..n {
+			for i in j..n {
+				factor.set_value(i, j, l[self.index(i, j)])?;
+			}
+		}
+		return Ok(factor);
+	}
+
+	// Eigenvalues and orthonormal eigenvectors, as `eigen_symmetric`
+	pub fn eigen(&self) -> Result<(Vec<f64>, Matrix), MathMatrixError> {
+		return self.to_matrix()?.eigen_symmetric();
+	}
+
+	// Offset of entry (row, col), row >= col, in the packed lower triangle
+	fn index(&self, row: usize, col: usize) -> usize {
+		return col * (2 * self.size - col + 1) / 2 + row - col;
+	}
+
+	fn check_position(&self, row: usize, col: usize) -> Result<(), MathMatrixError> {
+		if row >= self.size || col >= self.size {
+			return Err(MathMatrixError::new(
+				OutOfBoundary,
+				format!(
+					"Entry ({}, {}) outside a {}x{} matrix",
+					row, col, self.size, self.size
+				),
+			));
+		}
+		return Ok(());
+	}
+}
+
+impl LinearOperator for SymmetricMatrix {
+	fn get_size(&self) -> (usize, usize) {
+		return SymmetricMatrix::get_size(self);
+	}
+
+	fn apply(&self, v: &Matrix) -> Result<Matrix, MathMatrixError> {
+		return self.multiplied_by_matrix(v);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_symmetric_matrix() {
+		let a = Matrix::new(3, 2, vec![1.0, 2.0, 0.5, -1.0, 3.0, 1.0]).unwrap();
+		let gram = SymmetricMatrix::gram(&a).unwrap();
+		let dense = a.transposed().multiplied_by_matrix(&a).unwrap();
+		assert!(gram.to_matrix().unwrap().approx_eq(&dense, 1e-12));
+		assert_eq!(gram.get_value(0, 1).unwrap(), gram.get_value(1, 0).unwrap());
+		let b = Matrix::new(2, 2, vec![1.0, -2.0, 0.5, 4.0]).unwrap();
+		assert!(gram
+			.multiplied_by_matrix(&b)
+			.unwrap()
+			.approx_eq(&dense.multiplied_by_matrix(&b).unwrap(), 1e-12));
+		let doubled = gram.added_to(&gram).unwrap();
+		assert_eq!(doubled, gram.multiplied_by_scalar(2.0));
+		let mut edited = gram.clone();
+		edited.set_value(0, 1, 7.0).unwrap();
+		assert_eq!(edited.get_value(1, 0).unwrap(), 7.0);
+		assert!(edited.get_value(2, 0).is_err());
+	}
+
+	#[test]
+	fn test_symmetric_cholesky() {
+		let dense =
+			Matrix::new(3, 3, vec![4.0, 2.0, -2.0, 2.0, 10.0, 2.0, -2.0, 2.0, 6.0]).unwrap();
+		let symmetric = SymmetricMatrix::from_matrix(&dense).unwrap();
+		let l = symmetric.cholesky().unwrap();
+		assert!(l
+			.to_matrix()
+			.unwrap()
+			.approx_eq(&dense.cholesky().unwrap(), 1e-12));
+		let (values, _) = symmetric.eigen().unwrap();
+		assert_eq!(values.len(), 3);
+		let indefinite = symmetric.added_to(
+			&SymmetricMatrix::from_matrix(
+				&Matrix::identity(3, 3).unwrap().multiplied_by_scalar(-20.0),
+			)
+			.unwrap(),
+		);
+		let error = indefinite.unwrap().cholesky().unwrap_err();
+		assert_eq!(error.get_kind(), "NotPositiveDefinite");
+	}
+}
@@ -0,0 +1,241 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::labeled::Agg;
+use super::matrix::Matrix;
+
+/* Matrix whose rows are observations taken at increasing times. Timestamps
+are plain numbers in whatever unit the caller uses (seconds since an epoch,
+days, samples), so resampling intervals are in the same unit.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeSeries {
+	timestamps: Vec<f64>,
+	matrix: Matrix,
+}
+
+impl TimeSeries {
+	pub fn new(timestamps: Vec<f64>, matrix: Matrix) -> Result<Self, MathMatrixError> {
+		let rows = matrix.get_size().0;
+		if timestamps.len() != rows {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				format!("{} timestamps for {} rows", timestamps.len(), rows),
+			));
+		}
+		if timestamps.iter().any(|t| !t.is_finite()) || timestamps.windows(2).any(|w| w[0] >= w[1])
+		{
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"Timestamps must be finite and strictly increasing".to_owned(),
+			));
+		}
+		return Ok(Self { timestamps, matrix });
+	}
+
+	pub fn get_timestamps(&self) -> &[f64] {
+		return &self.timestamps;
+	}
+
+	pub fn get_matrix(&self) -> &Matrix {
+		return &self.matrix;
+	}
+
+	pub fn into_matrix(self) -> Matrix {
+		return self.matrix;
+	}
+
+	/* One row per interval [k * interval, (k + 1) * interval) holding at
+	least one observation, stamped with the start of the interval. Every
+	column is combined with `agg` over the rows falling in the interval;
+	intervals without observations are left out rather than filled.
+	*/
+	pub fn resample_rows(&self, interval: f64, agg: Agg) -> Result<TimeSeries, MathMatrixError> {
+		if interval <= 0.0 || !interval.is_finite() {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				format!("Resampling interval must be positive, got {}", interval),
+			));
+		}
+		let mut starts: Vec<f64> = vec![];
+		let mut bins: Vec<Vec<usize>> = vec![];
+		for (i, t) in self.timestamps.iter().enumerate() {
+			let start = (t / interval).floor() * interval;
+			if starts.last() != Some(&start) {
+				starts.push(start);
+				bins.push(vec![]);
+			}
+			bins.last_mut().unwrap().push(i);
+		}
+		let cols = self.matrix.get_size().1;
+		let mut data = Vec::with_capacity(bins.len() * cols);
+		for j in 0..cols {
+			for rows in bins.iter() {
+				let values = rows
+					.iter()
+					.map(|i| self.matrix.get_value(*i, j))
+					.collect::<Result<Vec<f64>, MathMatrixError>>()?;
+				data.push(agg.apply(&values));
+			}
+		}
+		return Self::new(starts, Matrix::new(bins.len(), cols, data)?);
+	}
+
+	/* The rows of `self` and `other` at the timestamps both have, as two
+	series on the same time axis (an inner join). Matching is exact, so
+	resample both first if their clocks are not in step.
+	*/
+	pub fn align(&self, other: &TimeSeries) -> Result<(TimeSeries, TimeSeries), MathMatrixError> {
+		let (mut left, mut right) = (vec![], vec![]);
+		let (mut i, mut k) = (0, 0);
+		while i < self.timestamps.len() && k < other.timestamps.len() {
+			if self.timestamps[i] < other.timestamps[k] {
+				i += 1;
+			} else if self.timestamps[i] > other.timestamps[k] {
+				k += 1;
+			} else {
+				left.push(i);
+				right.push(k);
+				i += 1;
+				k += 1;
+			}
+		}
+		if left.is_empty() {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				"Time series have no timestamp in common".to_owned(),
+			));
+		}
+		return Ok((self.take_rows(&left)?, other.take_rows(&right)?));
+	}
+
+	fn take_rows(&self, positions: &[usize]) -> Result<TimeSeries, MathMatrixError> {
+		let cols = self.matrix.get_size().1;
+		let mut data = Vec::with_capacity(positions.len() * cols);
+		for j in 0..cols {
+			for i in positions.iter() {
+				data.push(self.matrix.get_value(*i, j)?);
+			}
+		}
+		return Ok(Self {
+			timestamps: positions.iter().map(|i| self.timestamps[*i]).collect(),
+			matrix: Matrix::new(positions.len(), cols, data)?,
+		});
+	}
+}
+
+// Trend removed from each column by `detrend_cols`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trend {
+	// The column mean
+	Constant,
+	// The least squares line through (i, a_ij) over the row index i
+	Linear,
+}
+
+impl Matrix {
+	// Each column minus its fitted trend, with rows taken as equally spaced
+	pub fn detrend_cols(&self, trend: Trend) -> Matrix {
+		let (rows, cols) = self.get_size();
+		let mut data = self.get_data();
+		// Centered row index: sum of t is 0, so mean and slope decouple
+		let center = (rows - 1) as f64 / 2.0;
+		let t_squares: f64 = (0..rows).map(|i| (i as f64 - center).powi(2)).sum();
+		for column in data.chunks_mut(rows) {
+			let mean = column.iter().sum::<f64>() / rows as f64;
+			let slope = match trend {
+				Trend::Linear if t_squares > 0.0 => {
+					column
+						.iter()
+						.enumerate()
+						.map(|(i, x)| (i as f64 - center) * x)
+						.sum::<f64>() / t_squares
+				}
+				_ => 0.0,
+			};
+			for (i, x) in column.iter_mut().enumerate() {
+				*x -= mean + slope * (i as f64 - center);
+			}
+		}
+		return Matrix::from_vec_unchecked(rows, cols, data);
+	}
+
+	// Differences of consecutive rows, applied `order` times: (N - order) x M
+	pub fn diff_rows(&self, order: usize) -> Result<Matrix, MathMatrixError> {
+		let (rows, cols) = self.get_size();
+		if order >= rows {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				format!(
+					"Difference of order {} needs more than {} rows",
+					order, rows
+				),
+			));
+		}
+		let mut data = self.get_data();
+		let mut length = rows;
+		for _ in 0..order {
+			// Difference every column in place, then drop its last entry
+			for j in 0..cols {
+				let column = &mut data[j * length..(j + 1) * length];
+				for i in 0..(length - 1) {
+					column[i] = column[i + 1] - column[i];
+				}
+			}
+			data = data
+				.chunks(length)
+				.flat_map(|column| column[..length - 1].iter().copied())
+				.collect();
+			length -= 1;
+		}
+		return Matrix::new(length, cols, data);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::vector::Vector;
+
+	#[test]
+	fn test_time_series() {
+		let matrix = Matrix::new(5, 1, vec![1.0, 3.0, 5.0, 7.0, 9.0]).unwrap();
+		let series = TimeSeries::new(vec![0.0, 10.0, 30.0, 45.0, 70.0], matrix).unwrap();
+		let resampled = series.resample_rows(30.0, Agg::Mean).unwrap();
+		assert_eq!(resampled.get_timestamps(), &[0.0, 30.0, 60.0]);
+		assert_eq!(resampled.get_matrix().get_data(), vec![2.0, 6.0, 9.0]);
+		assert!(series.resample_rows(0.0, Agg::Sum).is_err());
+		let other = TimeSeries::new(
+			vec![10.0, 20.0, 45.0],
+			Matrix::new(3, 2, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap(),
+		)
+		.unwrap();
+		let (left, right) = series.align(&other).unwrap();
+		assert_eq!(left.get_timestamps(), &[10.0, 45.0]);
+		assert_eq!(left.get_matrix().get_data(), vec![3.0, 7.0]);
+		assert_eq!(right.get_matrix().get_data(), vec![1.0, 3.0, 4.0, 6.0]);
+		assert!(TimeSeries::new(vec![1.0, 1.0], Matrix::zeros(2, 1).unwrap()).is_err());
+	}
+
+	#[test]
+	fn test_detrend_and_diff() {
+		// Column 0 is the line 1 + 2i, column 1 is a constant plus a bump
+		let a = Matrix::new(4, 2, vec![1.0, 3.0, 5.0, 7.0, 2.0, 2.0, 6.0, 2.0]).unwrap();
+		let linear = a.detrend_cols(Trend::Linear);
+		assert!(Vector::from_column(&linear, 0).unwrap().norm() < 1e-12);
+		let constant = a.detrend_cols(Trend::Constant);
+		assert_eq!(
+			Vector::from_column(&constant, 1).unwrap().get_values(),
+			&[-1.0, -1.0, 3.0, -1.0]
+		);
+		assert_eq!(
+			a.diff_rows(1).unwrap().get_data(),
+			vec![2.0, 2.0, 2.0, 0.0, 4.0, -4.0]
+		);
+		assert_eq!(
+			a.diff_rows(2).unwrap().get_data(),
+			vec![0.0, 0.0, 4.0, -8.0]
+		);
+		assert_eq!(a.diff_rows(0).unwrap(), a);
+		assert!(a.diff_rows(4).is_err());
+	}
+}
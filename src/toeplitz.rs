@@ -0,0 +1,312 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+use super::operator::LinearOperator;
+
+/* Toeplitz matrix, constant along every diagonal: t_ij = c_(i - j) below the
+diagonal and r_(j - i) above it, for first column c and first row r. Stores
+the N + M - 1 distinct values, and multiplies a vector in O((N + M) log(N + M))
+as a linear convolution done with the FFT.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToeplitzMatrix {
+	rows: usize,
+	cols: usize,
+	// t_(k - cols + 1) at index k: the first row reversed, then the first column
+	diagonals: Vec<f64>,
+}
+
+impl ToeplitzMatrix {
+	// first_row[0] and first_col[0] are the same entry and must agree
+	pub fn new(first_col: Vec<f64>, first_row: Vec<f64>) -> Result<Self, MathMatrixError> {
+		if first_col.is_empty() || first_row.is_empty() {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"Rows and columns must be lager than 0".to_owned(),
+			));
+		}
+		if first_col[0] != first_row[0] {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				format!(
+					"First column and first row disagree on entry (0, 0): {} != {}",
+					first_col[0], first_row[0]
+				),
+			));
+		}
+		let (rows, cols) = (first_col.len(), first_row.len());
+		let mut diagonals: Vec<f64> = first_row.into_iter().skip(1).rev().collect();
+		diagonals.extend(first_col);
+		return Ok(Self {
+			rows,
+			cols,
+			diagonals,
+		});
+	}
+
+	// Symmetric Toeplitz matrix, e.g. the covariance of a stationary process
+	pub fn symmetric(first_col: Vec<f64>) -> Result<Self, MathMatrixError> {
+		return Self::new(first_col.clone(), first_col);
+	}
+
+	pub fn get_size(&self) -> (usize, usize) {
+		return (self.rows, self.cols);
+	}
+
+	pub fn get_value(&self, row: usize, col: usize) -> Result<f64, MathMatrixError> {
+		check_position(row, col, self.rows, self.cols)?;
+		return Ok(self.diagonals[row + self.cols - 1 - col]);
+	}
+
+	pub fn to_matrix(&self) -> Result<Matrix, MathMatrixError> {
+		let mut mat = Matrix::zeros(self.rows, self.cols)?;
+		for j in 0..self.cols {
+			for i in 0..self.rows {
+				mat.set_value(i, j, self.diagonals[i + self.cols - 1 - j])?;
+			}
+		}
+		return Ok(mat);
+	}
+
+	/* (T * x)_i = sum_j t_(i - j) x_j is entry i + M - 1 of the linear
+	convolution of the diagonals with x
+	*/
+	pub fn multiplied_by_matrix(&self, other: &Matrix) -> Result<Matrix, MathMatrixError> {
+		let (other_rows, other_cols) = other.get_size();
+		if self.cols != other_rows {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Multiplication allowed for NxM * MxO".to_owned(),
+			));
+		}
+		let convolution = Convolution::new(&self.diagonals, self.cols);
+		let mut data = Vec::with_capacity(self.rows * other_cols);
+		for column in other.get_data().chunks(other_rows) {
+			let z = convolution.apply(column);
+			data.extend_from_slice(&z[self.cols - 1..self.cols - 1 + self.rows]);
+		}
+		return Matrix::new(self.rows, other_cols, data);
+	}
+}
+
+impl LinearOperator for ToeplitzMatrix {
+	fn get_size(&self) -> (usize, usize) {
+		return ToeplitzMatrix::get_size(self);
+	}
+
+	fn apply(&self, v: &Matrix) -> Result<Matrix, MathMatrixError> {
+		return self.multiplied_by_matrix(v);
+	}
+}
+
+/* Circulant matrix: each column is the previous one rotated down by one, so
+c_ij = c_((i - j) mod N) for first column c. Multiplying by it is a circular
+convolution with c, O(N log N) with the FFT; only c is stored.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct CirculantMatrix {
+	first_col: Vec<f64>,
+}
+
+impl CirculantMatrix {
+	pub fn new(first_col: Vec<f64>) -> Result<Self, MathMatrixError> {
+		if first_col.is_empty() {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"Rows and columns must be lager than 0".to_owned(),
+			));
+		}
+		return Ok(Self { first_col });
+	}
+
+	pub fn get_size(&self) -> (usize, usize) {
+		return (self.first_col.len(), self.first_col.len());
+	}
+
+	pub fn get_first_col(&self) -> &[f64] {
+		return &self.first_col;
+	}
+
+	pub fn get_value(&self, row: usize, col: usize) -> Result<f64, MathMatrixError> {
+		let n = self.first_col.len();
+		check_position(row, col, n, n)?;
+		return Ok(self.first_col[(row + n - col) % n]);
+	}
+
+	pub fn to_matrix(&self) -> Result<Matrix, MathMatrixError> {
+		let n = self.first_col.len();
+		let mut mat = Matrix::zeros(n, n)?;
+		for j in 0..n {
+			for i in 0..n {
+				mat.set_value(i, j, self.first_col[(i + n - j) % n])?;
+			}
+		}
+		return Ok(mat);
+	}
+
+	// Circular convolution: the linear one with its tail wrapped onto its head
+	pub fn multiplied_by_matrix(&self, other: &Matrix) -> Result<Matrix, MathMatrixError> {
+		let n = self.first_col.len();
+		let (other_rows, other_cols) = other.get_size();
+		if n != other_rows {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Multiplication allowed for NxM * MxO".to_owned(),
+			));
+		}
+		let convolution = Convolution::new(&self.first_col, n);
+		let mut data = Vec::with_capacity(n * other_cols);
+		for column in other.get_data().chunks(n) {
+			let z = convolution.apply(column);
+			data.extend((0..n).map(|i| z[i] + z.get(i + n).copied().unwrap_or(0.0)));
+		}
+		return Matrix::new(n, other_cols, data);
+	}
+}
+
+impl LinearOperator for CirculantMatrix {
+	fn get_size(&self) -> (usize, usize) {
+		return CirculantMatrix::get_size(self);
+	}
+
+	fn apply(&self, v: &Matrix) -> Result<Matrix, MathMatrixError> {
+		return self.multiplied_by_matrix(v);
+	}
+}
+
+fn check_position(row: usize, col: usize, rows: usize, cols: usize) -> Result<(), MathMatrixError> {
+	if row >= rows || col >= cols {
+		return Err(MathMatrixError::new(
+			OutOfBoundary,
+			format!(
+				"Entry ({}, {}) outside a {}x{} matrix",
+				row, col, rows, cols
+			),
+		));
+	}
+	return Ok(());
+}
+
+/* Linear convolution with a fixed kernel against inputs of a fixed length,
+through a zero-padded power-of-two FFT. The transform of the kernel is
+computed once and reused for every input column.
+*/
+struct Convolution {
+	size: usize,
+	output_len: usize,
+	kernel_re: Vec<f64>,
+	kernel_im: Vec<f64>,
+}
+
+impl Convolution {
+	fn new(kernel: &[f64], input_len: usize) -> Self {
+		let output_len = kernel.len() + input_len - 1;
+		let size = output_len.next_power_of_two();
+		let mut kernel_re = kernel.to_vec();
+		kernel_re.resize(size, 0.0);
+		let mut kernel_im = vec![0f64; size];
+		fft(&mut kernel_re, &mut kernel_im, false);
+		return Self {
+			size,
+			output_len,
+			kernel_re,
+			kernel_im,
+		};
+	}
+
+	fn apply(&self, input: &[f64]) -> Vec<f64> {
+		let mut re = input.to_vec();
+		re.resize(self.size, 0.0);
+		let mut im = vec![0f64; self.size];
+		fft(&mut re, &mut im, false);
+		for k in 0..self.size {
+			let (a, b) = (re[k], im[k]);
+			let (c, d) = (self.kernel_re[k], self.kernel_im[k]);
+			re[k] = a * c - b * d;
+			im[k] = a * d + b * c;
+		}
+		fft(&mut re, &mut im, true);
+		re.truncate(self.output_len);
+		return re;
+	}
+}
+
+/* In-place iterative radix-2 FFT of the complex sequence re + i * im, whose
+length must be a power of two. The inverse transform includes the 1 / N
+scaling.
+*/
+fn fft(re: &mut [f64], im: &mut [f64], inverse: bool) {
+	let n = re.len();
+	// Bit-reversal permutation
+	let mut j = 0;
+	for i in 1..n {
+		let mut bit = n >> 1;
+		while j & bit != 0 {
+			j ^= bit;
+			bit >>= 1;
+		}
+		j |= bit;
+		if i < j {
+			re.swap(i, j);
+			im.swap(i, j);
+		}
+	}
+	let sign = if inverse { 1.0 } else { -1.0 };
+	let mut length = 2;
+	while length <= n {
+		let angle = sign * 2.0 * std::f64::consts::PI / length as f64;
+		for start in (0..n).step_by(length) {
+			for k in 0..length / 2 {
+				let (w_re, w_im) = ((angle * k as f64).cos(), (angle * k as f64).sin());
+				let (p, q) = (start + k, start + k + length / 2);
+				let t_re = re[q] * w_re - im[q] * w_im;
+				let t_im = re[q] * w_im + im[q] * w_re;
+				re[q] = re[p] - t_re;
+				im[q] = im[p] - t_im;
+				re[p] += t_re;
+				im[p] += t_im;
+			}
+		}
+		length <<= 1;
+	}
+	if inverse {
+		re.iter_mut().for_each(|x| *x /= n as f64);
+		im.iter_mut().for_each(|x| *x /= n as f64);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_toeplitz_matrix() {
+		let t = ToeplitzMatrix::new(vec![1.0, 2.0, 3.0], vec![1.0, -1.0, 0.5, 4.0]).unwrap();
+		let dense = t.to_matrix().unwrap();
+		assert_eq!(t.get_size(), (3, 4));
+		assert_eq!(t.get_value(2, 1).unwrap(), 2.0);
+		assert_eq!(t.get_value(0, 3).unwrap(), 4.0);
+		let x = Matrix::new(4, 2, vec![1.0, 2.0, -3.0, 0.5, 0.0, 1.0, 1.0, -2.0]).unwrap();
+		assert!(t
+			.multiplied_by_matrix(&x)
+			.unwrap()
+			.approx_eq(&dense.multiplied_by_matrix(&x).unwrap(), 1e-12));
+		assert!(ToeplitzMatrix::new(vec![1.0], vec![2.0]).is_err());
+		assert!(t.multiplied_by_matrix(&dense).is_err());
+		let symmetric = ToeplitzMatrix::symmetric(vec![2.0, 1.0, 0.5]).unwrap();
+		assert!(symmetric.to_matrix().unwrap().is_symmetric(0.0));
+	}
+
+	#[test]
+	fn test_circulant_matrix() {
+		let c = CirculantMatrix::new(vec![1.0, 2.0, 3.0, 4.0, 5.0]).unwrap();
+		let dense = c.to_matrix().unwrap();
+		assert_eq!(c.get_value(0, 1).unwrap(), 5.0);
+		let x = Matrix::new(5, 1, vec![1.0, -1.0, 2.0, 0.0, 3.0]).unwrap();
+		assert!(c
+			.multiplied_by_matrix(&x)
+			.unwrap()
+			.approx_eq(&dense.multiplied_by_matrix(&x).unwrap(), 1e-12));
+		assert!(c.get_value(5, 0).is_err());
+	}
+}
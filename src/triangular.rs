@@ -0,0 +1,413 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+
+/* Square triangular matrices that store only their triangle, packed column by
+column: n * (n + 1) / 2 entries instead of n^2. Solves are O(n^2) per
+right-hand side and inversion O(n^3 / 3), and the inverse is triangular of
+the same shape. The L and U of `plu_decompose` convert with `from_matrix`, or
+come packed directly from `plu_triangular`.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct LowerTriangular {
+	storage: Packed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpperTriangular {
+	storage: Packed,
+}
+
+impl LowerTriangular {
+	pub fn zeros(size: usize) -> Result<Self, MathMatrixError> {
+		return Ok(Self {
+			storage: Packed::zeros(size, true)?,
+		});
+	}
+
+	// Reads only the lower triangle of `mat`, diagonal included
+	pub fn from_matrix(mat: &Matrix) -> Result<Self, MathMatrixError> {
+		return Ok(Self {
+			storage: Packed::from_matrix(mat, true)?,
+		});
+	}
+
+	pub fn to_matrix(&self) -> Result<Matrix, MathMatrixError> {
+		return self.storage.to_matrix();
+	}
+
+	pub fn get_size(&self) -> (usize, usize) {
+		return (self.storage.size, self.storage.size);
+	}
+
+	// Zero above the diagonal
+	pub fn get_value(&self, row: usize, col: usize) -> Result<f64, MathMatrixError> {
+		return self.storage.get_value(row, col);
+	}
+
+	pub fn set_value(&mut self, row: usize, col: usize, value: f64) -> Result<(), MathMatrixError> {
+		return self.storage.set_value(row, col, value);
+	}
+
+	pub fn transposed(&self) -> UpperTriangular {
+		return UpperTriangular {
+			storage: self.storage.transposed(),
+		};
+	}
+
+	pub fn multiplied_by_matrix(&self, other: &Matrix) -> Result<Matrix, MathMatrixError> {
+		return self.storage.multiplied_by_matrix(other);
+	}
+
+	// Solve L * X = B by forward substitution
+	pub fn solve(&self, b: &Matrix) -> Result<Matrix, MathMatrixError> {
+		return self.storage.solve(b);
+	}
+
+	pub fn inverse(&self) -> Result<LowerTriangular, MathMatrixError> {
+		return Ok(Self {
+			storage: self.storage.inverse()?,
+		});
+	}
+}
+
+impl UpperTriangular {
+	pub fn zeros(size: usize) -> Result<Self, MathMatrixError> {
+		return Ok(Self {
+			storage: Packed::zeros(size, false)?,
+		});
+	}
+
+	// Reads only the upper triangle of `mat`, diagonal included
+	pub fn from_matrix(mat: &Matrix) -> Result<Self, MathMatrixError> {
+		return Ok(Self {
+			storage: Packed::from_matrix(mat, false)?,
+		});
+	}
+
+	pub fn to_matrix(&self) -> Result<Matrix, MathMatrixError> {
+		return self.storage.to_matrix();
+	}
+
+	pub fn get_size(&self) -> (usize, usize) {
+		return (self.storage.size, self.storage.size);
+	}
+
+	// Zero below the diagonal
+	pub fn get_value(&self, row: usize, col: usize) -> Result<f64, MathMatrixError> {
+		return self.storage.get_value(row, col);
+	}
+
+	pub fn set_value(&mut self, row: usize, col: usize, value: f64) -> Result<(), MathMatrixError> {
+		return self.storage.set_value(row, col, value);
+	}
+
+	pub fn transposed(&self) -> LowerTriangular {
+		return LowerTriangular {
+			storage: self.storage.transposed(),
+		};
+	}
+
+	pub fn multiplied_by_matrix(&self, other: &Matrix) -> Result<Matrix, MathMatrixError> {
+		return self.storage.multiplied_by_matrix(other);
+	}
+
+	// Solve U * X = B by back substitution
+	pub fn solve(&self, b: &Matrix) -> Result<Matrix, MathMatrixError> {
+		return self.storage.solve(b);
+	}
+
+	pub fn inverse(&self) -> Result<UpperTriangular, MathMatrixError> {
+		return Ok(Self {
+			storage: self.storage.inverse()?,
+		});
+	}
+}
+
+impl Matrix {
+	// `plu_decompose` with L and U packed
+	pub fn plu_triangular(
+		&self,
+	) -> Result<(Matrix, LowerTriangular, UpperTriangular), MathMatrixError> {
+		let (p_mat, l_mat, u_mat) = self.plu_decompose()?;
+		return Ok((
+			p_mat,
+			LowerTriangular::from_matrix(&l_mat)?,
+			UpperTriangular::from_matrix(&u_mat)?,
+		));
+	}
+}
+
+/* Packed triangle shared by both shapes. Column j of a lower triangle holds
+rows j..n, column j of an upper triangle rows 0..=j.
+*/
+#[derive(Debug, Clone, PartialEq)]
+struct Packed {
+	size: usize,
+	lower: bool,
+	data: Vec<f64>,
+}
+
+impl Packed {
+	fn zeros(size: usize, lower: bool) -> Result<Self, MathMatrixError> {
+		if size == 0 {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"Rows and columns must be lager than 0".to_owned(),
+			));
+		}
+		return Ok(Self {
+			size,
+			lower,
+			data: vec![0f64; size * (size + 1) / 2],
+		});
+	}
+
+	fn from_matrix(mat: &Matrix, lower: bool) -> Result<Self, MathMatrixError> {
+		let (rows, cols) = mat.get_size();
+		if rows != cols {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Triangular matrices must be square".to_owned(),
+			));
+		}
+		let mut packed = Self::zeros(rows, lower)?;
+		for j in 0..rows {
+			for i in packed.column_rows(j) {
+				let index = packed.index(i, j);
+				packed.data[index] = mat.get_value(i, j)?;
+			}
+		}
+		return Ok(packed);
+	}
+
+	fn to_matrix(&self) -> Result<Matrix, MathMatrixError> {
+		let mut mat = Matrix::zeros(self.size, self.size)?;
+		for j in 0..self.size {
+			for i in self.column_rows(j) {
+				mat.set_value(i, j, self.data[self.index(i, j)])?;
+			}
+		}
+		return Ok(mat);
+	}
+
+	fn get_value(&self, row: usize, col: usize) -> Result<f64, MathMatrixError> {
+		self.check_position(row, col)?;
+		if !self.contains(row, col) {
+			return Ok(0.0);
+		}
+		return Ok(self.data[self.index(row, col)]);
+	}
+
+	fn set_value(&mut self, row: usize, col: usize, value: f64) -> Result<(), MathMatrixError> {
+		self.check_position(row, col)?;
+		if !self.contains(row, col) {
+			return Err(MathMatrixError::new(
+				OutOfBoundary,
+				format!("Entry ({}, {}) outside the triangle", row, col),
+			));
+		}
+		let index = self.index(row, col);
+		self.data[index] = value;
+		return Ok(());
+	}
+
+	fn transposed(&self) -> Packed {
+		let mut transposed = Self::zeros(self.size, !self.lower).unwrap();
+		for j in 0..self.size {
+			for i in self.column_rows(j) {
+				let index = transposed.index(j, i);
+				transposed.data[index] = self.data[self.index(i, j)];
+			}
+		}
+		return transposed;
+	}
+
+	fn multiplied_by_matrix(&self, other: &Matrix) -> Result<Matrix, MathMatrixError> {
+		let (other_rows, other_cols) = other.get_size();
+		if self.size != other_rows {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Multiplication allowed for NxM * MxO".to_owned(),
+			));
+		}
+		let n = self.size;
+		let b = other.get_data();
+		let mut c = vec![0f64; n * other_cols];
+		for k in 0..other_cols {
+			for j in 0..n {
+				let b_jk = b[k * n + j];
+				for i in self.column_rows(j) {
+					c[k * n + i] += self.data[self.index(i, j)] * b_jk;
+				}
+			}
+		}
+		return Matrix::new(n, other_cols, c);
+	}
+
+	// Column-oriented substitution, walking each packed column contiguously
+	fn solve(&self, b: &Matrix) -> Result<Matrix, MathMatrixError> {
+		let (rows, cols) = b.get_size();
+		if rows != self.size {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Triangular solve requires a square NxN matrix and an NxK right-hand side"
+					.to_owned(),
+			));
+		}
+		self.check_nonsingular()?;
+		let n = self.size;
+		let mut x = b.get_data();
+		for c in 0..cols {
+			let column = &mut x[c * n..(c + 1) * n];
+			self.substitute(column, 0..n);
+		}
+		return Matrix::new(n, cols, x);
+	}
+
+	/* Column j of the inverse solves T * x = e_j, and x is zero outside the
+	rows of column j of T, so each solve only touches that part.
+	*/
+	fn inverse(&self) -> Result<Packed, MathMatrixError> {
+		self.check_nonsingular()?;
+		let n = self.size;
+		let mut inverse = Self::zeros(n, self.lower)?;
+		let mut x = vec![0f64; n];
+		for j in 0..n {
+			x.iter_mut().for_each(|elem| *elem = 0.0);
+			x[j] = 1.0;
+			self.substitute(&mut x, self.column_rows(j));
+			for i in self.column_rows(j) {
+				let index = inverse.index(i, j);
+				inverse.data[index] = x[i];
+			}
+		}
+		return Ok(inverse);
+	}
+
+	// x <- T^-1 * x in place, for x zero outside the rows `active`
+	fn substitute(&self, x: &mut [f64], active: std::ops::Range<usize>) {
+		let n = self.size;
+		if self.lower {
+			for j in active {
+				let start = self.index(j, j);
+				x[j] /= self.data[start];
+				for (offset, i) in ((j + 1)..n).enumerate() {
+					x[i] -= self.data[start + 1 + offset] * x[j];
+				}
+			}
+		} else {
+			for j in active.rev() {
+				let start = self.index(0, j);
+				x[j] /= self.data[start + j];
+				for i in 0..j {
+					x[i] -= self.data[start + i] * x[j];
+				}
+			}
+		}
+	}
+
+	fn contains(&self, row: usize, col: usize) -> bool {
+		return if self.lower { row >= col } else { row <= col };
+	}
+
+	fn index(&self, row: usize, col: usize) -> usize {
+		if self.lower {
+			return col * (2 * self.size - col + 1) / 2 + row - col;
+		}
+		return col * (col + 1) / 2 + row;
+	}
+
+	fn column_rows(&self, col: usize) -> std::ops::Range<usize> {
+		return if self.lower {
+			col..self.size
+		} else {
+			0..(col + 1)
+		};
+	}
+
+	fn check_nonsingular(&self) -> Result<(), MathMatrixError> {
+		if (0..self.size).any(|i| self.data[self.index(i, i)] == 0.0) {
+			return Err(MathMatrixError::new(
+				FailedToDecompose,
+				"Matrix is singular".to_owned(),
+			));
+		}
+		return Ok(());
+	}
+
+	fn check_position(&self, row: usize, col: usize) -> Result<(), MathMatrixError> {
+		if row >= self.size || col >= self.size {
+			return Err(MathMatrixError::new(
+				OutOfBoundary,
+				format!(
+					"Entry ({}, {}) outside a {}x{} matrix",
+					row, col, self.size, self.size
+				),
+			));
+		}
+		return Ok(());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_triangular_from_lu() {
+		let a = Matrix::new(
+			4,
+			4,
+			vec![
+				2.0, 4.0, -2.0, 1.0, 1.0, 3.0, 0.0, 5.0, -1.0, 2.0, 6.0, 1.0, 3.0, 1.0, 2.0, 7.0,
+			],
+		)
+		.unwrap();
+		let b = Matrix::new(4, 2, (0..8).map(|k| k as f64 - 3.0).collect()).unwrap();
+		let (p, l, u) = a.plu_triangular().unwrap();
+		assert_eq!(l.get_value(0, 3).unwrap(), 0.0);
+		assert_eq!(
+			l.to_matrix()
+				.unwrap()
+				.multiplied_by_matrix(&u.to_matrix().unwrap())
+				.unwrap(),
+			l.multiplied_by_matrix(&u.to_matrix().unwrap()).unwrap()
+		);
+		// P * A * X = P * B through the packed factors
+		let x = u
+			.solve(&l.solve(&p.multiplied_by_matrix(&b).unwrap()).unwrap())
+			.unwrap();
+		assert!(x.approx_eq(&a.solve(&b).unwrap(), 1e-12));
+		assert!(l.solve(&Matrix::zeros(3, 1).unwrap()).is_err());
+	}
+
+	#[test]
+	fn test_triangular_inverse() {
+		let dense = Matrix::new(3, 3, vec![2.0, 1.0, -1.0, 0.0, 4.0, 3.0, 0.0, 0.0, 5.0]).unwrap();
+		let lower = LowerTriangular::from_matrix(&dense).unwrap();
+		let upper = lower.transposed();
+		assert_eq!(
+			upper.to_matrix().unwrap(),
+			lower.to_matrix().unwrap().transposed()
+		);
+		let identity = Matrix::identity(3, 3).unwrap();
+		for (t, inverse) in [
+			(lower.to_matrix(), lower.inverse().unwrap().to_matrix()),
+			(upper.to_matrix(), upper.inverse().unwrap().to_matrix()),
+		]
+		.iter()
+		{
+			let product = t
+				.as_ref()
+				.unwrap()
+				.multiplied_by_matrix(inverse.as_ref().unwrap())
+				.unwrap();
+			assert!(product.approx_eq(&identity, 1e-12));
+		}
+		let mut singular = upper.clone();
+		singular.set_value(1, 1, 0.0).unwrap();
+		assert!(singular.inverse().is_err());
+		assert!(singular.set_value(2, 0, 1.0).is_err());
+	}
+}
@@ -0,0 +1,333 @@
+use super::diagonal::DiagonalMatrix;
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+use super::reduction;
+
+/* Dense vector of N values. Equivalent to an Nx1 Matrix, but without
+row/column bookkeeping for code that works with vectors: dot products, norms
+and cross products take and return vectors directly.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vector {
+	values: Vec<f64>,
+}
+
+impl Vector {
+	pub fn new(values: Vec<f64>) -> Result<Self, MathMatrixError> {
+		if values.is_empty() {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"Vectors must have at least one entry".to_owned(),
+			));
+		}
+		return Ok(Self { values });
+	}
+
+	pub fn zeros(length: usize) -> Result<Self, MathMatrixError> {
+		return Self::new(vec![0f64; length]);
+	}
+
+	// Column `col` of `mat`
+	pub fn from_column(mat: &Matrix, col: usize) -> Result<Self, MathMatrixError> {
+		let rows = mat.get_size().0;
+		check_index(mat, col, 1)?;
+		let values = (0..rows)
+			.map(|i| mat.get_value(i, col))
+			.collect::<Result<Vec<f64>, MathMatrixError>>()?;
+		return Self::new(values);
+	}
+
+	// Row `row` of `mat`
+	pub fn from_row(mat: &Matrix, row: usize) -> Result<Self, MathMatrixError> {
+		let cols = mat.get_size().1;
+		check_index(mat, row, 0)?;
+		let values = (0..cols)
+			.map(|j| mat.get_value(row, j))
+			.collect::<Result<Vec<f64>, MathMatrixError>>()?;
+		return Self::new(values);
+	}
+
+	// Nx1 matrix
+	pub fn to_column(&self) -> Matrix {
+		return Matrix::from_vec_unchecked(self.values.len(), 1, self.values.clone());
+	}
+
+	// 1xN matrix
+	pub fn to_row(&self) -> Matrix {
+		return Matrix::from_vec_unchecked(1, self.values.len(), self.values.clone());
+	}
+
+	pub fn get_length(&self) -> usize {
+		return self.values.len();
+	}
+
+	pub fn get_values(&self) -> &[f64] {
+		return &self.values;
+	}
+
+	pub fn get_value(&self, index: usize) -> Result<f64, MathMatrixError> {
+		return self.values.get(index).copied().ok_or_else(|| {
+			MathMatrixError::new(
+				OutOfBoundary,
+				format!(
+					"Entry {} outside a vector of length {}",
+					index,
+					self.values.len()
+				),
+			)
+		});
+	}
+
+	pub fn set_value(&mut self, index: usize, value: f64) -> Result<(), MathMatrixError> {
+		let length = self.values.len();
+		let elem = self.values.get_mut(index).ok_or_else(|| {
+			MathMatrixError::new(
+				OutOfBoundary,
+				format!("Entry {} outside a vector of length {}", index, length),
+			)
+		})?;
+		*elem = value;
+		return Ok(());
+	}
+
+	pub fn dot(&self, other: &Vector) -> Result<f64, MathMatrixError> {
+		self.check_length(other)?;
+		return Ok(reduction::dot(&self.values, &other.values));
+	}
+
+	// Euclidean norm
+	pub fn norm(&self) -> f64 {
+		return reduction::norm(&self.values);
+	}
+
+	// Unit vector in the same direction
+	pub fn normalized(&self) -> Result<Vector, MathMatrixError> {
+		let norm = self.norm();
+		if norm == 0.0 {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				"The zero vector has no direction".to_owned(),
+			));
+		}
+		return Ok(self.multiplied_by_scalar(1.0 / norm));
+	}
+
+	// Defined for vectors of length 3 only
+	pub fn cross(&self, other: &Vector) -> Result<Vector, MathMatrixError> {
+		if self.values.len() != 3 || other.values.len() != 3 {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Cross product allowed only between vectors of length 3".to_owned(),
+			));
+		}
+		let (a, b) = (&self.values, &other.values);
+		return Self::new(vec![
+			a[1] * b[2] - a[2] * b[1],
+			a[2] * b[0] - a[0] * b[2],
+			a[0] * b[1] - a[1] * b[0],
+		]);
+	}
+
+	// u * v^T, m x n for u of length m and v of length n
+	pub fn outer(&self, other: &Vector) -> Matrix {
+		let mut data = Vec::with_capacity(self.values.len() * other.values.len());
+		for v in other.values.iter() {
+			data.extend(self.values.iter().map(|u| u * v));
+		}
+		return Matrix::from_vec_unchecked(self.values.len(), other.values.len(), data);
+	}
+
+	pub fn added_to(&self, other: &Vector) -> Result<Vector, MathMatrixError> {
+		self.check_length(other)?;
+		return Self::new(
+			self.values
+				.iter()
+				.zip(other.values.iter())
+				.map(|(a, b)| a + b)
+				.collect(),
+		);
+	}
+
+	pub fn multiplied_by_scalar(&self, scalar: f64) -> Vector {
+		return Self {
+			values: self.values.iter().map(|x| x * scalar).collect(),
+		};
+	}
+
+	fn check_length(&self, other: &Vector) -> Result<(), MathMatrixError> {
+		if self.values.len() != other.values.len() {
+			return Err(MathMatrixError::new(
+				SizeMismatch,
+				"Operation not allowed between matrices with different sizes".to_owned(),
+			));
+		}
+		return Ok(());
+	}
+}
+
+// Matrix::get_value does not reject an index equal to the size on its own
+fn check_index(mat: &Matrix, index: usize, axis: usize) -> Result<(), MathMatrixError> {
+	let (rows, cols) = mat.get_size();
+	let size = if axis == 0 { rows } else { cols };
+	if index >= size {
+		return Err(MathMatrixError::new(
+			OutOfBoundary,
+			format!("Index {} outside a {}x{} matrix", index, rows, cols),
+		));
+	}
+	return Ok(());
+}
+
+fn as_vector(mat: &Matrix) -> Result<Vector, MathMatrixError> {
+	let (rows, cols) = mat.get_size();
+	if rows != 1 && cols != 1 {
+		return Err(MathMatrixError::new(
+			SizeMismatch,
+			format!(
+				"Expected a row or column vector, got a {}x{} matrix",
+				rows, cols
+			),
+		));
+	}
+	return Vector::new(mat.get_data());
+}
+
+// What `normalize_rows` and `normalize_cols` make equal to one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Normalization {
+	// The Euclidean norm
+	UnitNorm,
+	// The sum of the entries, e.g. for probability distributions
+	SumToOne,
+}
+
+impl Normalization {
+	fn factor(&self, line: &[f64]) -> Result<f64, MathMatrixError> {
+		let (name, size) = match self {
+			Normalization::UnitNorm => ("norm", reduction::norm(line)),
+			Normalization::SumToOne => ("sum", line.iter().sum()),
+		};
+		if size == 0.0 || !size.is_finite() {
+			return Err(MathMatrixError::new(
+				OperationNotPermitted,
+				format!("Cannot normalize a row or column with {} {}", name, size),
+			));
+		}
+		return Ok(1.0 / size);
+	}
+}
+
+impl Matrix {
+	// A * v
+	pub fn multiplied_by_vector(&self, v: &Vector) -> Result<Vector, MathMatrixError> {
+		let product = self.multiplied_by_matrix(&v.to_column())?;
+		return Vector::new(product.into_vec());
+	}
+
+	// Row i multiplied by v_i, i.e. diag(v) * A
+	pub fn scale_rows(&self, v: &Vector) -> Result<Matrix, MathMatrixError> {
+		return DiagonalMatrix::new(v.values.clone())?.multiplied_by_matrix(self);
+	}
+
+	// Column j multiplied by v_j, i.e. A * diag(v)
+	pub fn scale_cols(&self, v: &Vector) -> Result<Matrix, MathMatrixError> {
+		return self.multiplied_by_diagonal(&DiagonalMatrix::new(v.values.clone())?);
+	}
+
+	// Every row divided by its norm or sum; fails on a row where that is zero
+	pub fn normalize_rows(&self, normalization: Normalization) -> Result<Matrix, MathMatrixError> {
+		let rows = self.get_size().0;
+		let factors = (0..rows)
+			.map(|i| normalization.factor(Vector::from_row(self, i)?.get_values()))
+			.collect::<Result<Vec<f64>, MathMatrixError>>()?;
+		return self.scale_rows(&Vector::new(factors)?);
+	}
+
+	// Every column divided by its norm or sum; fails on a column where that is zero
+	pub fn normalize_cols(&self, normalization: Normalization) -> Result<Matrix, MathMatrixError> {
+		let rows = self.get_size().0;
+		let factors = self
+			.get_data()
+			.chunks(rows)
+			.map(|column| normalization.factor(column))
+			.collect::<Result<Vec<f64>, MathMatrixError>>()?;
+		return self.scale_cols(&Vector::new(factors)?);
+	}
+
+	// u * v^T for row or column vectors u and v
+	pub fn outer_product(&self, other: &Matrix) -> Result<Matrix, MathMatrixError> {
+		let (u, v) = (as_vector(self)?, as_vector(other)?);
+		return Ok(u.outer(&v));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_vector() {
+		let x = Vector::new(vec![1.0, 0.0, 0.0]).unwrap();
+		let y = Vector::new(vec![0.0, 1.0, 0.0]).unwrap();
+		assert_eq!(x.cross(&y).unwrap().get_values(), &[0.0, 0.0, 1.0]);
+		assert_eq!(x.dot(&y).unwrap(), 0.0);
+		let v = Vector::new(vec![3.0, 4.0]).unwrap();
+		assert_eq!(v.norm(), 5.0);
+		let unit = v.normalized().unwrap();
+		assert!((unit.norm() - 1.0).abs() < 1e-15);
+		assert!((unit.dot(&v).unwrap() - 5.0).abs() < 1e-15);
+		assert!(v.cross(&v).is_err());
+		assert!(v.dot(&x).is_err());
+		assert!(Vector::zeros(2).unwrap().normalized().is_err());
+		let a = Matrix::new(2, 2, vec![1.0, 2.0, 3.0, 4.0]).unwrap();
+		assert_eq!(Vector::from_row(&a, 1).unwrap().get_values(), &[2.0, 4.0]);
+		assert_eq!(
+			Vector::from_column(&a, 1).unwrap().to_row().get_data(),
+			vec![3.0, 4.0]
+		);
+		assert_eq!(
+			a.multiplied_by_vector(&v).unwrap().to_column(),
+			a.multiplied_by_matrix(&v.to_column()).unwrap()
+		);
+		assert!(Vector::from_row(&a, 2).is_err());
+	}
+
+	#[test]
+	fn test_outer_product() {
+		let u = Vector::new(vec![1.0, 2.0, 3.0]).unwrap();
+		let v = Vector::new(vec![4.0, 5.0]).unwrap();
+		let outer = u.outer(&v);
+		assert_eq!(
+			outer,
+			u.to_column().multiplied_by_matrix(&v.to_row()).unwrap()
+		);
+		assert_eq!(outer.get_size(), (3, 2));
+		assert_eq!(u.to_row().outer_product(&v.to_column()).unwrap(), outer);
+		assert!(outer.outer_product(&outer).is_err());
+	}
+
+	#[test]
+	fn test_scale_and_normalize() {
+		// [3, 1; 4, 3]
+		let a = Matrix::new(2, 2, vec![3.0, 4.0, 1.0, 3.0]).unwrap();
+		let v = Vector::new(vec![2.0, -1.0]).unwrap();
+		assert_eq!(
+			a.scale_rows(&v).unwrap().get_data(),
+			vec![6.0, -4.0, 2.0, -3.0]
+		);
+		assert_eq!(
+			a.scale_cols(&v).unwrap().get_data(),
+			vec![6.0, 8.0, -1.0, -3.0]
+		);
+		let columns = a.normalize_cols(Normalization::UnitNorm).unwrap();
+		assert!((Vector::from_column(&columns, 0).unwrap().get_values()[0] - 0.6).abs() < 1e-15);
+		let stochastic = a.normalize_rows(Normalization::SumToOne).unwrap();
+		assert!(stochastic
+			.sum_rows()
+			.approx_eq(&Matrix::new(2, 1, vec![1.0, 1.0]).unwrap(), 1e-15));
+		assert!(a.scale_rows(&Vector::zeros(3).unwrap()).is_err());
+		let zero_row = Matrix::new(2, 2, vec![1.0, 0.0, 1.0, 0.0]).unwrap();
+		assert!(zero_row.normalize_rows(Normalization::UnitNorm).is_err());
+	}
+}
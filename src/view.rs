@@ -0,0 +1,136 @@
+use super::error::MathMatrixError;
+use super::error::MathMatrixErrorKind::*;
+use super::matrix::Matrix;
+
+/* Read-only column-major view over borrowed memory. Element (i, j) lives at
+data[i + j * col_stride], so col_stride >= rows allows viewing a sub-block of
+a larger buffer.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatrixView<'a> {
+	data: &'a [f64],
+	rows: usize,
+	cols: usize,
+	col_stride: usize,
+}
+
+impl<'a> MatrixView<'a> {
+	pub fn new(
+		data: &'a [f64],
+		rows: usize,
+		cols: usize,
+		col_stride: usize,
+	) -> Result<Self, MathMatrixError> {
+		if rows * cols == 0 {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"Rows and columns must be lager than 0".to_owned(),
+			));
+		}
+		if col_stride < rows {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				format!("Column stride {} < rows {}", col_stride, rows),
+			));
+		}
+		let required = (cols - 1) * col_stride + rows;
+		if data.len() < required {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				format!("Size of data < required: {} < {}", data.len(), required),
+			));
+		}
+		Ok(Self {
+			data,
+			rows,
+			cols,
+			col_stride,
+		})
+	}
+
+	/// View over memory owned elsewhere, e.g. a C host application or mapped
+	/// GPU memory. The pointer is checked for null and the sizes are checked as
+	/// in `new`.
+	///
+	/// # Safety
+	/// For the whole lifetime 'a, ptr must point to at least
+	/// (cols - 1) * col_stride + rows initialized f64 values that are readable
+	/// from the CPU, properly aligned, and not written to by anyone else. Device
+	/// memory that is not mapped into the host address space does not qualify.
+	pub unsafe fn from_raw_parts(
+		ptr: *const f64,
+		rows: usize,
+		cols: usize,
+		col_stride: usize,
+	) -> Result<Self, MathMatrixError> {
+		if ptr.is_null() {
+			return Err(MathMatrixError::new(
+				FailedToInitialize,
+				"Null data pointer".to_owned(),
+			));
+		}
+		if rows * cols == 0 || col_stride < rows {
+			return Self::new(&[], rows, cols, col_stride);
+		}
+		let len = (cols - 1) * col_stride + rows;
+		return Self::new(std::slice::from_raw_parts(ptr, len), rows, cols, col_stride);
+	}
+
+	pub fn get_size(&self) -> (usize, usize) {
+		return (self.rows, self.cols);
+	}
+
+	pub fn get_col_stride(&self) -> usize {
+		return self.col_stride;
+	}
+
+	pub fn get_value(&self, row: usize, col: usize) -> Result<f64, MathMatrixError> {
+		if row >= self.rows {
+			return Err(MathMatrixError::new(
+				OutOfBoundary,
+				format!("Row {} >= {}", row, self.rows),
+			));
+		}
+		if col >= self.cols {
+			return Err(MathMatrixError::new(
+				OutOfBoundary,
+				format!("Column {} >= {}", col, self.cols),
+			));
+		}
+		return Ok(self.data[col * self.col_stride + row]);
+	}
+
+	pub fn to_matrix(&self) -> Matrix {
+		let mut data = Vec::with_capacity(self.rows * self.cols);
+		for j in 0..self.cols {
+			let start = j * self.col_stride;
+			data.extend_from_slice(&self.data[start..start + self.rows]);
+		}
+		return Matrix::new(self.rows, self.cols, data).unwrap();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_view_with_stride() {
+		// View the bottom 2x2 block of a 3x2 matrix
+		let data = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+		let view = MatrixView::new(&data[1..], 2, 2, 3).unwrap();
+		assert_eq!(view.get_value(0, 1).unwrap(), 5.0);
+		assert_eq!(view.to_matrix().get_data(), vec![2.0, 3.0, 5.0, 6.0]);
+		assert!(view.get_value(2, 0).is_err());
+		assert!(MatrixView::new(&data, 2, 4, 2).is_err());
+	}
+
+	#[test]
+	fn test_view_from_raw_parts() {
+		let data = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+		let view = unsafe { MatrixView::from_raw_parts(data.as_ptr().add(1), 2, 2, 3) }.unwrap();
+		assert_eq!(view, MatrixView::new(&data[1..], 2, 2, 3).unwrap());
+		assert!(unsafe { MatrixView::from_raw_parts(std::ptr::null(), 2, 2, 2) }.is_err());
+		assert!(unsafe { MatrixView::from_raw_parts(data.as_ptr(), 3, 2, 2) }.is_err());
+	}
+}
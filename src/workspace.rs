@@ -0,0 +1,63 @@
+/* Pool of scratch buffers that algorithms borrow instead of allocating
+temporaries on every call. Reusing one Workspace across calls in a tight
+loop means buffers only grow until they fit the largest problem seen.
+*/
+#[derive(Debug, Default)]
+pub struct Workspace {
+	buffers: Vec<Vec<f64>>,
+}
+
+impl Workspace {
+	pub fn new() -> Self {
+		return Self::default();
+	}
+
+	// Zeroed buffer of `len` elements, reusing pooled capacity when possible
+	pub fn take(&mut self, len: usize) -> Vec<f64> {
+		let index = self
+			.buffers
+			.iter()
+			.position(|buffer| buffer.capacity() >= len)
+			.or(if self.buffers.is_empty() {
+				None
+			} else {
+				Some(self.buffers.len() - 1)
+			});
+		let mut buffer = match index {
+			Some(index) => self.buffers.swap_remove(index),
+			None => Vec::new(),
+		};
+		buffer.clear();
+		buffer.resize(len, 0.0);
+		return buffer;
+	}
+
+	// Return a buffer to the pool, e.g. from `Matrix::into_vec`
+	pub fn recycle(&mut self, buffer: Vec<f64>) {
+		self.buffers.push(buffer);
+	}
+
+	// Number of f64 the pooled buffers can hold without reallocating
+	pub fn capacity(&self) -> usize {
+		return self.buffers.iter().map(|buffer| buffer.capacity()).sum();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_take_reuses_capacity() {
+		let mut workspace = Workspace::new();
+		let mut buffer = workspace.take(16);
+		buffer[3] = 1.0;
+		let ptr = buffer.as_ptr();
+		workspace.recycle(buffer);
+		assert!(workspace.capacity() >= 16);
+		let buffer = workspace.take(8);
+		assert_eq!(buffer.as_ptr(), ptr);
+		assert_eq!(buffer, vec![0.0; 8]);
+		assert_eq!(workspace.capacity(), 0);
+	}
+}
@@ -0,0 +1,177 @@
+/* Golden-file regression tests: factorizations and solutions of a small
+gallery of matrices are compared against the values stored in
+tests/golden/<matrix>.txt, entry by entry with a per-quantity relative
+tolerance. Only quantities that are unique are stored (e.g. |R| instead of
+the signs chosen for Q and R, singular values instead of vectors).
+
+After an intended change of results, regenerate the files with
+	UPDATE_GOLDEN=1 cargo test --test golden
+and review the diff.
+
+File format, repeated per quantity:
+	<name> <rows> <cols> <tolerance>
+	<values, row by row, whitespace separated>
+*/
+#![allow(clippy::needless_return, clippy::needless_range_loop)]
+
+use math::matrix::Matrix;
+use math::multigrid::laplacian_1d;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+fn gallery() -> Vec<(&'static str, Matrix)> {
+	let hilbert = Matrix::new(
+		4,
+		4,
+		(0..16)
+			.map(|k| 1.0 / ((k / 4 + k % 4 + 1) as f64))
+			.collect(),
+	);
+	let pascal = (0..16)
+		.map(|k| {
+			let (i, j) = (k % 4, k / 4);
+			// binomial(i + j, i)
+			return (1..=i).fold(1.0, |acc, m| acc * (j + m) as f64 / m as f64);
+		})
+		.collect();
+	let nonsymmetric = vec![
+		4.0, -2.0, 1.0, 0.5, 1.0, 3.0, -1.0, 2.0, 0.0, 1.0, 5.0, -3.0, 2.0, 0.0, 1.0, 6.0,
+	];
+	return vec![
+		("hilbert4", hilbert.unwrap()),
+		("pascal4", Matrix::new(4, 4, pascal).unwrap()),
+		("laplacian5", laplacian_1d(5).unwrap()),
+		("nonsymmetric4", Matrix::new(4, 4, nonsymmetric).unwrap()),
+	];
+}
+
+// (name, value, tolerance) for everything checked on one matrix
+fn quantities(mat: &Matrix) -> Vec<(&'static str, Matrix, f64)> {
+	let n = mat.get_size().0;
+	let mut out = Vec::new();
+	let (p, l, u) = mat.plu_decompose().unwrap();
+	out.push(("plu_p", p, 0.0));
+	out.push(("plu_l", l, 1e-10));
+	out.push(("plu_u", u, 1e-10));
+	let (_, r) = mat.qr().unwrap();
+	let abs_r = r.get_data().iter().map(|v| v.abs()).collect();
+	out.push(("qr_abs_r", Matrix::new(n, n, abs_r).unwrap(), 1e-10));
+	let b = Matrix::new(n, 1, (1..=n).map(|i| i as f64).collect()).unwrap();
+	out.push(("solve", mat.solve(&b).unwrap(), 1e-8));
+	out.push((
+		"determinant",
+		Matrix::new(1, 1, vec![mat.determinant().unwrap()]).unwrap(),
+		1e-10,
+	));
+	let (_, s, _) = mat.svd().unwrap();
+	let singular_values = (0..n).map(|i| s.get_value(i, i).unwrap()).collect();
+	out.push((
+		"singular_values",
+		Matrix::new(n, 1, singular_values).unwrap(),
+		1e-10,
+	));
+	if mat.is_symmetric(0.0) {
+		let (values, _) = mat.eigen_symmetric().unwrap();
+		out.push(("eigenvalues", Matrix::new(n, 1, values).unwrap(), 1e-10));
+		if let Ok(l) = mat.cholesky() {
+			out.push(("cholesky", l, 1e-10));
+		}
+	} else {
+		let (mut real, _) = mat.eigenvalues().unwrap();
+		real.sort_by(|a, b| a.partial_cmp(b).unwrap());
+		out.push(("eigenvalues_real", Matrix::new(n, 1, real).unwrap(), 1e-10));
+	}
+	return out;
+}
+
+fn path(name: &str) -> PathBuf {
+	return PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+		.join("tests/golden")
+		.join(format!("{}.txt", name));
+}
+
+fn render(quantities: &[(&'static str, Matrix, f64)]) -> String {
+	let mut out = String::new();
+	for (name, value, tolerance) in quantities {
+		let (rows, cols) = value.get_size();
+		writeln!(out, "{} {} {} {:e}", name, rows, cols, tolerance).unwrap();
+		for i in 0..rows {
+			let row: Vec<String> = (0..cols)
+				.map(|j| format!("{:e}", value.get_value(i, j).unwrap()))
+				.collect();
+			writeln!(out, "{}", row.join(" ")).unwrap();
+		}
+	}
+	return out;
+}
+
+// (name, rows, cols, tolerance, values) in file order
+fn parse(text: &str) -> Vec<(String, usize, usize, f64, Vec<f64>)> {
+	let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+	let mut out = Vec::new();
+	while let Some(header) = lines.next() {
+		let fields: Vec<&str> = header.split_whitespace().collect();
+		let (rows, cols): (usize, usize) = (fields[1].parse().unwrap(), fields[2].parse().unwrap());
+		let mut values = Vec::with_capacity(rows * cols);
+		for _ in 0..rows {
+			values.extend(
+				lines
+					.next()
+					.unwrap()
+					.split_whitespace()
+					.map(|v| v.parse::<f64>().unwrap()),
+			);
+		}
+		out.push((
+			fields[0].to_owned(),
+			rows,
+			cols,
+			fields[3].parse().unwrap(),
+			values,
+		));
+	}
+	return out;
+}
+
+#[test]
+fn test_golden_files() {
+	let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+	let mut failures = Vec::new();
+	for (matrix_name, mat) in gallery() {
+		let actual = quantities(&mat);
+		if update {
+			std::fs::write(path(matrix_name), render(&actual)).unwrap();
+			continue;
+		}
+		let text = std::fs::read_to_string(path(matrix_name))
+			.unwrap_or_else(|_| panic!("Missing golden file for {}", matrix_name));
+		let expected = parse(&text);
+		assert_eq!(
+			expected.iter().map(|e| e.0.as_str()).collect::<Vec<_>>(),
+			actual.iter().map(|a| a.0).collect::<Vec<_>>(),
+			"Quantities of {} changed",
+			matrix_name
+		);
+		for ((name, rows, cols, tolerance, values), (_, value, _)) in
+			expected.iter().zip(actual.iter())
+		{
+			assert_eq!((*rows, *cols), value.get_size(), "{}/{}", matrix_name, name);
+			for i in 0..*rows {
+				for j in 0..*cols {
+					let (want, got) = (values[i * cols + j], value.get_value(i, j).unwrap());
+					if (want - got).abs() > tolerance * want.abs().max(1.0) {
+						failures.push(format!(
+							"{}/{}[{}, {}]: expected {:e}, got {:e}",
+							matrix_name, name, i, j, want, got
+						));
+					}
+				}
+			}
+		}
+	}
+	assert!(
+		failures.is_empty(),
+		"Golden mismatches:\n{}",
+		failures.join("\n")
+	);
+}